@@ -0,0 +1,1661 @@
+// C header import
+use crate::parser::{
+    Block, Fun, Item, ItemKind, Name, Node, NodeKind, Param, Primitive, StructField, Type,
+    TypeKind, UnaryOp, Variant,
+};
+use crate::span::Span;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// C typedefs from the standard headers that map straight to a Zinc type. System headers
+/// define them through layers of platform specific macros, so they aren't resolved from the
+/// headers. Sizes are the ones of 64 bit Linux and macOS.
+const KNOWN_TYPEDEFS: &[(&str, Primitive)] = &[
+    ("time_t", Primitive::I64),
+    ("clock_t", Primitive::I64),
+    ("suseconds_t", Primitive::I64),
+    ("size_t", Primitive::U64),
+    ("ssize_t", Primitive::I64),
+    ("ptrdiff_t", Primitive::I64),
+    ("intptr_t", Primitive::I64),
+    ("uintptr_t", Primitive::U64),
+    ("off_t", Primitive::I64),
+    ("pid_t", Primitive::I32),
+    ("uid_t", Primitive::U32),
+    ("gid_t", Primitive::U32),
+    ("wchar_t", Primitive::I32),
+    ("int8_t", Primitive::I8),
+    ("uint8_t", Primitive::U8),
+    ("int16_t", Primitive::I16),
+    ("uint16_t", Primitive::U16),
+    ("int32_t", Primitive::I32),
+    ("uint32_t", Primitive::U32),
+    ("int64_t", Primitive::I64),
+    ("uint64_t", Primitive::U64),
+];
+
+/// Words that change how a declaration is stored or linked, but not its type.
+const QUALIFIERS: &[&str] = &[
+    "const",
+    "volatile",
+    "restrict",
+    "__restrict",
+    "__restrict__",
+    "extern",
+    "static",
+    "inline",
+    "__inline",
+    "__inline__",
+    "register",
+    "auto",
+    "_Noreturn",
+    "__extension__",
+    "_Nullable",
+    "_Nonnull",
+];
+
+/// Compiler extensions followed by a parenthesized argument that doesn't matter for the
+/// declaration, i.e. `__attribute__ ((__nothrow__))`.
+const EXTENSIONS: &[&str] = &["__attribute__", "__attribute", "__asm__", "__asm", "asm"];
+
+/// The keywords that make up arithmetic types, i.e. `unsigned long int`.
+const TYPE_WORDS: &[&str] = &[
+    "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool",
+    "bool",
+];
+
+/// Binding power of the unary operators in preprocessor expressions.
+const UNARY_POWER: u8 = 12;
+
+/// # System Include Paths
+///
+/// The directories `<header.h>` is looked for in, after the ones passed with `-I`.
+pub fn system_include_paths() -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("/usr/local/include"),
+        PathBuf::from(format!("/usr/include/{}-linux-gnu", std::env::consts::ARCH)),
+        PathBuf::from("/usr/include"),
+    ]
+}
+
+/// # C Type
+///
+/// A type as written in a C declaration.
+#[derive(PartialEq, Debug, Clone)]
+pub enum CType {
+    Void,
+    Char,                 // Plain `char`. A `char *` is a string
+    Primitive(Primitive), // Every other arithmetic type
+    Typedef(String),
+    Tag(String), // `struct tm`, `enum e` or `union u`. Empty for a definition without a tag
+    Pointer(Box<CType>),
+    Function, // What a function pointer points to
+}
+
+/// The parameters of a C function, with their names if the header gives them.
+type CParams = Vec<(Option<String>, CType)>;
+
+/// # C Declaration
+///
+/// A declaration from a C header that can be imported.
+#[derive(PartialEq, Debug, Clone)]
+pub enum CDecl {
+    Function {
+        name: String,
+        params: CParams,
+        return_type: CType,
+        is_variadic: bool, // `int printf(const char *format, ...)`
+    },
+    Typedef {
+        name: String,
+        ty: CType,
+    },
+    Struct {
+        name: String,
+        fields: Vec<(String, CType)>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<(String, i128)>,
+    },
+    Constant {
+        name: String,
+        value: i128, // `#define` integer constants, and the values of enums without a name
+    },
+}
+
+impl CDecl {
+    /// # Name
+    ///
+    /// Returns the name the declaration is imported by.
+    pub fn name(&self) -> &str {
+        match self {
+            CDecl::Function { name, .. }
+            | CDecl::Typedef { name, .. }
+            | CDecl::Struct { name, .. }
+            | CDecl::Enum { name, .. }
+            | CDecl::Constant { name, .. } => name,
+        }
+    }
+}
+
+/// # Header
+///
+/// The declarations of a C header and the headers it includes.
+///
+/// Only the common subset of C that headers are written in is understood: function
+/// prototypes, typedefs, structs, enums and `#define` integer constants. Declarations that
+/// can't be parsed, variables, unions and `inline` function definitions are skipped. A struct
+/// with a field that can't be parsed is skipped whole. The preprocessor follows `#include`,
+/// `#if`/`#ifdef` and expands macros without arguments.
+#[derive(Debug)]
+pub struct Header {
+    pub decls: Vec<CDecl>,
+}
+
+impl Header {
+    /// # Load
+    ///
+    /// Finds a header in the include paths, then preprocesses and parses it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The header, i.e. `time.h` for `<time.h>`.
+    /// * `include_paths` - The directories to look for it and its includes in, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Header>` - `None` if the header can't be found.
+    pub fn load(name: &str, include_paths: &[PathBuf]) -> Option<Header> {
+        let path: PathBuf = include_paths
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())?;
+        let mut preprocessor: Preprocessor = Preprocessor {
+            include_paths,
+            macros: HashMap::new(),
+            order: Vec::new(),
+            included: HashSet::new(),
+            tokens: Vec::new(),
+        };
+        preprocessor.include_file(&path);
+        let mut parser: DeclParser = DeclParser {
+            tokens: &preprocessor.tokens,
+            pos: 0,
+            decls: Vec::new(),
+            anonymous: None,
+        };
+        parser.parse();
+        let mut decls: Vec<CDecl> = parser.decls;
+        decls.extend(preprocessor.constants());
+        Some(Header { decls })
+    }
+
+    /// # Decl
+    ///
+    /// Finds a declaration by name.
+    pub fn decl(&self, name: &str) -> Option<&CDecl> {
+        self.decls.iter().find(|decl| decl.name() == name)
+    }
+
+    /// # Exported
+    ///
+    /// Returns the declarations a glob import brings in: every named declaration, except
+    /// those whose name starts with `_`, which C reserves for the implementation.
+    pub fn exported(&self) -> Vec<&CDecl> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        self.decls
+            .iter()
+            .filter(|decl| {
+                let name: &str = decl.name();
+                !name.is_empty() && !name.starts_with('_') && seen.insert(name)
+            })
+            .collect()
+    }
+
+    /// # Extern Item
+    ///
+    /// Turns a declaration into the Zinc item it is imported as. Functions become `Fun`s
+    /// marked `is_extern` with an empty body, typedefs become type aliases and `#define`s
+    /// become constants.
+    ///
+    /// # Arguments
+    ///
+    /// * `decl` - A declaration of this header.
+    /// * `span` - Where the declaration is imported, used for every span in the item.
+    pub fn extern_item(&self, decl: &CDecl, span: Span) -> Item {
+        let name = |text: &str| Name {
+            text: text.to_string(),
+            span,
+        };
+        let kind: ItemKind = match decl {
+            CDecl::Function {
+                name: fun_name,
+                params,
+                return_type,
+                is_variadic,
+            } => ItemKind::Fun(Fun {
+                name: name(fun_name),
+                params: params
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (param_name, ty))| Param {
+                        name: match param_name {
+                            Some(param_name) => name(param_name),
+                            None => name(&format!("arg{}", index)),
+                        },
+                        ty: self.zinc_type(ty, span),
+                    })
+                    .collect(),
+                return_type: match return_type {
+                    CType::Void => None,
+                    ty => Some(self.zinc_type(ty, span)),
+                },
+                is_strict_return: false,
+                is_comptime: false,
+                is_extern: true,
+                is_variadic: *is_variadic,
+                body: Block {
+                    stmts: Vec::new(),
+                    span,
+                },
+            }),
+            CDecl::Typedef {
+                name: alias_name,
+                ty,
+            } => ItemKind::Alias {
+                name: name(alias_name),
+                ty: match known_typedef(alias_name) {
+                    Some(primitive) => Type {
+                        kind: TypeKind::Primitive(primitive),
+                        span,
+                    },
+                    None => self.zinc_type(ty, span),
+                },
+            },
+            CDecl::Struct {
+                name: struct_name,
+                fields,
+            } => ItemKind::Struct {
+                name: name(struct_name),
+                fields: fields
+                    .iter()
+                    .map(|(field_name, ty)| StructField {
+                        docs: Vec::new(),
+                        name: name(field_name),
+                        ty: self.zinc_type(ty, span),
+                        default: None,
+                        span,
+                    })
+                    .collect(),
+            },
+            CDecl::Enum {
+                name: enum_name,
+                variants,
+            } => ItemKind::Enum {
+                name: name(enum_name),
+                variants: variants
+                    .iter()
+                    .map(|(variant_name, value)| Variant {
+                        docs: Vec::new(),
+                        name: name(variant_name),
+                        value: Some(int_node(*value, span)),
+                        span,
+                    })
+                    .collect(),
+                is_error: false,
+            },
+            CDecl::Constant {
+                name: const_name,
+                value,
+            } => ItemKind::Const {
+                name: name(const_name),
+                ty: Type {
+                    kind: TypeKind::Primitive(if i32::try_from(*value).is_ok() {
+                        Primitive::I32
+                    } else if i64::try_from(*value).is_ok() {
+                        Primitive::I64
+                    } else {
+                        Primitive::U64
+                    }),
+                    span,
+                },
+                value: int_node(*value, span),
+            },
+        };
+        Item {
+            kind,
+            docs: Vec::new(),
+            span,
+        }
+    }
+
+    /// # Zinc Type
+    ///
+    /// Maps a C type to the Zinc type it is imported as. `char *` is a `string`, other
+    /// pointers are `*T` and function pointers are `*void`. Typedefs are resolved to the type
+    /// they name, except for typedefs of structs, which keep their name.
+    pub fn zinc_type(&self, ty: &CType, span: Span) -> Type {
+        self.zinc_type_with(ty, span, 0)
+    }
+
+    /// # Zinc Type With
+    ///
+    /// [`Header::zinc_type`], giving up on resolving typedefs `depth` typedefs deep.
+    fn zinc_type_with(&self, ty: &CType, span: Span, depth: usize) -> Type {
+        let named = |name: &str| TypeKind::Named {
+            path: vec![name.to_string()],
+            args: Vec::new(),
+        };
+        let kind: TypeKind = match ty {
+            CType::Void => TypeKind::Primitive(Primitive::Void),
+            CType::Char => TypeKind::Primitive(Primitive::I8),
+            CType::Primitive(primitive) => TypeKind::Primitive(*primitive),
+            CType::Pointer(inner) => match inner.as_ref() {
+                CType::Char => TypeKind::Primitive(Primitive::String),
+                CType::Function => return self.zinc_type_with(inner, span, depth),
+                inner => TypeKind::Pointer(Box::new(self.zinc_type_with(inner, span, depth))),
+            },
+            CType::Function => TypeKind::Pointer(Box::new(Type {
+                kind: TypeKind::Primitive(Primitive::Void),
+                span,
+            })),
+            CType::Tag(name) => named(name),
+            CType::Typedef(name) => match known_typedef(name) {
+                Some(primitive) => TypeKind::Primitive(primitive),
+                None => match self.decls.iter().find(
+                    |decl| matches!(decl, CDecl::Typedef { name: found, .. } if found == name),
+                ) {
+                    Some(CDecl::Typedef { ty: target, .. })
+                        if depth < 16 && !matches!(target, CType::Tag(_)) =>
+                    {
+                        return self.zinc_type_with(target, span, depth + 1);
+                    }
+                    _ => named(name),
+                },
+            },
+        };
+        Type { kind, span }
+    }
+}
+
+/// # Known Typedef
+///
+/// Returns the Zinc type of one of the [`KNOWN_TYPEDEFS`].
+fn known_typedef(name: &str) -> Option<Primitive> {
+    KNOWN_TYPEDEFS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, primitive)| *primitive)
+}
+
+/// # Int Node
+///
+/// Returns an integer literal expression, negated if the value is negative.
+fn int_node(value: i128, span: Span) -> Node {
+    let literal: Node = Node {
+        kind: NodeKind::Int(value.unsigned_abs(), None),
+        span,
+    };
+    if value < 0 {
+        Node {
+            kind: NodeKind::Unary {
+                op: UnaryOp::Negate,
+                operand: Box::new(literal),
+            },
+            span,
+        }
+    } else {
+        literal
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum CToken {
+    Ident(String),
+    Int(i128),
+    Punct(String),
+    Literal, // A string, character or float literal
+}
+
+impl CToken {
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self, CToken::Punct(found) if found == punct)
+    }
+
+    fn is_ident(&self, ident: &str) -> bool {
+        matches!(self, CToken::Ident(found) if found == ident)
+    }
+}
+
+/// # Lex
+///
+/// Splits a line of C, without comments, into tokens.
+fn lex(text: &str) -> Vec<CToken> {
+    const PUNCTS: &[&str] = &[
+        "...", "<<=", ">>=", "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "##", "++", "--",
+    ];
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<CToken> = Vec::new();
+    let mut i: usize = 0;
+    while i < chars.len() {
+        let c: char = chars[i];
+        let start: usize = i;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(CToken::Ident(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric()
+                    || chars[i] == '.'
+                    || (matches!(chars[i], '+' | '-') && matches!(chars[i - 1], 'e' | 'E')))
+            {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            tokens.push(parse_int(&number).map_or(CToken::Literal, CToken::Int));
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+            tokens.push(CToken::Literal);
+        } else {
+            let rest: String = chars[i..(i + 3).min(chars.len())].iter().collect();
+            let punct: String = PUNCTS
+                .iter()
+                .find(|punct| rest.starts_with(*punct))
+                .map_or(c.to_string(), |punct| punct.to_string());
+            i += punct.chars().count();
+            tokens.push(CToken::Punct(punct));
+        }
+    }
+    tokens
+}
+
+/// # Parse Int
+///
+/// Parses a C integer literal: decimal, hex or octal, with `u` and `l` suffixes.
+fn parse_int(text: &str) -> Option<i128> {
+    let digits: &str = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        i128::from_str_radix(hex, 16).ok()
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i128::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+/// # Strip Comments
+///
+/// Replaces every comment with a space, leaving string and character literals alone.
+fn strip_comments(src: &str) -> String {
+    let mut out: String = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        if let Some(open) = quote {
+            out.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    out.push(escaped);
+                }
+            } else if c == open || c == '\n' {
+                quote = None;
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|next| *next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous: char = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                out.push(' ');
+            }
+            ('"' | '\'', _) => {
+                quote = Some(c);
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// # Evaluate
+///
+/// Evaluates an integer constant expression, as used in `#if`, `#define` and enum values.
+///
+/// # Arguments
+///
+/// * `tokens` - The expression, with macros expanded.
+/// * `lookup` - The value of a name, or `None` if the expression isn't a constant.
+fn evaluate(tokens: &[CToken], lookup: &dyn Fn(&str) -> Option<i128>) -> Option<i128> {
+    let mut pos: usize = 0;
+    let value: i128 = evaluate_with(tokens, &mut pos, 0, lookup)?;
+    (pos == tokens.len()).then_some(value)
+}
+
+/// # Evaluate With
+///
+/// Evaluates the part of an expression starting at `pos` whose operators bind at least as
+/// tightly as `min_power`.
+fn evaluate_with(
+    tokens: &[CToken],
+    pos: &mut usize,
+    min_power: u8,
+    lookup: &dyn Fn(&str) -> Option<i128>,
+) -> Option<i128> {
+    let mut left: i128 = match tokens.get(*pos)? {
+        CToken::Int(value) => {
+            *pos += 1;
+            *value
+        }
+        CToken::Ident(name) => {
+            *pos += 1;
+            lookup(name)?
+        }
+        CToken::Punct(open) if open == "(" => {
+            *pos += 1;
+            let value: i128 = evaluate_with(tokens, pos, 0, lookup)?;
+            if !tokens.get(*pos)?.is_punct(")") {
+                return None;
+            }
+            *pos += 1;
+            value
+        }
+        CToken::Punct(op) => {
+            let op: String = op.clone();
+            *pos += 1;
+            let value: i128 = evaluate_with(tokens, pos, UNARY_POWER, lookup)?;
+            match op.as_str() {
+                "-" => value.checked_neg()?,
+                "+" => value,
+                "!" => (value == 0) as i128,
+                "~" => !value,
+                _ => return None,
+            }
+        }
+        CToken::Literal => return None,
+    };
+    while let Some(CToken::Punct(op)) = tokens.get(*pos) {
+        if op == "?" {
+            if min_power > 1 {
+                break;
+            }
+            *pos += 1;
+            let then_value: i128 = evaluate_with(tokens, pos, 0, lookup)?;
+            if !tokens.get(*pos)?.is_punct(":") {
+                return None;
+            }
+            *pos += 1;
+            let else_value: i128 = evaluate_with(tokens, pos, 1, lookup)?;
+            left = if left != 0 { then_value } else { else_value };
+            continue;
+        }
+        let power: u8 = match op.as_str() {
+            "||" => 2,
+            "&&" => 3,
+            "|" => 4,
+            "^" => 5,
+            "&" => 6,
+            "==" | "!=" => 7,
+            "<" | ">" | "<=" | ">=" => 8,
+            "<<" | ">>" => 9,
+            "+" | "-" => 10,
+            "*" | "/" | "%" => 11,
+            _ => break,
+        };
+        if power < min_power {
+            break;
+        }
+        let op: String = op.clone();
+        *pos += 1;
+        let right: i128 = evaluate_with(tokens, pos, power + 1, lookup)?;
+        left = match op.as_str() {
+            "||" => (left != 0 || right != 0) as i128,
+            "&&" => (left != 0 && right != 0) as i128,
+            "|" => left | right,
+            "^" => left ^ right,
+            "&" => left & right,
+            "==" => (left == right) as i128,
+            "!=" => (left != right) as i128,
+            "<" => (left < right) as i128,
+            ">" => (left > right) as i128,
+            "<=" => (left <= right) as i128,
+            ">=" => (left >= right) as i128,
+            "<<" => left.checked_shl(u32::try_from(right).ok()?)?,
+            ">>" => left.checked_shr(u32::try_from(right).ok()?)?,
+            "+" => left.checked_add(right)?,
+            "-" => left.checked_sub(right)?,
+            "*" => left.checked_mul(right)?,
+            "/" => left.checked_div(right)?,
+            _ => left.checked_rem(right)?,
+        };
+    }
+    Some(left)
+}
+
+enum Macro {
+    Object(Vec<CToken>), // `#define NAME body`
+    Function,            // `#define NAME(args) body`, which isn't expanded
+}
+
+/// An `#if`, `#ifdef` or `#ifndef` the preprocessor is inside of.
+struct Condition {
+    is_parent_active: bool, // Whether the lines around the `#if` are kept
+    is_active: bool,        // Whether the lines of the current branch are kept
+    is_taken: bool,         // Whether one of the branches so far was kept
+}
+
+/// # Preprocessor
+///
+/// Runs the C preprocessor over a header and the headers it includes, collecting the tokens
+/// of the lines it keeps.
+struct Preprocessor<'p> {
+    include_paths: &'p [PathBuf],
+    macros: HashMap<String, Macro>,
+    order: Vec<String>, // The defined macros, in the order they were defined
+    included: HashSet<PathBuf>,
+    tokens: Vec<CToken>,
+}
+
+impl Preprocessor<'_> {
+    /// # Include File
+    ///
+    /// Preprocesses a file, unless it has been included already. Headers are assumed to have
+    /// include guards or `#pragma once`.
+    fn include_file(&mut self, path: &Path) {
+        let Ok(canonical) = fs::canonicalize(path) else {
+            return;
+        };
+        if !self.included.insert(canonical) {
+            return;
+        }
+        let Ok(src) = fs::read_to_string(path) else {
+            return;
+        };
+        let src: String = strip_comments(&src.replace("\\\r\n", "").replace("\\\n", ""));
+        let mut conditions: Vec<Condition> = Vec::new();
+        for line in src.lines() {
+            let is_active: bool = conditions
+                .last()
+                .is_none_or(|condition| condition.is_active);
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if is_active {
+                    let tokens: Vec<CToken> = self.expand(lex(line), &mut Vec::new());
+                    self.tokens.extend(tokens);
+                }
+                continue;
+            };
+            let directive: &str = directive.trim_start();
+            let (name, rest) = directive.split_at(ident_len(directive));
+            match name {
+                "ifdef" | "ifndef" => {
+                    let is_defined: bool = self.macros.contains_key(rest.trim());
+                    let is_kept: bool = is_active && is_defined == (name == "ifdef");
+                    conditions.push(Condition {
+                        is_parent_active: is_active,
+                        is_active: is_kept,
+                        is_taken: is_kept,
+                    });
+                }
+                "if" => {
+                    let is_kept: bool = is_active && self.condition(rest);
+                    conditions.push(Condition {
+                        is_parent_active: is_active,
+                        is_active: is_kept,
+                        is_taken: is_kept,
+                    });
+                }
+                "elif" => {
+                    if let Some(condition) = conditions.last_mut() {
+                        let is_kept: bool = condition.is_parent_active
+                            && !condition.is_taken
+                            && self.condition(rest);
+                        condition.is_active = is_kept;
+                        condition.is_taken |= is_kept;
+                    }
+                }
+                "else" => {
+                    if let Some(condition) = conditions.last_mut() {
+                        condition.is_active = condition.is_parent_active && !condition.is_taken;
+                        condition.is_taken = true;
+                    }
+                }
+                "endif" => {
+                    conditions.pop();
+                }
+                _ if !is_active => {}
+                "define" => self.define(rest),
+                "undef" => {
+                    self.macros.remove(rest.trim());
+                }
+                "include" => self.include(rest, path),
+                _ => {}
+            }
+        }
+    }
+
+    /// # Define
+    ///
+    /// Handles `#define NAME body` and `#define NAME(args) body`.
+    fn define(&mut self, rest: &str) {
+        let rest: &str = rest.trim_start();
+        let (name, body) = rest.split_at(ident_len(rest));
+        if name.is_empty() {
+            return;
+        }
+        let defined: Macro = if body.starts_with('(') {
+            Macro::Function
+        } else {
+            Macro::Object(lex(body))
+        };
+        self.macros.insert(name.to_string(), defined);
+        self.order.push(name.to_string());
+    }
+
+    /// # Include
+    ///
+    /// Handles `#include <header.h>` and `#include "header.h"`. A quoted header is looked for
+    /// next to the including file first. Headers that can't be found are skipped, since system
+    /// headers include compiler specific ones.
+    fn include(&mut self, rest: &str, current: &Path) {
+        let rest: &str = rest.trim();
+        let (name, is_quoted) = if let Some(quoted) = rest.strip_prefix('"') {
+            (quoted.split('"').next().unwrap_or_default(), true)
+        } else if let Some(angled) = rest.strip_prefix('<') {
+            (angled.split('>').next().unwrap_or_default(), false)
+        } else {
+            return;
+        };
+        let mut dirs: Vec<&Path> = Vec::new();
+        if is_quoted && let Some(parent) = current.parent() {
+            dirs.push(parent);
+        }
+        dirs.extend(self.include_paths.iter().map(PathBuf::as_path));
+        if let Some(path) = dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+        {
+            self.include_file(&path);
+        }
+    }
+
+    /// # Condition
+    ///
+    /// Evaluates the expression of an `#if` or `#elif`. Names that aren't macros are `0`, and
+    /// expressions that can't be evaluated, i.e. ones that call macros with arguments, are
+    /// false.
+    fn condition(&self, rest: &str) -> bool {
+        let tokens: Vec<CToken> = lex(rest);
+        let mut resolved: Vec<CToken> = Vec::new();
+        let mut i: usize = 0;
+        while i < tokens.len() {
+            if !tokens[i].is_ident("defined") {
+                resolved.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+            let (name, len) = match (tokens.get(i + 1), tokens.get(i + 2)) {
+                (Some(CToken::Punct(open)), Some(CToken::Ident(name))) if open == "(" => (name, 4),
+                (Some(CToken::Ident(name)), _) => (name, 2),
+                _ => return false,
+            };
+            resolved.push(CToken::Int(self.macros.contains_key(name) as i128));
+            i += len;
+        }
+        let expanded: Vec<CToken> = self.expand(resolved, &mut Vec::new());
+        evaluate(&expanded, &|_| Some(0)).is_some_and(|value| value != 0)
+    }
+
+    /// # Expand
+    ///
+    /// Replaces the macros without arguments in `tokens` with their bodies. `hidden` holds the
+    /// macros being expanded, which aren't expanded again inside themselves.
+    fn expand(&self, tokens: Vec<CToken>, hidden: &mut Vec<String>) -> Vec<CToken> {
+        let mut expanded: Vec<CToken> = Vec::new();
+        for token in tokens {
+            if let CToken::Ident(name) = &token
+                && !hidden.contains(name)
+                && let Some(Macro::Object(body)) = self.macros.get(name)
+            {
+                hidden.push(name.clone());
+                expanded.extend(self.expand(body.clone(), hidden));
+                hidden.pop();
+            } else {
+                expanded.push(token);
+            }
+        }
+        expanded
+    }
+
+    /// # Constants
+    ///
+    /// Returns the macros that are still defined and expand to an integer constant.
+    fn constants(&self) -> Vec<CDecl> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        self.order
+            .iter()
+            .filter(|name| seen.insert(name.as_str()))
+            .filter_map(|name| match self.macros.get(name)? {
+                Macro::Object(body) if !body.is_empty() => {
+                    let expanded: Vec<CToken> = self.expand(body.clone(), &mut vec![name.clone()]);
+                    Some(CDecl::Constant {
+                        name: name.clone(),
+                        value: evaluate(&expanded, &|_| None)?,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// # Ident Len
+///
+/// Returns the length of the identifier `text` starts with.
+fn ident_len(text: &str) -> usize {
+    text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len())
+}
+
+/// A declarator: the name and the part of the type written around it, i.e. `*ctime(...)`.
+struct Declarator {
+    name: Option<String>,              // Parameters don't need a name
+    ty: CType,                         // For a function, the return type
+    function: Option<(CParams, bool)>, // The parameters, and whether it's variadic
+}
+
+/// # Decl Parser
+///
+/// Parses the preprocessed tokens of a header into declarations. Declarations it doesn't
+/// understand are skipped.
+struct DeclParser<'t> {
+    tokens: &'t [CToken],
+    pos: usize,
+    decls: Vec<CDecl>,
+    anonymous: Option<CDecl>, // The last struct or enum defined without a tag
+}
+
+impl DeclParser<'_> {
+    fn parse(&mut self) {
+        while self.pos < self.tokens.len() {
+            // `extern "C" {` and its closing brace
+            if self.is_ident(0, "extern")
+                && self.peek(1) == Some(&CToken::Literal)
+                && self.is_punct(2, "{")
+            {
+                self.pos += 3;
+                continue;
+            }
+            if self.is_punct(0, "}") || self.is_punct(0, ";") {
+                self.pos += 1;
+                continue;
+            }
+            let start: usize = self.pos;
+            if self.parse_decl().is_none() {
+                self.pos = start;
+                self.skip_decl();
+            }
+        }
+    }
+
+    /// # Parse Decl
+    ///
+    /// Parses a declaration up to and including its `;`.
+    fn parse_decl(&mut self) -> Option<()> {
+        self.anonymous = None;
+        // `__extension__ typedef ...`
+        while self.skip_qualifier() {}
+        let is_typedef: bool = self.eat_ident("typedef");
+        let base: CType = self.parse_specifiers()?;
+        if !self.eat_punct(";") {
+            loop {
+                let declarator: Declarator = self.parse_declarator(base.clone())?;
+                let name: String = declarator.name?;
+                match (is_typedef, declarator.function) {
+                    (true, None) if declarator.ty == CType::Tag(String::new()) => {
+                        match self.anonymous.take() {
+                            Some(CDecl::Struct { fields, .. }) => {
+                                self.decls.push(CDecl::Struct { name, fields })
+                            }
+                            Some(CDecl::Enum { variants, .. }) => {
+                                self.decls.push(CDecl::Enum { name, variants })
+                            }
+                            _ => {}
+                        }
+                    }
+                    (true, function) => self.decls.push(CDecl::Typedef {
+                        name,
+                        ty: match function {
+                            Some(_) => CType::Function,
+                            None => declarator.ty,
+                        },
+                    }),
+                    (false, Some((params, is_variadic))) => {
+                        // Attributes and `asm` labels after the parameters
+                        self.skip_to_end()?;
+                        if self.is_punct(0, "{") {
+                            // An `inline` definition, which there's nothing to link against for
+                            self.skip_balanced();
+                            return Some(());
+                        }
+                        self.decls.push(CDecl::Function {
+                            name,
+                            params,
+                            return_type: declarator.ty,
+                            is_variadic,
+                        });
+                    }
+                    // Variables aren't imported
+                    (false, None) => self.skip_to_end()?,
+                }
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+            self.expect_punct(";")?;
+        }
+        // The values of an enum without a name are constants
+        if let Some(CDecl::Enum { variants, .. }) = self.anonymous.take() {
+            self.decls.extend(
+                variants
+                    .into_iter()
+                    .map(|(name, value)| CDecl::Constant { name, value }),
+            );
+        }
+        Some(())
+    }
+
+    /// # Parse Specifiers
+    ///
+    /// Parses the type a declaration starts with, i.e. `const unsigned long` or `struct tm`,
+    /// skipping qualifiers.
+    fn parse_specifiers(&mut self) -> Option<CType> {
+        let mut words: Vec<String> = Vec::new();
+        let mut ty: Option<CType> = None;
+        loop {
+            if self.skip_qualifier() {
+                continue;
+            }
+            let Some(CToken::Ident(word)) = self.peek(0) else {
+                break;
+            };
+            if TYPE_WORDS.contains(&word.as_str()) && ty.is_none() {
+                words.push(word.clone());
+                self.pos += 1;
+            } else if ty.is_some() || !words.is_empty() {
+                break;
+            } else if matches!(word.as_str(), "struct" | "union" | "enum") {
+                ty = Some(self.parse_tag()?);
+            } else {
+                ty = Some(CType::Typedef(word.clone()));
+                self.pos += 1;
+            }
+        }
+        if words.is_empty() {
+            return ty;
+        }
+        let has = |word: &str| words.iter().any(|found| found == word);
+        let is_unsigned: bool = has("unsigned");
+        let pick = |signed: Primitive, unsigned: Primitive| {
+            CType::Primitive(if is_unsigned { unsigned } else { signed })
+        };
+        Some(if has("void") {
+            CType::Void
+        } else if has("_Bool") || has("bool") {
+            CType::Primitive(Primitive::Bool)
+        } else if has("float") {
+            CType::Primitive(Primitive::F32)
+        } else if has("double") {
+            CType::Primitive(Primitive::F64)
+        } else if has("char") {
+            if is_unsigned || has("signed") {
+                pick(Primitive::I8, Primitive::U8)
+            } else {
+                CType::Char
+            }
+        } else if has("short") {
+            pick(Primitive::I16, Primitive::U16)
+        } else if has("long") {
+            pick(Primitive::I64, Primitive::U64)
+        } else {
+            pick(Primitive::I32, Primitive::U32)
+        })
+    }
+
+    /// # Parse Tag
+    ///
+    /// Parses `struct name`, `enum name` or `union name`, with or without a body. Structs and
+    /// enums with a body are declared; unions can't be imported, so their body is skipped.
+    fn parse_tag(&mut self) -> Option<CType> {
+        let Some(CToken::Ident(keyword)) = self.peek(0).cloned() else {
+            return None;
+        };
+        self.pos += 1;
+        while self.skip_qualifier() {}
+        let tag: Option<String> = match self.peek(0) {
+            Some(CToken::Ident(tag)) => {
+                let tag: String = tag.clone();
+                self.pos += 1;
+                Some(tag)
+            }
+            _ => None,
+        };
+        if !self.is_punct(0, "{") {
+            return Some(CType::Tag(tag?));
+        }
+        let name: String = tag.clone().unwrap_or_default();
+        let decl: CDecl = match keyword.as_str() {
+            "struct" => CDecl::Struct {
+                name,
+                fields: self.parse_fields()?,
+            },
+            "enum" => CDecl::Enum {
+                name,
+                variants: self.parse_enumerators()?,
+            },
+            _ => {
+                self.skip_balanced();
+                return Some(CType::Tag(tag.unwrap_or_default()));
+            }
+        };
+        match tag {
+            Some(tag) => {
+                self.decls.push(decl);
+                Some(CType::Tag(tag))
+            }
+            None => {
+                self.anonymous = Some(decl);
+                Some(CType::Tag(String::new()))
+            }
+        }
+    }
+
+    /// # Parse Fields
+    ///
+    /// Parses the `{ ... }` body of a struct. A struct with a field that can't be parsed isn't
+    /// imported at all, since leaving the field out would give it the wrong layout.
+    fn parse_fields(&mut self) -> Option<Vec<(String, CType)>> {
+        self.expect_punct("{")?;
+        let mut fields: Vec<(String, CType)> = Vec::new();
+        while !self.eat_punct("}") {
+            self.peek(0)?;
+            self.parse_field(&mut fields)?;
+        }
+        Some(fields)
+    }
+
+    /// # Parse Field
+    ///
+    /// Parses a field declaration like `int tm_sec;` or `unsigned flags : 3, mode : 2;`.
+    fn parse_field(&mut self, fields: &mut Vec<(String, CType)>) -> Option<()> {
+        let base: CType = self.parse_specifiers()?;
+        if self.eat_punct(";") {
+            return Some(());
+        }
+        loop {
+            let declarator: Declarator = self.parse_declarator(base.clone())?;
+            if self.eat_punct(":") {
+                // The width of a bit field
+                while !self.is_punct(0, ",") && !self.is_punct(0, ";") {
+                    self.peek(0)?;
+                    self.pos += 1;
+                }
+            }
+            let ty: CType = match declarator.function {
+                Some(_) => CType::Pointer(Box::new(CType::Function)),
+                None => declarator.ty,
+            };
+            fields.push((declarator.name?, ty));
+            if !self.eat_punct(",") {
+                break;
+            }
+        }
+        self.expect_punct(";")
+    }
+
+    /// # Parse Enumerators
+    ///
+    /// Parses the `{ ... }` body of an enum. A value without an explicit one is one more than
+    /// the value before it.
+    fn parse_enumerators(&mut self) -> Option<Vec<(String, i128)>> {
+        self.expect_punct("{")?;
+        let mut variants: Vec<(String, i128)> = Vec::new();
+        let mut next: i128 = 0;
+        while !self.eat_punct("}") {
+            let Some(CToken::Ident(name)) = self.peek(0).cloned() else {
+                return None;
+            };
+            self.pos += 1;
+            let value: i128 = if self.eat_punct("=") {
+                let start: usize = self.pos;
+                let mut depth: usize = 0;
+                loop {
+                    match self.peek(0)? {
+                        CToken::Punct(close) if depth == 0 && (close == "," || close == "}") => {
+                            break;
+                        }
+                        CToken::Punct(open) if open == "(" => depth += 1,
+                        CToken::Punct(close) if close == ")" => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+                let lookup = |name: &str| {
+                    variants
+                        .iter()
+                        .find(|(found, _)| found == name)
+                        .map(|(_, value)| *value)
+                };
+                evaluate(&self.tokens[start..self.pos], &lookup)?
+            } else {
+                next
+            };
+            variants.push((name, value));
+            next = value + 1;
+            if !self.eat_punct(",") {
+                self.expect_punct("}")?;
+                break;
+            }
+        }
+        Some(variants)
+    }
+
+    /// # Parse Declarator
+    ///
+    /// Parses the part of a declaration after its type: pointers, the name, and a function's
+    /// parameters or an array's size. Function pointers like `void (*callback)(int)` are
+    /// parsed too, but their parameters are skipped.
+    fn parse_declarator(&mut self, base: CType) -> Option<Declarator> {
+        let mut ty: CType = base;
+        loop {
+            if self.eat_punct("*") {
+                ty = CType::Pointer(Box::new(ty));
+            } else if !self.skip_qualifier() {
+                break;
+            }
+        }
+        if self.is_punct(0, "(") && self.is_punct(1, "*") {
+            self.pos += 2;
+            while self.eat_punct("*") || self.skip_qualifier() {}
+            let name: Option<String> = self.take_ident();
+            self.expect_punct(")")?;
+            if self.is_punct(0, "(") {
+                self.skip_balanced();
+            }
+            return Some(Declarator {
+                name,
+                ty: CType::Pointer(Box::new(CType::Function)),
+                function: None,
+            });
+        }
+        let name: Option<String> = self.take_ident();
+        if self.is_punct(0, "(") {
+            let function: (CParams, bool) = self.parse_params()?;
+            return Some(Declarator {
+                name,
+                ty,
+                function: Some(function),
+            });
+        }
+        while self.is_punct(0, "[") {
+            self.skip_balanced();
+            ty = CType::Pointer(Box::new(ty));
+        }
+        Some(Declarator {
+            name,
+            ty,
+            function: None,
+        })
+    }
+
+    /// # Parse Params
+    ///
+    /// Parses a function's parameter list, including the parentheses.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(CParams, bool)>` - The parameters, and whether the
+    ///   function takes more arguments after them (`...`).
+    fn parse_params(&mut self) -> Option<(CParams, bool)> {
+        self.expect_punct("(")?;
+        if self.eat_punct(")") {
+            return Some((Vec::new(), false));
+        }
+        if self.is_ident(0, "void") && self.is_punct(1, ")") {
+            self.pos += 2;
+            return Some((Vec::new(), false));
+        }
+        let mut params: CParams = Vec::new();
+        loop {
+            if self.eat_punct("...") {
+                self.expect_punct(")")?;
+                return Some((params, true));
+            }
+            let base: CType = self.parse_specifiers()?;
+            let declarator: Declarator = self.parse_declarator(base)?;
+            let ty: CType = match declarator.function {
+                Some(_) => CType::Pointer(Box::new(CType::Function)),
+                None => declarator.ty,
+            };
+            params.push((declarator.name, ty));
+            if self.eat_punct(")") {
+                return Some((params, false));
+            }
+            self.expect_punct(",")?;
+        }
+    }
+
+    /// # Skip Qualifier
+    ///
+    /// Skips one of the [`QUALIFIERS`], or one of the [`EXTENSIONS`] and its argument.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether anything was skipped.
+    fn skip_qualifier(&mut self) -> bool {
+        let Some(CToken::Ident(word)) = self.peek(0) else {
+            return false;
+        };
+        if QUALIFIERS.contains(&word.as_str()) {
+            self.pos += 1;
+            true
+        } else if EXTENSIONS.contains(&word.as_str()) && self.is_punct(1, "(") {
+            self.pos += 1;
+            self.skip_balanced();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// # Skip To End
+    ///
+    /// Skips to the `,`, `;` or `{` that ends a declarator, i.e. past attributes after a
+    /// function's parameters or a variable's initializer.
+    fn skip_to_end(&mut self) -> Option<()> {
+        loop {
+            match self.peek(0)? {
+                CToken::Punct(end) if end == "," || end == ";" || end == "{" => return Some(()),
+                CToken::Punct(open) if open == "(" || open == "[" => self.skip_balanced(),
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// # Skip Decl
+    ///
+    /// Skips past a declaration that failed to parse: up to and including the next `;` outside
+    /// of brackets, or the closing brace of a function body.
+    fn skip_decl(&mut self) {
+        let mut depth: usize = 0;
+        let mut is_body: bool = false;
+        while let Some(token) = self.tokens.get(self.pos) {
+            let previous: Option<&CToken> = self.pos.checked_sub(1).map(|pos| &self.tokens[pos]);
+            self.pos += 1;
+            let CToken::Punct(punct) = token else {
+                continue;
+            };
+            match punct.as_str() {
+                "{" if depth == 0 => {
+                    is_body = previous.is_some_and(|previous| previous.is_punct(")"));
+                    depth += 1;
+                }
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" if depth == 0 => return,
+                ")" | "]" | "}" => {
+                    depth -= 1;
+                    if depth == 0 && is_body {
+                        return;
+                    }
+                }
+                ";" if depth == 0 => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// # Skip Balanced
+    ///
+    /// Skips from an opening bracket to just past its matching closing bracket.
+    fn skip_balanced(&mut self) {
+        let mut depth: usize = 0;
+        while let Some(token) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            match token {
+                CToken::Punct(open) if open == "(" || open == "[" || open == "{" => depth += 1,
+                CToken::Punct(close) if close == ")" || close == "]" || close == "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn take_ident(&mut self) -> Option<String> {
+        let Some(CToken::Ident(name)) = self.peek(0) else {
+            return None;
+        };
+        let name: String = name.clone();
+        self.pos += 1;
+        Some(name)
+    }
+
+    fn peek(&self, forward: usize) -> Option<&CToken> {
+        self.tokens.get(self.pos + forward)
+    }
+
+    fn is_punct(&self, forward: usize, punct: &str) -> bool {
+        self.peek(forward)
+            .is_some_and(|token| token.is_punct(punct))
+    }
+
+    fn is_ident(&self, forward: usize, ident: &str) -> bool {
+        self.peek(forward)
+            .is_some_and(|token| token.is_ident(ident))
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let is_match: bool = self.is_punct(0, punct);
+        if is_match {
+            self.pos += 1;
+        }
+        is_match
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        let is_match: bool = self.is_ident(0, ident);
+        if is_match {
+            self.pos += 1;
+        }
+        is_match
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Option<()> {
+        self.eat_punct(punct).then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files::TestDir;
+
+    /// Writes `files` to a new directory and loads the first one as a header.
+    fn header(test: &str, files: &[(&str, &str)]) -> Header {
+        let dir: TestDir = TestDir::new(&format!("c-header-{}", test), files);
+        Header::load(files[0].0, &[dir.path().to_path_buf()]).expect("the header should be found")
+    }
+
+    /// Returns the Zinc type a declaration's C type is imported as, i.e. `*tm`.
+    fn zinc(header: &Header, ty: &CType) -> String {
+        header.zinc_type(ty, Span::default()).to_string()
+    }
+
+    #[test]
+    fn parses_declarations() {
+        let header: Header = header(
+            "decls",
+            &[
+                (
+                    "clock.h",
+                    "#ifndef CLOCK_H\n\
+                     #define CLOCK_H\n\
+                     #include \"types.h\"\n\
+                     #define __THROW __attribute__ ((__nothrow__))\n\
+                     #define MAX_ALARMS 0x10\n\
+                     #define NEGATIVE (-(MAX_ALARMS << 1))\n\
+                     #define NOT_A_NUMBER \"text\"\n\
+                     /* The broken down time,\n   like `struct tm` */\n\
+                     struct tm {\n\
+                         int tm_sec, tm_min;\n\
+                         long int tm_gmtoff; // Seconds east of UTC\n\
+                         const char *tm_zone;\n\
+                         unsigned flags : 3;\n\
+                         void (*on_tick)(int);\n\
+                     };\n\
+                     typedef struct { short x; } point_t;\n\
+                     typedef enum { SLOW, FAST = 4, FASTEST } speed;\n\
+                     enum { ANON_A = 2, ANON_B };\n\
+                     extern \"C\" {\n\
+                     extern my_time_t time (my_time_t *__timer) __THROW;\n\
+                     extern char *ctime (const my_time_t *__restrict __timer) __THROW __wur;\n\
+                     int printf(const char *, ...);\n\
+                     void reset(void);\n\
+                     static inline int twice(int x) { return x * 2; }\n\
+                     extern int daylight;\n\
+                     struct tm *localtime(const my_time_t *);\n\
+                     }\n\
+                     #endif\n",
+                ),
+                (
+                    "types.h",
+                    "typedef long my_time_t;\n#define CLOCK_H_TYPES 1\n",
+                ),
+            ],
+        );
+        let names: Vec<&str> = header.decls.iter().map(CDecl::name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "my_time_t",
+                "tm",
+                "point_t",
+                "speed",
+                "ANON_A",
+                "ANON_B",
+                "time",
+                "ctime",
+                "printf",
+                "reset",
+                "localtime",
+                "CLOCK_H_TYPES",
+                "MAX_ALARMS",
+                "NEGATIVE",
+            ]
+        );
+        let Some(CDecl::Struct { fields, .. }) = header.decl("tm") else {
+            panic!("expected a struct");
+        };
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(name, ty)| format!("{}: {}", name, zinc(&header, ty)))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "tm_sec: i32",
+                "tm_min: i32",
+                "tm_gmtoff: i64",
+                "tm_zone: string",
+                "flags: u32",
+                "on_tick: *void",
+            ]
+        );
+        assert_eq!(
+            header.decl("speed"),
+            Some(&CDecl::Enum {
+                name: "speed".to_string(),
+                variants: vec![
+                    ("SLOW".to_string(), 0),
+                    ("FAST".to_string(), 4),
+                    ("FASTEST".to_string(), 5)
+                ],
+            })
+        );
+        assert_eq!(
+            header.decl("ANON_B"),
+            Some(&CDecl::Constant {
+                name: "ANON_B".to_string(),
+                value: 3
+            })
+        );
+        assert_eq!(
+            header.decl("NEGATIVE"),
+            Some(&CDecl::Constant {
+                name: "NEGATIVE".to_string(),
+                value: -32
+            })
+        );
+        let Some(CDecl::Function {
+            params,
+            return_type,
+            ..
+        }) = header.decl("ctime")
+        else {
+            panic!("expected a function");
+        };
+        assert_eq!(
+            (zinc(&header, &params[0].1), zinc(&header, return_type)),
+            ("*i64".to_string(), "string".to_string())
+        );
+        assert!(matches!(
+            header.decl("printf"),
+            Some(CDecl::Function {
+                is_variadic: true,
+                ..
+            })
+        ));
+        let Some(CDecl::Function { return_type, .. }) = header.decl("localtime") else {
+            panic!("expected a function");
+        };
+        assert_eq!(zinc(&header, return_type), "*tm");
+    }
+
+    #[test]
+    fn skips_structs_with_unreadable_fields() {
+        let header: Header = header(
+            "fields",
+            &[(
+                "fields.h",
+                "struct packed { int a; int ALIGNED(8) b; int c; };\n\
+                 struct broken { int a ) ; ] int b; };\n\
+                 typedef struct { long x; CHUNK(2) y; } chunk_t;\n\
+                 struct ok { int a; };\n\
+                 int after(void);\n",
+            )],
+        );
+        let names: Vec<&str> = header.decls.iter().map(CDecl::name).collect();
+        assert_eq!(names, vec!["ok", "after"]);
+    }
+
+    #[test]
+    fn lexes_non_ascii_punctuation() {
+        assert_eq!(
+            lex("\u{b5}+x"),
+            vec![
+                CToken::Punct("\u{b5}".to_string()),
+                CToken::Punct("+".to_string()),
+                CToken::Ident("x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn preprocessor_conditions() {
+        let header: Header = header(
+            "conditions",
+            &[(
+                "conditions.h",
+                "#define VERSION 3\n\
+                 #define FEATURE\n\
+                 #if VERSION >= 3 && defined(FEATURE)\n\
+                 int new_api(void);\n\
+                 #elif VERSION == 2\n\
+                 int v2_api(void);\n\
+                 #else\n\
+                 int old_api(void);\n\
+                 #endif\n\
+                 #ifdef MISSING\n\
+                 int hidden(void);\n\
+                 #  if 1\n\
+                 int nested_hidden(void);\n\
+                 #  endif\n\
+                 #else\n\
+                 int shown(void);\n\
+                 #endif\n\
+                 #if __GNUC_PREREQ (4, 1) || UNKNOWN\n\
+                 int unknown_condition(void);\n\
+                 #endif\n\
+                 #undef VERSION\n",
+            )],
+        );
+        let names: Vec<&str> = header.decls.iter().map(CDecl::name).collect();
+        assert_eq!(names, vec!["new_api", "shown"]);
+    }
+
+    #[test]
+    fn extern_items() {
+        let header: Header = header(
+            "items",
+            &[(
+                "items.h",
+                "typedef long time_t;\n\
+                 typedef struct _IO_FILE FILE;\n\
+                 time_t time(time_t *);\n\
+                 int fclose(FILE *stream);\n\
+                 void _internal(void);\n\
+                 #define LIMIT -5\n",
+            )],
+        );
+        let exported: Vec<&str> = header.exported().into_iter().map(CDecl::name).collect();
+        assert_eq!(exported, vec!["time_t", "FILE", "time", "fclose", "LIMIT"]);
+
+        let item: Item = header.extern_item(header.decl("time").unwrap(), Span::default());
+        let ItemKind::Fun(fun) = &item.kind else {
+            panic!("expected a function");
+        };
+        assert!(fun.is_extern && fun.body.stmts.is_empty());
+        assert_eq!(
+            (
+                fun.params[0].name.text.as_str(),
+                fun.params[0].ty.to_string(),
+                fun.return_type.as_ref().map(Type::to_string)
+            ),
+            ("arg0", "*i64".to_string(), Some("i64".to_string()))
+        );
+        let item: Item = header.extern_item(header.decl("fclose").unwrap(), Span::default());
+        let ItemKind::Fun(fun) = &item.kind else {
+            panic!("expected a function");
+        };
+        assert_eq!(fun.params[0].ty.to_string(), "*FILE");
+        let item: Item = header.extern_item(header.decl("FILE").unwrap(), Span::default());
+        assert!(matches!(&item.kind, ItemKind::Alias { ty, .. } if ty.to_string() == "_IO_FILE"));
+        let item: Item = header.extern_item(header.decl("LIMIT").unwrap(), Span::default());
+        let ItemKind::Const { ty, value, .. } = &item.kind else {
+            panic!("expected a constant");
+        };
+        assert_eq!(ty.to_string(), "i32");
+        assert!(matches!(
+            &value.kind,
+            NodeKind::Unary {
+                op: UnaryOp::Negate,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,1054 @@
+// Semantic checks
+use crate::modules::{Module, Symbol};
+use crate::parser::{
+    AssignOp, Block, Case, Catch, Fun, Item, ItemKind, Node, NodeKind, Primitive, Stmt, StmtKind,
+    StructField, Type, TypeKind, Variant,
+};
+use crate::span::{FileId, Span};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// The built in error type, i.e. `throw Error::DivisionByZero;`. Its variants aren't declared
+/// anywhere, so any variant is accepted.
+const BUILTIN_ERROR: &str = "Error";
+
+/// # Check Error
+///
+/// A problem found after parsing, i.e. throwing a value that isn't an error.
+#[derive(PartialEq, Debug, Clone)]
+pub struct CheckError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[Line {}, Column {}] {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+/// # Checker
+///
+/// Checks the desugared items of a file for mistakes the grammar can't catch. Only types
+/// that are written out or easy to see are known (declarations, parameters, literals,
+/// enum paths and calls to declared functions, including ones imported from C headers);
+/// expressions of unknown type are let through.
+pub struct Checker<'a> {
+    items: &'a [Item],
+    enums: HashMap<&'a str, &'a [Variant]>,
+    error_enums: HashSet<&'a str>, // The enums declared as errors, `enum IoError: Error`
+    structs: HashMap<&'a str, &'a [StructField]>,
+    functions: HashMap<&'a str, &'a Fun>,
+    return_type: Option<&'a Type>, // The return type of the function being checked
+    scopes: Vec<HashMap<String, Option<Type>>>, // The variables in scope, innermost block last
+    errors: Vec<CheckError>,
+}
+
+impl<'a> Checker<'a> {
+    /// # New
+    ///
+    /// Create a new checker for the items of a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items returned by `Parser::parse`, after `desugar::desugar`.
+    /// * `scope` - The items the file can use by name, the ones it declares and the ones it
+    ///   imports, i.e. from `modules::Loader::resolve`.
+    pub fn new(items: &'a [Item], scope: impl IntoIterator<Item = (&'a str, &'a Item)>) -> Self {
+        let mut enums: HashMap<&'a str, &'a [Variant]> = HashMap::new();
+        let mut error_enums: HashSet<&'a str> = HashSet::new();
+        let mut structs: HashMap<&'a str, &'a [StructField]> = HashMap::new();
+        let mut functions: HashMap<&'a str, &'a Fun> = HashMap::new();
+        for (name, item) in scope {
+            match &item.kind {
+                ItemKind::Enum {
+                    variants, is_error, ..
+                } => {
+                    enums.insert(name, variants);
+                    if *is_error {
+                        error_enums.insert(name);
+                    }
+                }
+                ItemKind::Struct { fields, .. } => {
+                    structs.insert(name, fields);
+                }
+                ItemKind::Fun(fun) => {
+                    functions.insert(name, fun);
+                }
+                _ => {}
+            }
+        }
+        Checker {
+            items,
+            enums,
+            error_enums,
+            structs,
+            functions,
+            return_type: None,
+            scopes: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// # Check
+    ///
+    /// Runs every check. Problems end up in [`Checker::errors`].
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut checker: check::Checker =
+    ///     check::Checker::new(&module.items, loader.resolve(module.file_id));
+    /// checker.check();
+    /// for error in checker.errors() {
+    ///     zlog::err(&error.to_string(), &c_settings);
+    /// }
+    /// ```
+    pub fn check(&mut self) {
+        for item in self.items {
+            if let ItemKind::Fun(fun) = &item.kind {
+                self.return_type = fun.return_type.as_ref();
+                self.scopes.push(
+                    fun.params
+                        .iter()
+                        .map(|param| (param.name.text.clone(), Some(param.ty.clone())))
+                        .collect(),
+                );
+                self.check_block(&fun.body);
+                self.scopes.pop();
+            }
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns the errors found by [`Checker::check`].
+    pub fn errors(&self) -> &[CheckError] {
+        &self.errors
+    }
+
+    /// # Check Block
+    ///
+    /// Checks the statements of a block in a scope of their own.
+    fn check_block(&mut self, block: &Block) {
+        self.scopes.push(HashMap::new());
+        for stmt in &block.stmts {
+            self.check_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    /// # Check Stmt
+    ///
+    /// Checks a statement and the blocks inside it.
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        for node in stmt.children().0 {
+            self.check_expr(node);
+        }
+        match &stmt.kind {
+            StmtKind::Let {
+                name, ty, value, ..
+            } => {
+                if let Some(value) = value {
+                    self.check_struct_literal(value, ty);
+                }
+                self.declare(&name.text, Some(ty.clone()));
+            }
+            StmtKind::Return(Some(value)) => {
+                if let Some(return_type) = self.return_type {
+                    self.check_struct_literal(value, return_type);
+                }
+            }
+            StmtKind::Block(block)
+            | StmtKind::While { body: block, .. }
+            | StmtKind::DoWhile { body: block, .. } => self.check_block(block),
+            StmtKind::For { binding, body, .. } => {
+                self.scopes
+                    .push(HashMap::from([(binding.text.clone(), None)]));
+                self.check_block(body);
+                self.scopes.pop();
+            }
+            StmtKind::If {
+                then_block,
+                else_branch,
+                ..
+            } => {
+                self.check_block(then_block);
+                if let Some(else_branch) = else_branch {
+                    self.check_stmt(else_branch);
+                }
+            }
+            StmtKind::Throw(value) => self.check_throw(value),
+            StmtKind::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                self.check_block(body);
+                for catch in catches {
+                    let mut scope: HashMap<String, Option<Type>> = HashMap::new();
+                    if let Some(binding) = &catch.binding {
+                        if !self.is_error_type(&binding.ty) {
+                            self.error(
+                                format!(
+                                    "Only errors can be caught, `{}` is not an error type",
+                                    binding.ty
+                                ),
+                                binding.ty.span,
+                            );
+                        }
+                        scope.insert(binding.name.text.clone(), Some(binding.ty.clone()));
+                    }
+                    self.scopes.push(scope);
+                    self.check_block(&catch.body);
+                    self.scopes.pop();
+                }
+                if let Some(finally) = finally {
+                    self.check_block(finally);
+                }
+            }
+            StmtKind::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                for case in cases {
+                    for pattern in &case.patterns {
+                        self.check_variant(pattern);
+                    }
+                    self.check_block(&case.body);
+                }
+                match default {
+                    Some(default) => self.check_block(default),
+                    None => self.check_exhaustive(value, cases, stmt.span),
+                }
+            }
+            StmtKind::Guarded { stmt, .. } => self.check_stmt(stmt),
+            StmtKind::Expr(_) | StmtKind::Break | StmtKind::Continue | StmtKind::Return(_) => {}
+        }
+    }
+
+    /// # Check Expr
+    ///
+    /// Checks the struct literals assigned to a variable or field of known type, or passed to a
+    /// declared function, anywhere in an expression.
+    fn check_expr(&mut self, node: &Node) {
+        for child in node.children() {
+            self.check_expr(child);
+        }
+        match &node.kind {
+            NodeKind::Assign {
+                target,
+                op: AssignOp::Assign,
+                value,
+            } => {
+                if let Some(ty) = self.type_of(target) {
+                    self.check_struct_literal(value, &ty);
+                }
+            }
+            NodeKind::Call { callee, args } => {
+                if let NodeKind::Ident(name) = &callee.kind
+                    && let Some(fun) = self.functions.get(name.as_str()).copied()
+                {
+                    for (arg, param) in args.iter().zip(&fun.params) {
+                        self.check_struct_literal(arg, &param.ty);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// # Check Throw
+    ///
+    /// Checks that a thrown value is an error, and that a thrown enum variant exists.
+    fn check_throw(&mut self, value: &Node) {
+        if !self.check_variant(value) {
+            return;
+        }
+        if let Some(ty) = self.type_of(value)
+            && !self.is_error_type(&ty)
+        {
+            self.error(
+                format!("Only errors can be thrown, found a value of type `{}`", ty),
+                value.span,
+            );
+        }
+    }
+
+    /// # Check Struct Literal
+    ///
+    /// Checks a struct literal against the struct it builds: every field it sets has to exist
+    /// and be set once, and every field without a default has to be set. Literals nested in
+    /// fields and ternary branches are checked too; values that aren't struct literals are let
+    /// through.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of a declaration, `return`, assignment or function argument.
+    /// * `ty` - The type the value is expected to have.
+    fn check_struct_literal(&mut self, value: &Node, ty: &Type) {
+        let inits = match &value.kind {
+            NodeKind::Ternary {
+                then_value,
+                else_value,
+                ..
+            } => {
+                self.check_struct_literal(then_value, ty);
+                self.check_struct_literal(else_value, ty);
+                return;
+            }
+            NodeKind::StructLiteral(inits) => inits,
+            _ => return,
+        };
+        if let TypeKind::Optional(inner) = &ty.kind {
+            self.check_struct_literal(value, inner);
+            return;
+        }
+        let name: Option<&str> = ty.simple_name();
+        let Some(fields) = name.and_then(|name| self.structs.get(name).copied()) else {
+            let is_enum: bool = name.is_some_and(|name| self.enums.contains_key(name));
+            if is_enum || !matches!(ty.kind, TypeKind::Named { .. }) {
+                self.error(
+                    format!("A struct literal can't build a value of type `{}`", ty),
+                    value.span,
+                );
+            }
+            return;
+        };
+        let mut set: HashSet<&str> = HashSet::new();
+        for init in inits {
+            match fields
+                .iter()
+                .find(|field| field.name.text == init.name.text)
+            {
+                _ if !set.insert(&init.name.text) => self.error(
+                    format!("The field `{}` is set more than once", init.name.text),
+                    init.name.span,
+                ),
+                Some(field) => self.check_struct_literal(&init.value, &field.ty),
+                None => self.error(
+                    format!("`{}` has no field `{}`", ty, init.name.text),
+                    init.name.span,
+                ),
+            }
+        }
+        let missing: Vec<String> = fields
+            .iter()
+            .filter(|field| field.default.is_none() && !set.contains(field.name.text.as_str()))
+            .map(|field| format!("`{}`", field.name.text))
+            .collect();
+        if !missing.is_empty() {
+            let message: String = if missing.len() == 1 {
+                format!(
+                    "`{}` needs a value for {}, which has no default",
+                    ty, missing[0]
+                )
+            } else {
+                format!(
+                    "`{}` needs values for {}, which have no default",
+                    ty,
+                    missing.join(", ")
+                )
+            };
+            self.error(message, value.span);
+        }
+    }
+
+    /// # Check Variant
+    ///
+    /// Checks that an enum variant path like `Color::Red` names a variant of a declared enum.
+    /// Anything else is let through.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `false` if the variant doesn't exist, which has been reported.
+    fn check_variant(&mut self, node: &Node) -> bool {
+        if let NodeKind::Path(path) = &node.kind
+            && let [enum_name, variant] = path.as_slice()
+            && let Some(variants) = self.enums.get(enum_name.as_str())
+            && !variants.iter().any(|known| &known.name.text == variant)
+        {
+            self.error(
+                format!("`{}` has no variant `{}`", enum_name, variant),
+                node.span,
+            );
+            return false;
+        }
+        true
+    }
+
+    /// # Check Exhaustive
+    ///
+    /// Checks that a `switch` without a `default` over a value of a declared enum has a case
+    /// for every variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The switched value.
+    /// * `cases` - The cases of the switch.
+    /// * `span` - The span of the whole switch, where a missing variant is reported.
+    fn check_exhaustive(&mut self, value: &Node, cases: &[Case], span: Span) {
+        let Some(Type {
+            kind: TypeKind::Named { path, .. },
+            ..
+        }) = self.type_of(value)
+        else {
+            return;
+        };
+        let [enum_name] = path.as_slice() else {
+            return;
+        };
+        let Some(variants) = self.enums.get(enum_name.as_str()) else {
+            return;
+        };
+        let missing: Vec<String> = variants
+            .iter()
+            .filter(|variant| {
+                !cases.iter().flat_map(|case| &case.patterns).any(|pattern| {
+                    matches!(&pattern.kind, NodeKind::Path(pattern_path)
+                        if pattern_path.len() == 2
+                            && &pattern_path[0] == enum_name
+                            && pattern_path[1] == variant.name.text)
+                })
+            })
+            .map(|variant| format!("`{}::{}`", enum_name, variant.name.text))
+            .collect();
+        if !missing.is_empty() {
+            self.error(
+                format!(
+                    "The switch over `{}` doesn't handle {}, add a case for {} or a `default`",
+                    enum_name,
+                    missing.join(", "),
+                    if missing.len() == 1 { "it" } else { "them" }
+                ),
+                span,
+            );
+        }
+    }
+
+    /// # Type Of
+    ///
+    /// Returns the type of an expression, or `None` if it isn't easy to tell.
+    fn type_of(&self, node: &Node) -> Option<Type> {
+        let primitive = |primitive: Primitive| {
+            Some(Type {
+                kind: TypeKind::Primitive(primitive),
+                span: node.span,
+            })
+        };
+        match &node.kind {
+            NodeKind::Int(_, suffix) => primitive(suffix.unwrap_or(Primitive::I32)),
+            NodeKind::Float(_, suffix) => primitive(suffix.unwrap_or(Primitive::F64)),
+            NodeKind::Bool(_) => primitive(Primitive::Bool),
+            NodeKind::Char(_) => primitive(Primitive::Char),
+            NodeKind::Str(_) => primitive(Primitive::String),
+            NodeKind::Ident(name) => self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))
+                .cloned()
+                .flatten(),
+            NodeKind::Path(path)
+                if path.len() == 2
+                    && (path[0] == BUILTIN_ERROR || self.enums.contains_key(path[0].as_str())) =>
+            {
+                Some(Type {
+                    kind: TypeKind::Named {
+                        path: vec![path[0].clone()],
+                        args: Vec::new(),
+                    },
+                    span: node.span,
+                })
+            }
+            NodeKind::Call { callee, .. } => match &callee.kind {
+                NodeKind::Ident(name) => self
+                    .functions
+                    .get(name.as_str())
+                    .and_then(|fun| fun.return_type.clone()),
+                _ => None,
+            },
+            NodeKind::Field { base, name } => {
+                let base_type: Type = self.type_of(base)?;
+                self.structs
+                    .get(base_type.simple_name()?)?
+                    .iter()
+                    .find(|field| &field.name.text == name)
+                    .map(|field| field.ty.clone())
+            }
+            NodeKind::Ternary { then_value, .. } => self.type_of(then_value),
+            _ => None,
+        }
+    }
+
+    /// # Is Error Type
+    ///
+    /// Checks whether values of a type can be thrown and caught: the built in `Error`, or an
+    /// enum declared as an error, i.e. `enum IoError: Error { ... }`. See `docs/errors.md`.
+    fn is_error_type(&self, ty: &Type) -> bool {
+        match ty.simple_name() {
+            Some(BUILTIN_ERROR) => true,
+            Some(name) => self.error_enums.contains(name),
+            None => false,
+        }
+    }
+
+    /// # Declare
+    ///
+    /// Adds a variable to the innermost scope.
+    fn declare(&mut self, name: &str, ty: Option<Type>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(CheckError { message, span });
+    }
+}
+
+/// A function or enum, by the module it is declared in and its index in that module's items.
+type ItemId = (FileId, usize);
+
+/// # Error Type
+///
+/// The type of an error that can be thrown: the built in `Error`, or an error enum.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum ErrorType {
+    Builtin,
+    Enum(ItemId),
+}
+
+/// An error that can leave a block: its type, if it is known, and the `throw` or call it
+/// escapes from.
+struct Escape {
+    ty: Option<ErrorType>,
+    span: Span,
+}
+
+/// # Check Uncaught Errors
+///
+/// Works out which errors each function of every loaded module can let escape, either by
+/// throwing them or by calling a function that does, outside of a `try` with a `catch` for
+/// them. Errors propagate through every call that doesn't catch them, including calls to
+/// imported functions, so this is repeated until no function lets a new one escape. An error
+/// that escapes `main` in the file being compiled would end the program, which is warned
+/// about.
+///
+/// This only looks at the code; `interpret::run` unwinds the errors when the program runs.
+///
+/// # Arguments
+///
+/// * `modules` - The loaded modules after `desugar::desugar`, i.e. `Loader::modules`.
+///
+/// # Returns
+///
+/// * `Vec<CheckError>` - The warnings, all in the file being compiled.
+pub fn check_uncaught_errors(modules: &[Module]) -> Vec<CheckError> {
+    let mut flow: ErrorFlow = ErrorFlow {
+        modules,
+        throwing: HashMap::new(),
+    };
+    loop {
+        let mut is_changed: bool = false;
+        for module in modules {
+            for (index, item) in module.items.iter().enumerate() {
+                let ItemKind::Fun(fun) = &item.kind else {
+                    continue;
+                };
+                for escape in flow.escaping_errors(module.file_id, &fun.body) {
+                    let types: &mut Vec<Option<ErrorType>> =
+                        flow.throwing.entry((module.file_id, index)).or_default();
+                    if !types.contains(&escape.ty) {
+                        types.push(escape.ty);
+                        is_changed = true;
+                    }
+                }
+            }
+        }
+        if !is_changed {
+            break;
+        }
+    }
+    let Some(root) = modules.first() else {
+        return Vec::new();
+    };
+    root.items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Fun(fun) if fun.name.text == "main" => flow
+                .escaping_errors(root.file_id, &fun.body)
+                .into_iter()
+                .next(),
+            _ => None,
+        })
+        .map(|escape| CheckError {
+            message: "This error is never caught and will end the program when it leaves `main`"
+                .to_string(),
+            span: escape.span,
+        })
+        .collect()
+}
+
+/// # Error Flow
+///
+/// Follows errors through the functions of every loaded module, looking names up in the
+/// namespace of the module each function is declared in.
+struct ErrorFlow<'a> {
+    modules: &'a [Module],
+    throwing: HashMap<ItemId, Vec<Option<ErrorType>>>, // The errors each function can let escape
+}
+
+impl<'a> ErrorFlow<'a> {
+    /// # Escaping Errors
+    ///
+    /// Finds the errors that can leave a block of a function in `file_id`: the ones thrown, or
+    /// let escape by a call to a throwing function, that aren't caught inside the block.
+    fn escaping_errors(&self, file_id: FileId, block: &Block) -> Vec<Escape> {
+        block
+            .stmts
+            .iter()
+            .flat_map(|stmt| self.escaping_errors_in_stmt(file_id, stmt))
+            .collect()
+    }
+
+    /// # Escaping Errors In Stmt
+    ///
+    /// [`ErrorFlow::escaping_errors`] for a single statement.
+    fn escaping_errors_in_stmt(&self, file_id: FileId, stmt: &Stmt) -> Vec<Escape> {
+        match &stmt.kind {
+            StmtKind::Throw(value) => vec![Escape {
+                ty: self.thrown_type(file_id, value),
+                span: stmt.span,
+            }],
+            StmtKind::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                // Errors thrown while handling one, or in `finally`, still escape
+                let mut escapes: Vec<Escape> = self
+                    .escaping_errors(file_id, body)
+                    .into_iter()
+                    .filter(|escape| {
+                        !catches
+                            .iter()
+                            .any(|catch| self.catches_error(file_id, catch, escape))
+                    })
+                    .collect();
+                for catch in catches {
+                    escapes.extend(self.escaping_errors(file_id, &catch.body));
+                }
+                if let Some(finally) = finally {
+                    escapes.extend(self.escaping_errors(file_id, finally));
+                }
+                escapes
+            }
+            _ => {
+                let (nodes, stmts) = stmt.children();
+                let mut escapes: Vec<Escape> = Vec::new();
+                for node in nodes {
+                    self.throwing_calls(file_id, node, &mut escapes);
+                }
+                for stmt in stmts {
+                    escapes.extend(self.escaping_errors_in_stmt(file_id, stmt));
+                }
+                escapes
+            }
+        }
+    }
+
+    /// # Throwing Calls
+    ///
+    /// Collects the errors the calls to throwing functions in an expression can let escape.
+    fn throwing_calls(&self, file_id: FileId, node: &Node, escapes: &mut Vec<Escape>) {
+        if let NodeKind::Call { callee, .. } = &node.kind
+            && let NodeKind::Ident(name) = &callee.kind
+            && let Some((id, _)) = self.resolve(file_id, name)
+            && let Some(types) = self.throwing.get(&id)
+        {
+            escapes.extend(types.iter().map(|ty| Escape {
+                ty: *ty,
+                span: node.span,
+            }));
+        }
+        for child in node.children() {
+            self.throwing_calls(file_id, child, escapes);
+        }
+    }
+
+    /// # Catches Error
+    ///
+    /// Checks whether a `catch` handles an escaping error. A `catch` without a binding and
+    /// `catch (e: Error)` handle every error; `catch (e: IoError)` only handles `IoError`s. An
+    /// error whose type isn't known is assumed to be handled by any `catch`.
+    fn catches_error(&self, file_id: FileId, catch: &Catch, escape: &Escape) -> bool {
+        let Some(binding) = &catch.binding else {
+            return true;
+        };
+        match (self.error_type(file_id, &binding.ty), escape.ty) {
+            (Some(ErrorType::Builtin), _) => true,
+            (Some(caught), Some(thrown)) => caught == thrown,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// # Thrown Type
+    ///
+    /// Returns the type of a thrown value, where it is easy to tell: an enum variant like
+    /// `IoError::Closed`, or a call to a function with a declared return type.
+    fn thrown_type(&self, file_id: FileId, value: &Node) -> Option<ErrorType> {
+        match &value.kind {
+            NodeKind::Path(path) if path.len() == 2 => {
+                if path[0] == BUILTIN_ERROR {
+                    return Some(ErrorType::Builtin);
+                }
+                match self.resolve(file_id, &path[0])? {
+                    (id, ItemKind::Enum { is_error: true, .. }) => Some(ErrorType::Enum(id)),
+                    _ => None,
+                }
+            }
+            NodeKind::Call { callee, .. } => {
+                let NodeKind::Ident(name) = &callee.kind else {
+                    return None;
+                };
+                match self.resolve(file_id, name)? {
+                    // The return type is named in the namespace of the callee's module
+                    ((callee_file, _), ItemKind::Fun(fun)) => {
+                        self.error_type(callee_file, fun.return_type.as_ref()?)
+                    }
+                    _ => None,
+                }
+            }
+            NodeKind::Ternary { then_value, .. } => self.thrown_type(file_id, then_value),
+            _ => None,
+        }
+    }
+
+    /// # Error Type
+    ///
+    /// Resolves a type named in `file_id` to an error type, if it is one.
+    fn error_type(&self, file_id: FileId, ty: &Type) -> Option<ErrorType> {
+        match ty.simple_name()? {
+            BUILTIN_ERROR => Some(ErrorType::Builtin),
+            name => match self.resolve(file_id, name)? {
+                (id, ItemKind::Enum { is_error: true, .. }) => Some(ErrorType::Enum(id)),
+                _ => None,
+            },
+        }
+    }
+
+    /// # Resolve
+    ///
+    /// Looks a name up in the namespace of `file_id`, returning the item it refers to.
+    fn resolve(&self, file_id: FileId, name: &str) -> Option<(ItemId, &'a ItemKind)> {
+        match self.modules[file_id.0 as usize].scope.get(name)? {
+            Symbol::Item { file_id, index } => {
+                let item: &Item = &self.modules[file_id.0 as usize].items[*index];
+                Some(((*file_id, *index), &item.kind))
+            }
+            Symbol::Std(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::desugar;
+    use crate::parser::Parser;
+    use crate::tokenizer::Tokenizer;
+    use std::path::PathBuf;
+
+    type Diagnostics = Vec<(String, u32)>;
+
+    /// Checks `src` and returns the errors and warnings as `(message, line)` pairs.
+    fn check(src: &str) -> (Diagnostics, Diagnostics) {
+        let c_settings: CSettings = CSettings::default();
+        let tokens = Tokenizer::new(src.to_string(), &c_settings).tokenize();
+        let mut parser: Parser = Parser::new(tokens, src);
+        let items: Vec<Item> = parser.parse();
+        assert_eq!(parser.errors(), &[]);
+        let scope: HashMap<String, Symbol> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let symbol: Symbol = Symbol::Item {
+                    file_id: FileId(0),
+                    index,
+                };
+                Some((item.name()?.text.clone(), symbol))
+            })
+            .collect();
+        let mut module: Module = Module {
+            file_id: FileId(0),
+            path: PathBuf::from("main.zc"),
+            items,
+            scope,
+        };
+        fn named(items: &[Item]) -> Vec<(&str, &Item)> {
+            items
+                .iter()
+                .filter_map(|item| Some((item.name()?.text.as_str(), item)))
+                .collect()
+        }
+        let declarations: desugar::Declarations = desugar::Declarations::new(named(&module.items));
+        desugar::desugar(&mut module.items, &declarations);
+        let mut checker: Checker = Checker::new(&module.items, named(&module.items));
+        checker.check();
+        let warnings: Vec<CheckError> = check_uncaught_errors(std::slice::from_ref(&module));
+        let pairs = |errors: &[CheckError]| {
+            errors
+                .iter()
+                .map(|error| (error.message.clone(), error.span.line))
+                .collect::<Diagnostics>()
+        };
+        (pairs(checker.errors()), pairs(&warnings))
+    }
+
+    #[test]
+    fn thrown_values_must_be_errors() {
+        let (errors, _) = check(
+            "enum IoError: Error { NotFound }\n\
+             enum ColorError { Red }\n\
+             fun f(x: i32, e: IoError) {\n\
+                 throw Error::DivisionByZero;\n\
+                 throw IoError::NotFound;\n\
+                 throw e;\n\
+                 throw x;\n\
+                 throw \"oops\";\n\
+                 throw ColorError::Red;\n\
+                 throw IoError::Gone;\n\
+                 throw unknown;\n\
+                 throw 42u8;\n\
+                 throw 1.5f32;\n\
+                 throw 1.5;\n\
+             }",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "Only errors can be thrown, found a value of type `i32`".to_string(),
+                    7
+                ),
+                (
+                    "Only errors can be thrown, found a value of type `string`".to_string(),
+                    8
+                ),
+                (
+                    "Only errors can be thrown, found a value of type `ColorError`".to_string(),
+                    9
+                ),
+                ("`IoError` has no variant `Gone`".to_string(), 10),
+                (
+                    "Only errors can be thrown, found a value of type `u8`".to_string(),
+                    12
+                ),
+                (
+                    "Only errors can be thrown, found a value of type `f32`".to_string(),
+                    13
+                ),
+                (
+                    "Only errors can be thrown, found a value of type `f64`".to_string(),
+                    14
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn catch_bindings_have_the_caught_type() {
+        let (errors, _) = check(
+            "fun f() {\n\
+                 x: string = \"shadowed\";\n\
+                 try {\n\
+                     throw Error::DivisionByZero;\n\
+                 } catch (const x: Error) {\n\
+                     throw x;\n\
+                 } catch (y: i32) {\n\
+                 } finally {\n\
+                     throw x;\n\
+                 }\n\
+             }",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "Only errors can be caught, `i32` is not an error type".to_string(),
+                    7
+                ),
+                (
+                    "Only errors can be thrown, found a value of type `string`".to_string(),
+                    9
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_propagate_through_calls_that_dont_catch() {
+        let src: &str = "fun can_error(y: i32) -> i32 {\n\
+                 if y == 0 { throw Error::DivisionByZero; }\n\
+                 return y;\n\
+             }\n\
+             fun passes_on(y: i32) -> i32 { return can_error(y) + 1; }\n\
+             fun handles(y: i32) -> i32 {\n\
+                 try { return passes_on(y); } catch (e: Error) { return 0; }\n\
+             }\n";
+        let (errors, warnings) = check(&format!("{}fun main() {{ handles(0); }}", src));
+        assert_eq!((errors, warnings), (vec![], vec![]));
+
+        let (_, warnings) = check(&format!(
+            "{}fun main() {{\n handles(0);\n passes_on(0) when true;\n}}",
+            src
+        ));
+        assert_eq!(
+            warnings,
+            vec![(
+                "This error is never caught and will end the program when it leaves `main`"
+                    .to_string(),
+                11
+            )]
+        );
+    }
+
+    #[test]
+    fn catches_only_handle_their_error_type() {
+        let src: &str = "enum IoError: Error { NotFound }\n\
+                         enum ParseError: Error { Bad }\n\
+                         fun read() { throw IoError::NotFound; }\n\
+                         fun main() {\n\
+                             try { read(); } catch (e: IoError) {}\n\
+                             try { throw ParseError::Bad; } catch (e: Error) {}\n\
+                             try { throw ParseError::Bad; } catch {}\n";
+        let (errors, warnings) = check(&format!("{}}}", src));
+        assert_eq!((errors, warnings), (vec![], vec![]));
+
+        let (_, warnings) = check(&format!(
+            "{}try {{ read(); }} catch (e: ParseError) {{}}\n}}",
+            src
+        ));
+        assert_eq!(
+            warnings,
+            vec![(
+                "This error is never caught and will end the program when it leaves `main`"
+                    .to_string(),
+                8
+            )]
+        );
+    }
+
+    #[test]
+    fn switches_over_enums_are_exhaustive() {
+        let (errors, _) = check(
+            "enum Test { test1, test2, test3 }\n\
+             fun f(t: Test, n: i32) {\n\
+                 switch t { case Test::test1, Test::test2: a(); case Test::test3: b(); }\n\
+                 switch t { case Test::test1: a(); default: b(); }\n\
+                 switch n { case 1..5: a(); }\n\
+                 switch t { case Test::test2: a(); }\n\
+                 switch t { case Test::test4: a(); case Test::test1, Test::test2: b(); }\n\
+             }",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "The switch over `Test` doesn't handle `Test::test1`, `Test::test3`, add a case for them or a `default`"
+                        .to_string(),
+                    6
+                ),
+                ("`Test` has no variant `test4`".to_string(), 7),
+                (
+                    "The switch over `Test` doesn't handle `Test::test3`, add a case for it or a `default`"
+                        .to_string(),
+                    7
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_literals_set_every_required_field() {
+        let (errors, _) = check(
+            "struct Person {\n\
+                 Name: string = \"Unknown\",\n\
+                 Gender: f32,\n\
+                 Height: i32,\n\
+             }\n\
+             struct Couple { First: Person, Second: Person? }\n\
+             fun f() -> Person {\n\
+                 a: Person = { Gender = 0.1, Height = 200 };\n\
+                 b: Person = { Name = \"Tallen\" };\n\
+                 c: Person = { Gender = 0.1, Height = 1, Nmae = \"x\", Gender = 0.2 };\n\
+                 d: Couple = { First = { Height = 1 }, Second = { Gender = 0.1, Height = 2 } };\n\
+                 e: i32 = { };\n\
+                 return { Gender = 0.1 };\n\
+             }",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "`Person` needs values for `Gender`, `Height`, which have no default"
+                        .to_string(),
+                    9
+                ),
+                ("`Person` has no field `Nmae`".to_string(), 10),
+                ("The field `Gender` is set more than once".to_string(), 10),
+                (
+                    "`Person` needs a value for `Gender`, which has no default".to_string(),
+                    11
+                ),
+                (
+                    "A struct literal can't build a value of type `i32`".to_string(),
+                    12
+                ),
+                (
+                    "`Person` needs a value for `Height`, which has no default".to_string(),
+                    13
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn assigned_and_passed_struct_literals_are_checked() {
+        let (errors, _) = check(
+            "struct Person { Name: string = \"Unknown\", Gender: f32 }\n\
+             struct Pair { First: Person, Second: Person }\n\
+             fun take(p: Person) {}\n\
+             fun f(pair: Pair) {\n\
+                 p: Person = { Gender = 0.1 };\n\
+                 p = { Nope = 1, Gender = 0.2 };\n\
+                 pair.First = { Name = \"Tallen\" };\n\
+                 take({ Gender = 0.3 });\n\
+                 take({ Nope = 2 });\n\
+             }",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ("`Person` has no field `Nope`".to_string(), 6),
+                (
+                    "`Person` needs a value for `Gender`, which has no default".to_string(),
+                    7
+                ),
+                ("`Person` has no field `Nope`".to_string(), 9),
+                (
+                    "`Person` needs a value for `Gender`, which has no default".to_string(),
+                    9
+                ),
+            ]
+        );
+    }
+}
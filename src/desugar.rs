@@ -0,0 +1,468 @@
+// Desugaring
+use crate::parser::{
+    AssignOp, Block, FieldInit, Item, ItemKind, Node, NodeKind, Stmt, StmtKind, StructField, Type,
+};
+use std::collections::HashMap;
+
+/// The fields of every declared struct, by struct name.
+type Structs = HashMap<String, Vec<StructField>>;
+
+/// The parameter types of every declared function, by function name.
+type Functions = HashMap<String, Vec<Type>>;
+
+/// # Declarations
+///
+/// The structs and functions a file can use by name, the ones it declares and the ones it
+/// imports, which is all desugaring needs to know about other items.
+pub struct Declarations {
+    structs: Structs,
+    functions: Functions,
+}
+
+impl Declarations {
+    /// # New
+    ///
+    /// Collects the structs and functions from the items a file's namespace refers to.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items by the name the file knows them by, i.e. from
+    ///   `modules::Loader::resolve`.
+    pub fn new<'i>(items: impl IntoIterator<Item = (&'i str, &'i Item)>) -> Self {
+        let mut structs: Structs = HashMap::new();
+        let mut functions: Functions = HashMap::new();
+        for (name, item) in items {
+            match &item.kind {
+                ItemKind::Struct { fields, .. } => {
+                    structs.insert(name.to_string(), fields.clone());
+                }
+                ItemKind::Fun(fun) => {
+                    let params: Vec<Type> =
+                        fun.params.iter().map(|param| param.ty.clone()).collect();
+                    functions.insert(name.to_string(), params);
+                }
+                _ => {}
+            }
+        }
+        Declarations { structs, functions }
+    }
+}
+
+/// # Desugar
+///
+/// Lowers syntax sugar in the parsed items to the core syntax, so later phases only have to
+/// handle the core. Runs after parsing.
+///
+/// Postfix guards become ordinary `if`s: `println("Debug is on") when debug;` is lowered to
+/// `if debug { println("Debug is on"); }`. The `if` keeps the span of the whole guarded
+/// statement, its condition keeps the span it had in the guard clause and the span of the
+/// clause itself is kept as the `if`'s `guard`, so diagnostics still point at the source as
+/// written.
+///
+/// Struct literals get the default value of every field they leave out, where the struct they
+/// build is known from a declaration, an assignment to a variable or field of known type, a
+/// function parameter or a return type: with `Age: i32 = 0` declared,
+/// `p: Person = { Name = "Tallen" };` becomes `p: Person = { Name = "Tallen", Age = 0 };`.
+/// Fields without a default are left for the checker to report.
+///
+/// # Arguments
+///
+/// * `items` - The items returned by `Parser::parse`.
+/// * `declarations` - The structs and functions the items can use.
+pub fn desugar(items: &mut [Item], declarations: &Declarations) {
+    for item in items {
+        if let ItemKind::Fun(fun) = &mut item.kind {
+            let mut desugarer: Desugarer = Desugarer {
+                structs: &declarations.structs,
+                functions: &declarations.functions,
+                return_type: fun.return_type.as_ref(),
+                scopes: vec![
+                    fun.params
+                        .iter()
+                        .map(|param| (param.name.text.clone(), Some(param.ty.clone())))
+                        .collect(),
+                ],
+            };
+            desugarer.desugar_block(&mut fun.body);
+        }
+    }
+}
+
+/// # Desugarer
+///
+/// Desugars a function body, keeping track of the types of the variables in scope.
+struct Desugarer<'d> {
+    structs: &'d Structs,
+    functions: &'d Functions,
+    return_type: Option<&'d Type>, // The return type of the function being desugared
+    scopes: Vec<HashMap<String, Option<Type>>>, // The variables in scope, innermost block last
+}
+
+impl Desugarer<'_> {
+    /// # Desugar Block
+    ///
+    /// Desugars every statement in a block, including the ones in nested blocks.
+    fn desugar_block(&mut self, block: &mut Block) {
+        self.scopes.push(HashMap::new());
+        for stmt in &mut block.stmts {
+            self.desugar_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    /// # Desugar Stmt
+    ///
+    /// Desugars a single statement in place.
+    fn desugar_stmt(&mut self, stmt: &mut Stmt) {
+        let kind: StmtKind = std::mem::replace(&mut stmt.kind, StmtKind::Break);
+        stmt.kind = match kind {
+            StmtKind::Guarded { stmt: inner, guard } => StmtKind::If {
+                condition: guard.condition,
+                then_block: Block {
+                    span: inner.span,
+                    stmts: vec![*inner],
+                },
+                else_branch: None,
+                guard: Some(guard.span),
+            },
+            kind => kind,
+        };
+        match &mut stmt.kind {
+            StmtKind::Let {
+                name, ty, value, ..
+            } => {
+                if let Some(value) = value {
+                    self.desugar_expr(value);
+                    fill_defaults(value, ty, self.structs);
+                }
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(name.text.clone(), Some(ty.clone()));
+                }
+            }
+            StmtKind::Return(Some(value)) => {
+                self.desugar_expr(value);
+                if let Some(return_type) = self.return_type {
+                    fill_defaults(value, return_type, self.structs);
+                }
+            }
+            StmtKind::Expr(node) | StmtKind::Throw(node) => self.desugar_expr(node),
+            StmtKind::Block(block) => self.desugar_block(block),
+            StmtKind::While { condition, body } | StmtKind::DoWhile { body, condition } => {
+                self.desugar_expr(condition);
+                self.desugar_block(body);
+            }
+            StmtKind::For {
+                binding,
+                iterable,
+                body,
+            } => {
+                self.desugar_expr(iterable);
+                self.scopes
+                    .push(HashMap::from([(binding.text.clone(), None)]));
+                self.desugar_block(body);
+                self.scopes.pop();
+            }
+            StmtKind::If {
+                condition,
+                then_block,
+                else_branch,
+                ..
+            } => {
+                self.desugar_expr(condition);
+                self.desugar_block(then_block);
+                if let Some(else_branch) = else_branch {
+                    self.desugar_stmt(else_branch);
+                }
+            }
+            StmtKind::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                self.desugar_block(body);
+                for catch in catches {
+                    self.scopes.push(
+                        catch
+                            .binding
+                            .iter()
+                            .map(|binding| (binding.name.text.clone(), Some(binding.ty.clone())))
+                            .collect(),
+                    );
+                    self.desugar_block(&mut catch.body);
+                    self.scopes.pop();
+                }
+                if let Some(finally) = finally {
+                    self.desugar_block(finally);
+                }
+            }
+            StmtKind::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                self.desugar_expr(value);
+                for case in cases {
+                    self.desugar_block(&mut case.body);
+                }
+                if let Some(default) = default {
+                    self.desugar_block(default);
+                }
+            }
+            StmtKind::Break
+            | StmtKind::Continue
+            | StmtKind::Return(None)
+            | StmtKind::Guarded { .. } => {}
+        }
+    }
+
+    /// # Desugar Expr
+    ///
+    /// Fills in the defaults of struct literals assigned to a variable or field of known type,
+    /// or passed to a declared function, anywhere in an expression.
+    fn desugar_expr(&self, node: &mut Node) {
+        for child in node.children_mut() {
+            self.desugar_expr(child);
+        }
+        match &mut node.kind {
+            NodeKind::Assign {
+                target,
+                op: AssignOp::Assign,
+                value,
+            } => {
+                if let Some(ty) = self.declared_type(target) {
+                    fill_defaults(value, &ty, self.structs);
+                }
+            }
+            NodeKind::Call { callee, args } => {
+                if let NodeKind::Ident(name) = &callee.kind
+                    && let Some(params) = self.functions.get(name)
+                {
+                    for (arg, ty) in args.iter_mut().zip(params) {
+                        fill_defaults(arg, ty, self.structs);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// # Declared Type
+    ///
+    /// Returns the declared type of a variable, or of a field of a variable of struct type,
+    /// i.e. `p.Name`.
+    fn declared_type(&self, target: &Node) -> Option<Type> {
+        match &target.kind {
+            NodeKind::Ident(name) => self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))
+                .cloned()
+                .flatten(),
+            NodeKind::Field { base, name } => {
+                let base_type: Type = self.declared_type(base)?;
+                self.structs
+                    .get(base_type.simple_name()?)?
+                    .iter()
+                    .find(|field| &field.name.text == name)
+                    .map(|field| field.ty.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// # Fill Defaults
+///
+/// Adds the default values of the fields a struct literal leaves out, after the fields it
+/// sets. Struct literals nested in fields of struct type, or in the branches of a ternary,
+/// are filled in too.
+///
+/// # Arguments
+///
+/// * `value` - The value of a declaration, `return`, assignment or function argument.
+/// * `ty` - The type the value is expected to have.
+/// * `structs` - The declared structs.
+fn fill_defaults(value: &mut Node, ty: &Type, structs: &Structs) {
+    match &mut value.kind {
+        NodeKind::Ternary {
+            then_value,
+            else_value,
+            ..
+        } => {
+            fill_defaults(then_value, ty, structs);
+            fill_defaults(else_value, ty, structs);
+        }
+        NodeKind::StructLiteral(inits) => {
+            let Some(fields) = ty.simple_name().and_then(|name| structs.get(name)) else {
+                return;
+            };
+            for init in inits.iter_mut() {
+                if let Some(field) = fields
+                    .iter()
+                    .find(|field| field.name.text == init.name.text)
+                {
+                    fill_defaults(&mut init.value, &field.ty, structs);
+                }
+            }
+            for field in fields {
+                if let Some(default) = &field.default
+                    && !inits.iter().any(|init| init.name.text == field.name.text)
+                {
+                    inits.push(FieldInit {
+                        name: field.name.clone(),
+                        value: default.clone(),
+                        span: field.span,
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::parser::Parser;
+    use crate::span::Span;
+    use crate::tokenizer::Tokenizer;
+
+    fn desugared(src: &str) -> Vec<Item> {
+        let c_settings: CSettings = CSettings::default();
+        let tokens = Tokenizer::new(src.to_string(), &c_settings).tokenize();
+        let mut parser: Parser = Parser::new(tokens, src);
+        let mut items: Vec<Item> = parser.parse();
+        assert_eq!(parser.errors(), &[]);
+        let declarations: Declarations = Declarations::new(
+            items
+                .iter()
+                .filter_map(|item| Some((item.name()?.text.as_str(), item))),
+        );
+        desugar(&mut items, &declarations);
+        items
+    }
+
+    fn desugared_body(src: &str) -> Vec<Stmt> {
+        desugared(src)
+            .iter()
+            .find_map(|item| match &item.kind {
+                ItemKind::Fun(fun) => Some(fun.body.stmts.clone()),
+                _ => None,
+            })
+            .expect("expected a function")
+    }
+
+    #[test]
+    fn guards_become_ifs() {
+        let src: &str =
+            "fun f() {\n    println(\"on\") when debug;\n    while true { return 1 if done; }\n}";
+        let stmts: Vec<Stmt> = desugared_body(src);
+        let StmtKind::If {
+            condition,
+            then_block,
+            else_branch: None,
+            guard: Some(guard),
+        } = &stmts[0].kind
+        else {
+            panic!("expected an if");
+        };
+        let text = |span: Span| &src[span.start..span.end];
+        assert_eq!(text(stmts[0].span), "println(\"on\") when debug;");
+        assert_eq!(text(condition.span), "debug");
+        assert_eq!(text(*guard), "when debug");
+        assert_eq!(text(then_block.span), "println(\"on\")");
+        assert!(matches!(then_block.stmts[0].kind, StmtKind::Expr(_)));
+
+        let StmtKind::While { body, .. } = &stmts[1].kind else {
+            panic!("expected a while");
+        };
+        let StmtKind::If { then_block, .. } = &body.stmts[0].kind else {
+            panic!("expected the guard in the loop to be lowered too");
+        };
+        assert!(matches!(
+            then_block.stmts[0].kind,
+            StmtKind::Return(Some(_))
+        ));
+    }
+
+    #[test]
+    fn struct_literals_get_default_fields() {
+        let src: &str = "struct Person { Name: string = \"Unknown\", Age: i32 = 0, Gender: f32 }\n\
+             struct Pair { First: Person, Second: Person }\n\
+             fun f() -> Person {\n\
+                 p: Pair = { First = { Age = 17 }, Second = { Name = \"Tallen\", Age = 30 } };\n\
+                 return { Gender = 0.1 };\n\
+             }";
+        let stmts: Vec<Stmt> = desugared_body(src);
+        let field_names = |node: &Node| match &node.kind {
+            NodeKind::StructLiteral(inits) => inits
+                .iter()
+                .map(|init| init.name.text.clone())
+                .collect::<Vec<String>>(),
+            _ => panic!("expected a struct literal"),
+        };
+        let StmtKind::Let {
+            value: Some(value), ..
+        } = &stmts[0].kind
+        else {
+            panic!("expected a declaration");
+        };
+        let NodeKind::StructLiteral(inits) = &value.kind else {
+            panic!("expected a struct literal");
+        };
+        assert_eq!(field_names(value), vec!["First", "Second"]);
+        assert_eq!(field_names(&inits[0].value), vec!["Age", "Name"]);
+        assert_eq!(field_names(&inits[1].value), vec!["Name", "Age"]);
+        let StmtKind::Return(Some(value)) = &stmts[1].kind else {
+            panic!("expected a return");
+        };
+        assert_eq!(field_names(value), vec!["Gender", "Name", "Age"]);
+        let NodeKind::StructLiteral(inits) = &value.kind else {
+            panic!("expected a struct literal");
+        };
+        assert_eq!(inits[1].value.kind, NodeKind::Str("Unknown".to_string()));
+    }
+
+    #[test]
+    fn assigned_and_passed_struct_literals_get_default_fields() {
+        let src: &str = "struct Person { Name: string = \"Unknown\", Age: i32 = 0, Gender: f32 }\n\
+             struct Pair { First: Person, Second: Person }\n\
+             fun take(p: Person) {}\n\
+             fun f(pair: Pair) {\n\
+                 p: Person = { Gender = 0.1 };\n\
+                 p = { Age = 3 };\n\
+                 pair.First = { Gender = 0.2 };\n\
+                 take({ Name = \"Tallen\" });\n\
+             }";
+        let items: Vec<Item> = desugared(src);
+        let ItemKind::Fun(fun) = &items[3].kind else {
+            panic!("expected a function");
+        };
+        let field_names = |node: &Node| match &node.kind {
+            NodeKind::StructLiteral(inits) => inits
+                .iter()
+                .map(|init| init.name.text.clone())
+                .collect::<Vec<String>>(),
+            _ => panic!("expected a struct literal"),
+        };
+        let assigned = |stmt: &Stmt| match &stmt.kind {
+            StmtKind::Expr(Node {
+                kind: NodeKind::Assign { value, .. },
+                ..
+            }) => field_names(value),
+            _ => panic!("expected an assignment"),
+        };
+        assert_eq!(assigned(&fun.body.stmts[1]), vec!["Age", "Name"]);
+        assert_eq!(assigned(&fun.body.stmts[2]), vec!["Gender", "Name", "Age"]);
+        let StmtKind::Expr(Node {
+            kind: NodeKind::Call { args, .. },
+            ..
+        }) = &fun.body.stmts[3].kind
+        else {
+            panic!("expected a call");
+        };
+        assert_eq!(field_names(&args[0]), vec!["Name", "Age"]);
+    }
+}
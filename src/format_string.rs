@@ -0,0 +1,858 @@
+// Format strings for `print` and `println`
+use crate::parser::{Item, ItemKind, Node, NodeKind, Stmt};
+use crate::span::Span;
+use crate::tokenizer::{LexError, Token, TokenType};
+
+/// The functions whose first argument is lexed as a format string.
+pub const FORMAT_FUNCTIONS: &[&str] = &["print", "println"];
+
+/// The `%` conversions a C-style format string may use.
+const CONVERSIONS: &[char] = &['d', 's', 'f'];
+
+/// How a placeholder's output is aligned within its width.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Align {
+    Left,   // `<`
+    Right,  // `>`
+    Center, // `^`
+}
+
+/// # Format Spec
+///
+/// The part of a placeholder that controls how its argument is printed, i.e. the `>8.2` in
+/// `{:>8.2}` or the `8.2` in `%8.2f`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct FormatSpec {
+    pub fill: Option<char>, // Only set together with `align`
+    pub align: Option<Align>,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+}
+
+impl FormatSpec {
+    /// # Pad
+    ///
+    /// Pads printed text out to the spec's width with its fill character, a space unless one
+    /// is given. Text that is already as wide is left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The printed argument, after the precision was applied.
+    /// * `default_align` - The alignment to use if the spec doesn't give one.
+    pub fn pad(&self, text: &str, default_align: Align) -> String {
+        let width: usize = self.width.unwrap_or(0);
+        let length: usize = text.chars().count();
+        if length >= width {
+            return text.to_string();
+        }
+        let fill: String = self.fill.unwrap_or(' ').to_string();
+        let padding: usize = width - length;
+        let (before, after): (usize, usize) = match self.align.unwrap_or(default_align) {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        format!("{}{}{}", fill.repeat(before), text, fill.repeat(after))
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum PieceKind {
+    Text(String), // Literal text, with escapes and `{{`/`}}`/`%%` already decoded
+    Placeholder {
+        argument: Option<String>, // `{}` is `None`, `{0}` and `{name}` name their argument
+        spec: FormatSpec,
+    },
+    Specifier {
+        conversion: char, // One of `CONVERSIONS`, i.e. the `d` in `%d`
+        spec: FormatSpec,
+    },
+}
+
+/// # Format Piece
+///
+/// A piece of a format string. The span points at the source of the piece inside the string
+/// literal, so a placeholder without an argument can be reported exactly.
+#[derive(PartialEq, Debug, Clone)]
+pub struct FormatPiece {
+    pub kind: PieceKind,
+    pub span: Span,
+}
+
+/// # Format String
+///
+/// A string literal passed to one of the [`FORMAT_FUNCTIONS`], split into literal text,
+/// `{}`-style placeholders and `%`-style specifiers.
+#[derive(PartialEq, Debug, Clone)]
+pub struct FormatString {
+    pub pieces: Vec<FormatPiece>,
+    pub span: Span, // The whole string literal
+}
+
+/// A decoded character of a string literal together with the source it came from. An escape
+/// sequence is one character whose span covers the whole escape.
+#[derive(Clone, Copy)]
+struct SourceChar {
+    ch: char,
+    span: Span,
+}
+
+impl FormatString {
+    /// # Lex
+    ///
+    /// Splits a string literal token into format pieces. Problems such as an unclosed `{` are
+    /// pushed to `errors` and lexing carries on after them.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - A `TokStringLiteral` token.
+    /// * `src` - The source the token was lexed from.
+    /// * `errors` - Where to put the errors found in the format string.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<FormatString>` - The format string, or `None` if `token` isn't a string literal.
+    pub fn lex(token: &Token, src: &str, errors: &mut Vec<LexError>) -> Option<FormatString> {
+        if token.tok_type != TokenType::TokStringLiteral {
+            return None;
+        }
+        let chars: Vec<SourceChar> = source_chars(token, src)?;
+        Some(lex_chars(&chars, token.span, errors))
+    }
+
+    /// # From Value
+    ///
+    /// Splits the decoded value of a string literal into format pieces, without the source it
+    /// was written as. Every piece gets the span of the whole literal, and problems are ignored:
+    /// they were reported when the literal was lexed with [`FormatString::lex`].
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of a `NodeKind::Str`.
+    /// * `span` - The span of the string literal.
+    pub fn from_value(value: &str, span: Span) -> FormatString {
+        let chars: Vec<SourceChar> = value.chars().map(|ch| SourceChar { ch, span }).collect();
+        lex_chars(&chars, span, &mut Vec::new())
+    }
+
+    /// # Placeholders
+    ///
+    /// Returns the pieces that print an argument, in order.
+    pub fn placeholders(&self) -> impl Iterator<Item = &FormatPiece> {
+        self.pieces
+            .iter()
+            .filter(|piece| !matches!(piece.kind, PieceKind::Text(_)))
+    }
+
+    /// # Arg Count
+    ///
+    /// The number of arguments the format string expects after it. `{}` and `%d` take the next
+    /// argument, `{0}` takes the first one and `{name}` prints the variable `name` so it doesn't
+    /// take one.
+    pub fn arg_count(&self) -> usize {
+        let mut next: usize = 0;
+        let mut count: usize = 0;
+        for piece in self.placeholders() {
+            let index: Option<usize> = match &piece.kind {
+                PieceKind::Placeholder {
+                    argument: Some(argument),
+                    ..
+                } => argument.parse().ok(),
+                _ => {
+                    next += 1;
+                    Some(next - 1)
+                }
+            };
+            if let Some(index) = index {
+                count = count.max(index + 1);
+            }
+        }
+        count
+    }
+
+    /// # Check Arg Count
+    ///
+    /// Checks that a call passes as many arguments as the format string expects.
+    ///
+    /// # Arguments
+    ///
+    /// * `given` - The number of arguments after the format string.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), LexError>` - An error pointing at the first placeholder without an argument,
+    ///   or at the whole string if there are arguments left over.
+    pub fn check_arg_count(&self, given: usize) -> Result<(), LexError> {
+        let expected: usize = self.arg_count();
+        if given > expected {
+            return Err(LexError {
+                message: format!(
+                    "{} argument(s) given but the format string only uses {}",
+                    given, expected
+                ),
+                span: self.span,
+            });
+        }
+        let mut next: usize = 0;
+        for piece in self.placeholders() {
+            let index: Option<usize> = match &piece.kind {
+                PieceKind::Placeholder {
+                    argument: Some(argument),
+                    ..
+                } => argument.parse().ok(),
+                _ => {
+                    next += 1;
+                    Some(next - 1)
+                }
+            };
+            if index.is_some_and(|index| index >= given) {
+                return Err(LexError {
+                    message: format!(
+                        "Placeholder has no matching argument: {} argument(s) given",
+                        given
+                    ),
+                    span: piece.span,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// # Lex Format Calls
+///
+/// Finds the calls to the [`FORMAT_FUNCTIONS`] whose first argument is a string literal and
+/// lexes that literal as a format string. Method calls with the same name, i.e.
+/// `file.print("{")`, are left alone.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens of a file.
+/// * `src` - The source the tokens were lexed from.
+/// * `errors` - Where to put the errors found in the format strings.
+///
+/// # Returns
+///
+/// * `Vec<FormatString>` - The format strings in source order.
+pub fn lex_format_calls(
+    tokens: &[Token],
+    src: &str,
+    errors: &mut Vec<LexError>,
+) -> Vec<FormatString> {
+    let mut format_strings: Vec<FormatString> = Vec::new();
+    let significant: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| token.tok_type != TokenType::TokNewline)
+        .collect();
+    for (index, window) in significant.windows(3).enumerate() {
+        // `x.print("...")` is a method call, not a call to the format function
+        let is_method: bool = index > 0 && significant[index - 1].tok_type == TokenType::TokDot;
+        let is_format_call: bool = !is_method
+            && window[0].tok_type == TokenType::TokIdentifier
+            && window[0]
+                .value
+                .as_deref()
+                .is_some_and(|name| FORMAT_FUNCTIONS.contains(&name))
+            && window[1].tok_type == TokenType::TokLeftParen;
+        if is_format_call && let Some(format_string) = FormatString::lex(window[2], src, errors) {
+            format_strings.push(format_string);
+        }
+    }
+    format_strings
+}
+
+/// # Check Format Calls
+///
+/// Checks that every call to one of the [`FORMAT_FUNCTIONS`] passes as many arguments as its
+/// format string expects, i.e. that `println("{} {}", 1)` is reported.
+///
+/// # Arguments
+///
+/// * `items` - The parsed items of a file.
+/// * `format_strings` - The format strings returned by [`lex_format_calls`] for the same file.
+///
+/// # Returns
+///
+/// * `Vec<LexError>` - The calls with too few or too many arguments.
+pub fn check_format_calls(items: &[Item], format_strings: &[FormatString]) -> Vec<LexError> {
+    let mut errors: Vec<LexError> = Vec::new();
+    let mut stmts: Vec<&Stmt> = items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Fun(fun) => Some(&fun.body.stmts),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let mut nodes: Vec<&Node> = Vec::new();
+    while let Some(stmt) = stmts.pop() {
+        let (stmt_nodes, children) = stmt.children();
+        nodes.extend(stmt_nodes);
+        stmts.extend(children);
+    }
+    while let Some(node) = nodes.pop() {
+        nodes.extend(node.children());
+        let NodeKind::Call { callee, args } = &node.kind else {
+            continue;
+        };
+        let is_format_call: bool = matches!(
+            &callee.kind,
+            NodeKind::Ident(name) if FORMAT_FUNCTIONS.contains(&name.as_str())
+        );
+        if let (true, Some(first)) = (is_format_call, args.first())
+            && let Some(format_string) = format_strings
+                .iter()
+                .find(|format_string| format_string.span == first.span)
+            && let Err(error) = format_string.check_arg_count(args.len() - 1)
+        {
+            errors.push(error);
+        }
+    }
+    errors.sort_by_key(|error| error.span.start);
+    errors
+}
+
+/// # Lex Chars
+///
+/// Splits the characters of a string literal into format pieces.
+///
+/// # Arguments
+///
+/// * `chars` - The decoded characters, each with the span of its source.
+/// * `span` - The span of the whole string literal.
+/// * `errors` - Where to put the errors found in the format string.
+fn lex_chars(chars: &[SourceChar], span: Span, errors: &mut Vec<LexError>) -> FormatString {
+    let mut pieces: Vec<FormatPiece> = Vec::new();
+    let mut text: String = String::new();
+    let mut text_start: usize = 0;
+    let mut index: usize = 0;
+    while index < chars.len() {
+        let ch: char = chars[index].ch;
+        let next: Option<char> = chars.get(index + 1).map(|next| next.ch);
+        if (ch == '{' && next == Some('{'))
+            || (ch == '}' && next == Some('}'))
+            || (ch == '%' && next == Some('%'))
+        {
+            if text.is_empty() {
+                text_start = index;
+            }
+            text.push(ch);
+            index += 2;
+            continue;
+        }
+        if ch != '{' && ch != '}' && ch != '%' {
+            if text.is_empty() {
+                text_start = index;
+            }
+            text.push(ch);
+            index += 1;
+            continue;
+        }
+        if !text.is_empty() {
+            pieces.push(FormatPiece {
+                kind: PieceKind::Text(std::mem::take(&mut text)),
+                span: span_of(&chars[text_start..index]),
+            });
+        }
+        let start: usize = index;
+        match ch {
+            '{' => {
+                let Some(length) = chars[start..].iter().position(|next| next.ch == '}') else {
+                    errors.push(LexError {
+                        message: "Unterminated placeholder: expected `}`".to_string(),
+                        span: span_of(&chars[start..]),
+                    });
+                    break;
+                };
+                index = start + length + 1;
+                let inner: String = chars[start + 1..index - 1].iter().map(|c| c.ch).collect();
+                match lex_placeholder(&inner) {
+                    Ok(kind) => pieces.push(FormatPiece {
+                        kind,
+                        span: span_of(&chars[start..index]),
+                    }),
+                    Err(message) => errors.push(LexError {
+                        message,
+                        span: span_of(&chars[start..index]),
+                    }),
+                }
+            }
+            '}' => {
+                index += 1;
+                errors.push(LexError {
+                    message: "Unmatched `}` in format string: use `}}` for a literal brace"
+                        .to_string(),
+                    span: span_of(&chars[start..index]),
+                });
+            }
+            _ => {
+                index += 1;
+                let mut spec: FormatSpec = FormatSpec {
+                    width: lex_count(chars, &mut index),
+                    ..FormatSpec::default()
+                };
+                if chars.get(index).map(|next| next.ch) == Some('.') {
+                    index += 1;
+                    spec.precision = lex_count(chars, &mut index);
+                }
+                match chars.get(index).map(|next| next.ch) {
+                    Some(conversion) if CONVERSIONS.contains(&conversion) => {
+                        index += 1;
+                        pieces.push(FormatPiece {
+                            kind: PieceKind::Specifier { conversion, spec },
+                            span: span_of(&chars[start..index]),
+                        });
+                    }
+                    conversion => {
+                        index = (index + 1).min(chars.len());
+                        let expected: &str = "expected `%d`, `%s`, `%f` or `%%`";
+                        errors.push(LexError {
+                            message: match conversion {
+                                Some(conversion) => format!(
+                                    "Unknown format specifier `%{}`: {}",
+                                    conversion, expected
+                                ),
+                                None => format!("Incomplete format specifier: {}", expected),
+                            },
+                            span: span_of(&chars[start..index]),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(FormatPiece {
+            kind: PieceKind::Text(text),
+            span: span_of(&chars[text_start..]),
+        });
+    }
+
+    let first_placeholder: Option<&FormatPiece> = pieces
+        .iter()
+        .find(|piece| matches!(piece.kind, PieceKind::Placeholder { .. }));
+    let first_specifier: Option<&FormatPiece> = pieces
+        .iter()
+        .find(|piece| matches!(piece.kind, PieceKind::Specifier { .. }));
+    if let (Some(placeholder), Some(specifier)) = (first_placeholder, first_specifier) {
+        errors.push(LexError {
+            message: "Format string mixes `{}` placeholders and `%` specifiers".to_string(),
+            span: if placeholder.span.start > specifier.span.start {
+                placeholder.span
+            } else {
+                specifier.span
+            },
+        });
+    }
+
+    FormatString { pieces, span }
+}
+
+/// # Lex Placeholder
+///
+/// Parses the text between the braces of a placeholder, i.e. `name:>8.2` in `{name:>8.2}`.
+fn lex_placeholder(inner: &str) -> Result<PieceKind, String> {
+    let (argument, spec_text): (&str, Option<&str>) = match inner.split_once(':') {
+        Some((argument, spec_text)) => (argument, Some(spec_text)),
+        None => (inner, None),
+    };
+    let argument: Option<String> = if argument.is_empty() {
+        None
+    } else if argument.chars().all(|ch| ch.is_ascii_digit())
+        || (argument.starts_with(|ch: char| ch == '_' || ch.is_alphabetic())
+            && argument.chars().all(|ch| ch == '_' || ch.is_alphanumeric()))
+    {
+        Some(argument.to_string())
+    } else {
+        return Err(format!(
+            "Invalid placeholder argument `{}`: expected a position or a name",
+            argument
+        ));
+    };
+    let Some(spec_text) = spec_text else {
+        return Ok(PieceKind::Placeholder {
+            argument,
+            spec: FormatSpec::default(),
+        });
+    };
+
+    let chars: Vec<char> = spec_text.chars().collect();
+    let align_of = |ch: Option<&char>| match ch {
+        Some('<') => Some(Align::Left),
+        Some('>') => Some(Align::Right),
+        Some('^') => Some(Align::Center),
+        _ => None,
+    };
+    let mut spec: FormatSpec = FormatSpec::default();
+    let mut index: usize = 0;
+    if let Some(align) = align_of(chars.get(1)) {
+        spec.fill = Some(chars[0]);
+        spec.align = Some(align);
+        index = 2;
+    } else if let Some(align) = align_of(chars.first()) {
+        spec.align = Some(align);
+        index = 1;
+    }
+    spec.width = lex_digits(&chars, &mut index);
+    if chars.get(index) == Some(&'.') {
+        index += 1;
+        spec.precision = lex_digits(&chars, &mut index);
+        if spec.precision.is_none() {
+            return Err(format!(
+                "Invalid format spec `:{}`: expected digits after `.`",
+                spec_text
+            ));
+        }
+    }
+    if index < chars.len() {
+        return Err(format!(
+            "Invalid format spec `:{}`: expected `[[fill]align][width][.precision]`",
+            spec_text
+        ));
+    }
+    Ok(PieceKind::Placeholder { argument, spec })
+}
+
+/// # Lex Digits
+///
+/// Consumes a run of ASCII digits from `chars` starting at `index` and returns its value.
+fn lex_digits(chars: &[char], index: &mut usize) -> Option<usize> {
+    let start: usize = *index;
+    while chars.get(*index).is_some_and(|ch| ch.is_ascii_digit()) {
+        *index += 1;
+    }
+    chars[start..*index].iter().collect::<String>().parse().ok()
+}
+
+/// # Lex Count
+///
+/// [`lex_digits`] over the characters of a string literal.
+fn lex_count(chars: &[SourceChar], index: &mut usize) -> Option<usize> {
+    let start: usize = *index;
+    while chars
+        .get(*index)
+        .is_some_and(|source| source.ch.is_ascii_digit())
+    {
+        *index += 1;
+    }
+    chars[start..*index]
+        .iter()
+        .map(|source| source.ch)
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// # Source Chars
+///
+/// Decodes a string literal token into its characters, each with the span of the source it was
+/// written as. The decoded characters come from the token's value; the source is only walked to
+/// find where each one starts and ends.
+///
+/// # Returns
+///
+/// * `Option<Vec<SourceChar>>` - The characters, or `None` if the token has no value.
+fn source_chars(token: &Token, src: &str) -> Option<Vec<SourceChar>> {
+    let raw: &str = token.span.text(src);
+    let is_raw: bool = raw.starts_with('r');
+    let body_start: usize = raw.find('"')? + 1;
+    let body_end: usize = raw.rfind('"')?.max(body_start);
+    let body: Vec<char> = raw[body_start..body_end].chars().collect();
+
+    let mut values = token.value.as_deref()?.chars();
+    let mut chars: Vec<SourceChar> = Vec::new();
+    let mut offset: usize = token.span.start + body_start;
+    let mut line: u32 = token.span.line;
+    let mut column: u32 = token.span.column + raw[..body_start].chars().count() as u32;
+    let mut index: usize = 0;
+    while index < body.len() {
+        let (start, start_line, start_column): (usize, u32, u32) = (offset, line, column);
+        let length: usize = if is_raw || body[index] != '\\' {
+            1
+        } else {
+            match body.get(index + 1) {
+                Some('\n') => {
+                    // A line continuation: the newline and the indentation after it are dropped
+                    let mut length: usize = 2;
+                    while body
+                        .get(index + length)
+                        .is_some_and(|ch| ch.is_whitespace())
+                    {
+                        length += 1;
+                    }
+                    length
+                }
+                Some('x') => {
+                    let digits: usize = body[index + 2..]
+                        .iter()
+                        .take(2)
+                        .take_while(|ch| ch.is_ascii_hexdigit())
+                        .count();
+                    2 + digits
+                }
+                Some('u') => match body[index..].iter().position(|&ch| ch == '}') {
+                    Some(close) => close + 1,
+                    None => body.len() - index,
+                },
+                _ => 2,
+            }
+        };
+        for &ch in &body[index..(index + length).min(body.len())] {
+            offset += ch.len_utf8();
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        let is_continuation: bool =
+            !is_raw && body[index] == '\\' && body.get(index + 1) == Some(&'\n');
+        index += length;
+        if is_continuation {
+            continue;
+        }
+        chars.push(SourceChar {
+            ch: values.next()?,
+            span: Span::new(token.span.file_id, start, offset, start_line, start_column),
+        });
+    }
+    Some(chars)
+}
+
+/// # Span Of
+///
+/// The span covering a run of string literal characters.
+fn span_of(chars: &[SourceChar]) -> Span {
+    match (chars.first(), chars.last()) {
+        (Some(first), Some(last)) => first.span.to(last.span),
+        _ => Span::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::parser::Parser;
+    use crate::tokenizer::Tokenizer;
+
+    fn lex(src: &str) -> (Vec<FormatString>, Vec<LexError>) {
+        let c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        let tokens: Vec<Token> = tokenizer.tokenize();
+        assert!(tokenizer.errors().is_empty());
+        let mut errors: Vec<LexError> = Vec::new();
+        let format_strings: Vec<FormatString> = lex_format_calls(&tokens, src, &mut errors);
+        (format_strings, errors)
+    }
+
+    fn pieces(src: &str) -> Vec<(PieceKind, String)> {
+        let (format_strings, errors) = lex(src);
+        assert_eq!(errors, vec![]);
+        format_strings[0]
+            .pieces
+            .iter()
+            .map(|piece| (piece.kind.clone(), piece.span.text(src).to_string()))
+            .collect()
+    }
+
+    fn placeholder(argument: Option<&str>, spec: FormatSpec) -> PieceKind {
+        PieceKind::Placeholder {
+            argument: argument.map(str::to_string),
+            spec,
+        }
+    }
+
+    #[test]
+    fn rust_style_placeholders() {
+        assert_eq!(
+            pieces("print(\"x = {}, {name} and {:>8.2}!\", x, y);"),
+            vec![
+                (PieceKind::Text("x = ".to_string()), "x = ".to_string()),
+                (placeholder(None, FormatSpec::default()), "{}".to_string()),
+                (PieceKind::Text(", ".to_string()), ", ".to_string()),
+                (
+                    placeholder(Some("name"), FormatSpec::default()),
+                    "{name}".to_string()
+                ),
+                (PieceKind::Text(" and ".to_string()), " and ".to_string()),
+                (
+                    placeholder(
+                        None,
+                        FormatSpec {
+                            align: Some(Align::Right),
+                            width: Some(8),
+                            precision: Some(2),
+                            ..FormatSpec::default()
+                        }
+                    ),
+                    "{:>8.2}".to_string()
+                ),
+                (PieceKind::Text("!".to_string()), "!".to_string()),
+            ]
+        );
+        assert_eq!(
+            pieces("println(\"{0:*^5}\", 1);")[0].0,
+            placeholder(
+                Some("0"),
+                FormatSpec {
+                    fill: Some('*'),
+                    align: Some(Align::Center),
+                    width: Some(5),
+                    precision: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn c_style_specifiers() {
+        assert_eq!(
+            pieces("print(\"%d%% of %s: %8.2f\", a, b, c);"),
+            vec![
+                (
+                    PieceKind::Specifier {
+                        conversion: 'd',
+                        spec: FormatSpec::default()
+                    },
+                    "%d".to_string()
+                ),
+                (PieceKind::Text("% of ".to_string()), "%% of ".to_string()),
+                (
+                    PieceKind::Specifier {
+                        conversion: 's',
+                        spec: FormatSpec::default()
+                    },
+                    "%s".to_string()
+                ),
+                (PieceKind::Text(": ".to_string()), ": ".to_string()),
+                (
+                    PieceKind::Specifier {
+                        conversion: 'f',
+                        spec: FormatSpec {
+                            width: Some(8),
+                            precision: Some(2),
+                            ..FormatSpec::default()
+                        }
+                    },
+                    "%8.2f".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_account_for_escapes() {
+        let src: &str = "println(\"\\t\\u{e9}{{\\\n    {}\");";
+        assert_eq!(
+            pieces(src),
+            vec![
+                (
+                    PieceKind::Text("\t\u{e9}{".to_string()),
+                    "\\t\\u{e9}{{".to_string()
+                ),
+                (placeholder(None, FormatSpec::default()), "{}".to_string()),
+            ]
+        );
+        let (format_strings, _) = lex(src);
+        let span: Span = format_strings[0].pieces[1].span;
+        assert_eq!((span.line, span.column), (2, 5));
+    }
+
+    #[test]
+    fn only_format_calls_are_lexed() {
+        let (format_strings, errors) =
+            lex("let s = \"{\"; print(s); file.print(\"{\"); println(\n\"{}\", 1);");
+        assert_eq!(errors, vec![]);
+        assert_eq!(format_strings.len(), 1);
+        assert_eq!(format_strings[0].span.line, 2);
+    }
+
+    #[test]
+    fn format_errors_point_at_the_placeholder() {
+        let src: &str = "print(\"a { b } %q {:x} %d {}\");";
+        let (_, errors) = lex(src);
+        let errors: Vec<(String, &str)> = errors
+            .iter()
+            .map(|error| (error.message.clone(), error.span.text(src)))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "Invalid placeholder argument ` b `: expected a position or a name".to_string(),
+                    "{ b }"
+                ),
+                (
+                    "Unknown format specifier `%q`: expected `%d`, `%s`, `%f` or `%%`".to_string(),
+                    "%q"
+                ),
+                (
+                    "Invalid format spec `:x`: expected `[[fill]align][width][.precision]`"
+                        .to_string(),
+                    "{:x}"
+                ),
+                (
+                    "Format string mixes `{}` placeholders and `%` specifiers".to_string(),
+                    "{}"
+                ),
+            ]
+        );
+
+        let (_, errors) = lex("print(\"{\"); print(\"}\");");
+        let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unterminated placeholder: expected `}`",
+                "Unmatched `}` in format string: use `}}` for a literal brace",
+            ]
+        );
+    }
+
+    #[test]
+    fn arg_count_is_checked() {
+        let src: &str = "print(\"{} {1} {name} {}\");";
+        let (format_strings, _) = lex(src);
+        let format_string: &FormatString = &format_strings[0];
+        assert_eq!(format_string.arg_count(), 2);
+        assert_eq!(format_string.check_arg_count(2), Ok(()));
+        let error: LexError = format_string.check_arg_count(1).unwrap_err();
+        assert_eq!(error.span.text(src), "{1}");
+        let error: LexError = format_string.check_arg_count(3).unwrap_err();
+        assert_eq!(error.span, format_string.span);
+    }
+
+    #[test]
+    fn format_calls_are_checked() {
+        let src: &str = "fun main() {\n\
+                         println(\"{} {}\", 1);\n\
+                         if ok { print(\"%d\", 1, 2); }\n\
+                         println(\"{name} {0}\", name);\n\
+                         }";
+        let (format_strings, _) = lex(src);
+        let c_settings: CSettings = CSettings::default();
+        let tokens: Vec<Token> = Tokenizer::new(src.to_string(), &c_settings).tokenize();
+        let mut parser: Parser = Parser::new(tokens, src);
+        let items: Vec<Item> = parser.parse();
+        assert_eq!(parser.errors(), &[]);
+        let errors: Vec<(String, u32)> = check_format_calls(&items, &format_strings)
+            .into_iter()
+            .map(|error| (error.message, error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "Placeholder has no matching argument: 1 argument(s) given".to_string(),
+                    2
+                ),
+                (
+                    "2 argument(s) given but the format string only uses 1".to_string(),
+                    3
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1390 @@
+// Running programs
+use crate::format_string::{Align, FormatString, PieceKind};
+use crate::modules::{Module, Symbol};
+use crate::parser::{
+    AssignOp, Block, Catch, Fun, Item, ItemKind, Node, NodeKind, Operator, PostfixOp, Stmt,
+    StmtKind, UnaryOp,
+};
+use crate::span::{FileId, Span};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+/// The built in error type. The interpreter throws its variants itself, i.e.
+/// `Error::DivisionByZero` for `x / 0`.
+const BUILTIN_ERROR: &str = "Error";
+
+/// How deep calls can nest before `Error::StackOverflow` is thrown.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// The stack size of the thread programs run on. Every call takes a few nested Rust calls, so
+/// the stack of a normal thread would overflow long before `MAX_CALL_DEPTH`.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// An item of a loaded module: the `FileId` of the module and the index of the item in it.
+type ItemId = (FileId, usize);
+
+/// A variable. A reference to a variable shares its slot, so writing through the reference
+/// changes the variable.
+type Slot = Rc<RefCell<Value>>;
+
+/// The result of running part of a function.
+type Eval<T> = Result<T, Unwind>;
+
+/// # Run
+///
+/// Runs `main` of the file being compiled on a thread of its own, with the [`Interpreter`].
+///
+/// # Arguments
+///
+/// * `modules` - The modules from `modules::Loader::modules`, after `desugar::desugar` and
+///   without check errors.
+/// * `out` - Where the program prints to, i.e. stdout.
+///
+/// # Returns
+///
+/// * `Result<Option<i128>, RunError>` - The number `main` returned, if it returned one, or why
+///   the program stopped, i.e. an error that nothing caught.
+///
+/// # Usage
+///
+/// ```
+/// let result: Result<Option<i128>, interpret::RunError> =
+///     interpret::run(loader.modules(), &mut std::io::stdout());
+/// ```
+pub fn run(modules: &[Module], out: &mut (dyn Write + Send)) -> Result<Option<i128>, RunError> {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || {
+                let result: Result<Value, RunError> = Interpreter::new(modules, out).run();
+                result.map(|value| match value {
+                    Value::Int(code) => Some(code),
+                    _ => None,
+                })
+            })
+            .expect("zinc: fatal error: Failed to start the program thread.")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// # Value
+///
+/// A value of a running program.
+#[derive(PartialEq, Debug, Clone)]
+enum Value {
+    Int(i128), // Every integer type, whatever its suffix
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    Null,
+    Void, // Returned by functions without a return value
+    Tuple(Vec<Value>),
+    Array(Vec<Value>),
+    Struct(Vec<(String, Value)>), // The fields in the order they were given
+    Variant {
+        enum_id: Option<ItemId>, // The declaring enum, `None` for the built in `Error`
+        enum_name: String,
+        name: String,
+    },
+    Ref(Slot), // '&x'
+}
+
+impl Value {
+    /// # Type Name
+    ///
+    /// Names the kind of value for error messages, i.e. `string` or `IoError`.
+    fn type_name(&self) -> &str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::Str(_) => "string",
+            Value::Null => "null",
+            Value::Void => "void",
+            Value::Tuple(_) => "tuple",
+            Value::Array(_) => "array",
+            Value::Struct(_) => "struct",
+            Value::Variant { enum_name, .. } => enum_name,
+            Value::Ref(_) => "reference",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Strings inside other values are quoted, so `("a, b", c)` can be told apart
+        let nested = |value: &Value| match value {
+            Value::Str(text) => format!("{:?}", text),
+            Value::Char(ch) => format!("{:?}", ch),
+            value => value.to_string(),
+        };
+        let join = |values: &[Value]| values.iter().map(nested).collect::<Vec<_>>().join(", ");
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+            Value::Null => write!(f, "Null"),
+            Value::Void => write!(f, "void"),
+            Value::Tuple(values) => write!(f, "({})", join(values)),
+            Value::Array(values) => write!(f, "[{}]", join(values)),
+            Value::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{} = {}", name, nested(value)))
+                    .collect();
+                write!(f, "{{ {} }}", fields.join(", "))
+            }
+            Value::Variant {
+                enum_name, name, ..
+            } => write!(f, "{}::{}", enum_name, name),
+            Value::Ref(slot) => write!(f, "{}", slot.borrow()),
+        }
+    }
+}
+
+/// # Run Error
+///
+/// A problem that stops a running program: an error that leaves `main` without being caught,
+/// or code the interpreter can't run, i.e. a call to a function imported from a C header.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RunError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[Line {}, Column {}] {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+/// Why running stopped before the end of a function.
+enum Unwind {
+    Throw(Value, Span), // A thrown error on its way up to a `catch`, and where it was thrown
+    Fault(RunError),    // Stops the program; `catch` and `finally` don't see it
+}
+
+/// How a statement finished.
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// The variables of a function call.
+struct Frame {
+    file_id: FileId, // The module the function is declared in, whose names it uses
+    scopes: Vec<HashMap<String, Slot>>, // Innermost block last
+}
+
+/// # Interpreter
+///
+/// Runs the desugared and checked items of every loaded module by walking their syntax trees,
+/// starting from `main` in the file being compiled. Names are looked up in the namespace of
+/// the module a function is declared in, like the checker does.
+///
+/// A `throw` unwinds through the calls until a `catch` that handles the error, running every
+/// `finally` block on the way. See `docs/errors.md`.
+struct Interpreter<'a> {
+    modules: &'a [Module],
+    out: &'a mut dyn Write, // Where `print` and `println` write to
+    frames: Vec<Frame>,     // The calls being run, innermost last
+}
+
+impl<'a> Interpreter<'a> {
+    /// # New
+    ///
+    /// Create a new interpreter for the loaded modules.
+    ///
+    /// # Arguments
+    ///
+    /// * `modules` - The modules to run, as for [`run`].
+    /// * `out` - Where the program prints to.
+    fn new(modules: &'a [Module], out: &'a mut dyn Write) -> Self {
+        Interpreter {
+            modules,
+            out,
+            frames: Vec::new(),
+        }
+    }
+
+    /// # Run
+    ///
+    /// Runs `main` of the file being compiled. If `main` takes arguments it gets an empty
+    /// array.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, RunError>` - What `main` returned, or why the program stopped.
+    fn run(&mut self) -> Result<Value, RunError> {
+        let main: Option<&'a Fun> = self.modules.first().and_then(|root| {
+            root.items.iter().find_map(|item| match &item.kind {
+                ItemKind::Fun(fun) if fun.name.text == "main" => Some(fun),
+                _ => None,
+            })
+        });
+        let Some(main) = main else {
+            return Err(RunError {
+                message: "There is no `main` function to run".to_string(),
+                span: Span::default(),
+            });
+        };
+        let args: Vec<Value> = main
+            .params
+            .iter()
+            .map(|_| Value::Array(Vec::new()))
+            .collect();
+        match self.call(FileId::default(), main, args, main.name.span) {
+            Ok(value) => Ok(value),
+            Err(Unwind::Throw(value, span)) => Err(RunError {
+                message: format!("Uncaught error `{}` ended the program", value),
+                span,
+            }),
+            Err(Unwind::Fault(error)) => Err(error),
+        }
+    }
+
+    /// # Call
+    ///
+    /// Runs a function declared in `file_id` with the values of its arguments.
+    fn call(&mut self, file_id: FileId, fun: &'a Fun, args: Vec<Value>, span: Span) -> Eval<Value> {
+        if fun.is_extern {
+            return fault(
+                format!(
+                    "Can't run `{}`: functions imported from C headers can't be run yet",
+                    fun.name.text
+                ),
+                span,
+            );
+        }
+        if args.len() != fun.params.len() {
+            return fault(
+                format!(
+                    "`{}` takes {} argument(s) but {} were given",
+                    fun.name.text,
+                    fun.params.len(),
+                    args.len()
+                ),
+                span,
+            );
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(builtin_error("StackOverflow", span));
+        }
+        let scope: HashMap<String, Slot> = fun
+            .params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (param.name.text.clone(), Rc::new(RefCell::new(arg))))
+            .collect();
+        self.frames.push(Frame {
+            file_id,
+            scopes: vec![scope],
+        });
+        let flow: Eval<Flow> = self.exec_block(&fun.body);
+        self.frames.pop();
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Value::Void),
+        }
+    }
+
+    /// # Exec Block
+    ///
+    /// Runs the statements of a block in a new scope, stopping at the first one that doesn't
+    /// finish normally.
+    fn exec_block(&mut self, block: &'a Block) -> Eval<Flow> {
+        self.frame().scopes.push(HashMap::new());
+        let mut flow: Eval<Flow> = Ok(Flow::Next);
+        for stmt in &block.stmts {
+            flow = self.exec(stmt);
+            if !matches!(flow, Ok(Flow::Next)) {
+                break;
+            }
+        }
+        self.frame().scopes.pop();
+        flow
+    }
+
+    /// # Exec
+    ///
+    /// Runs a statement.
+    fn exec(&mut self, stmt: &'a Stmt) -> Eval<Flow> {
+        match &stmt.kind {
+            StmtKind::Let { name, value, .. } => {
+                let value: Value = match value {
+                    Some(value) => self.eval(value)?,
+                    None => Value::Null,
+                };
+                self.declare(&name.text, value);
+                Ok(Flow::Next)
+            }
+            StmtKind::Expr(node) => {
+                self.eval(node)?;
+                Ok(Flow::Next)
+            }
+            StmtKind::Block(block) => self.exec_block(block),
+            StmtKind::If {
+                condition,
+                then_block,
+                else_branch,
+                ..
+            } => {
+                if self.condition(condition)? {
+                    self.exec_block(then_block)
+                } else if let Some(else_branch) = else_branch {
+                    self.exec(else_branch)
+                } else {
+                    Ok(Flow::Next)
+                }
+            }
+            StmtKind::While { condition, body } => {
+                while self.condition(condition)? {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            StmtKind::DoWhile { body, condition } => {
+                loop {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                    if !self.condition(condition)? {
+                        break;
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            StmtKind::For {
+                binding,
+                iterable,
+                body,
+            } => {
+                let values: Vec<Value> = match self.eval(iterable)? {
+                    Value::Array(values) => values,
+                    Value::Str(text) => text.chars().map(Value::Char).collect(),
+                    value => {
+                        return fault(
+                            format!("Can't loop over a `{}`", value.type_name()),
+                            iterable.span,
+                        );
+                    }
+                };
+                for value in values {
+                    self.frame().scopes.push(HashMap::new());
+                    self.declare(&binding.text, value);
+                    let flow: Eval<Flow> = self.exec_block(body);
+                    self.frame().scopes.pop();
+                    match flow? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            StmtKind::Break => Ok(Flow::Break),
+            StmtKind::Continue => Ok(Flow::Continue),
+            StmtKind::Return(value) => match value {
+                Some(value) => Ok(Flow::Return(self.eval(value)?)),
+                None => Ok(Flow::Return(Value::Void)),
+            },
+            StmtKind::Throw(value) => Err(Unwind::Throw(self.eval(value)?, stmt.span)),
+            StmtKind::Try {
+                body,
+                catches,
+                finally,
+            } => self.exec_try(body, catches, finally.as_ref()),
+            StmtKind::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                let value: Value = self.eval(value)?;
+                for case in cases {
+                    for pattern in &case.patterns {
+                        if self.matches(pattern, &value)? {
+                            return self.exec_block(&case.body);
+                        }
+                    }
+                }
+                match default {
+                    Some(default) => self.exec_block(default),
+                    None => Ok(Flow::Next),
+                }
+            }
+            StmtKind::Guarded { stmt, guard } => {
+                if self.condition(&guard.condition)? {
+                    self.exec(stmt)
+                } else {
+                    Ok(Flow::Next)
+                }
+            }
+        }
+    }
+
+    /// # Exec Try
+    ///
+    /// Runs a `try` statement. An error thrown in the body goes to the first `catch` that
+    /// handles its type, and keeps going up if none does. `finally` runs last, whatever the
+    /// body and the catch did, but a `return`, `break`, `continue` or `throw` in it replaces
+    /// what they did.
+    fn exec_try(
+        &mut self,
+        body: &'a Block,
+        catches: &'a [Catch],
+        finally: Option<&'a Block>,
+    ) -> Eval<Flow> {
+        let mut flow: Eval<Flow> = self.exec_block(body);
+        if let Err(Unwind::Throw(value, span)) = flow {
+            flow = match catches.iter().find(|catch| self.catches(catch, &value)) {
+                Some(catch) => {
+                    self.frame().scopes.push(HashMap::new());
+                    if let Some(binding) = &catch.binding {
+                        self.declare(&binding.name.text, value);
+                    }
+                    let flow: Eval<Flow> = self.exec_block(&catch.body);
+                    self.frame().scopes.pop();
+                    flow
+                }
+                None => Err(Unwind::Throw(value, span)),
+            };
+        }
+        match (finally, flow) {
+            (_, Err(Unwind::Fault(error))) => Err(Unwind::Fault(error)),
+            (Some(finally), flow) => match self.exec_block(finally)? {
+                Flow::Next => flow,
+                finally_flow => Ok(finally_flow),
+            },
+            (None, flow) => flow,
+        }
+    }
+
+    /// # Catches
+    ///
+    /// Checks whether a `catch` handles a thrown error. A `catch` without a binding, or with
+    /// the built in `Error` type, handles every error.
+    fn catches(&self, catch: &Catch, value: &Value) -> bool {
+        let Some(binding) = &catch.binding else {
+            return true;
+        };
+        match binding.ty.simple_name() {
+            Some(BUILTIN_ERROR) => true,
+            Some(name) => match (value, self.resolve(self.file_id(), name)) {
+                (Value::Variant { enum_id, .. }, Some((id, _))) => *enum_id == Some(id),
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    /// # Matches
+    ///
+    /// Checks whether a switched value matches a `case` pattern: a range, or a value it
+    /// equals.
+    fn matches(&mut self, pattern: &'a Node, value: &Value) -> Eval<bool> {
+        if let NodeKind::Binary {
+            left,
+            op: op @ (Operator::Range | Operator::RangeInclusive),
+            right,
+        } = &pattern.kind
+        {
+            let (Value::Int(start), Value::Int(end), Value::Int(value)) =
+                (self.eval(left)?, self.eval(right)?, value)
+            else {
+                return Ok(false);
+            };
+            return Ok(start <= *value
+                && (*value < end || (*op == Operator::RangeInclusive && *value == end)));
+        }
+        Ok(self.eval(pattern)? == *value)
+    }
+
+    /// # Condition
+    ///
+    /// Evaluates the condition of an `if` or a loop.
+    fn condition(&mut self, node: &'a Node) -> Eval<bool> {
+        match self.eval(node)? {
+            Value::Bool(value) => Ok(value),
+            value => fault(
+                format!(
+                    "Expected a `bool` condition, found a `{}`",
+                    value.type_name()
+                ),
+                node.span,
+            ),
+        }
+    }
+
+    /// # Eval
+    ///
+    /// Evaluates an expression.
+    fn eval(&mut self, node: &'a Node) -> Eval<Value> {
+        match &node.kind {
+            NodeKind::Int(value, _) => match i128::try_from(*value) {
+                Ok(value) => Ok(Value::Int(value)),
+                Err(_) => fault("The number is too large to run".to_string(), node.span),
+            },
+            NodeKind::Float(value, _) => Ok(Value::Float(*value)),
+            NodeKind::Bool(value) => Ok(Value::Bool(*value)),
+            NodeKind::Char(value) => Ok(Value::Char(*value)),
+            NodeKind::Str(value) => Ok(Value::Str(value.clone())),
+            NodeKind::Null => Ok(Value::Null),
+            NodeKind::Ident(name) => {
+                if let Some(slot) = self.variable(name) {
+                    return Ok(slot.borrow().clone());
+                }
+                match self.resolve(self.file_id(), name) {
+                    Some(((file_id, _), ItemKind::Const { value, .. })) => {
+                        self.frames.push(Frame {
+                            file_id,
+                            scopes: Vec::new(),
+                        });
+                        let value: Eval<Value> = self.eval(value);
+                        self.frames.pop();
+                        value
+                    }
+                    _ => fault(format!("Unknown variable `{}`", name), node.span),
+                }
+            }
+            NodeKind::Path(path) if path.len() == 2 => self.variant(path, node.span),
+            NodeKind::Tuple(items) => Ok(Value::Tuple(self.eval_all(items)?)),
+            NodeKind::Array(items) => {
+                // Ranges are spread out, so `[2..5]` is `[2, 3, 4]`
+                let mut values: Vec<Value> = Vec::new();
+                for item in items {
+                    match (&item.kind, self.eval(item)?) {
+                        (
+                            NodeKind::Binary {
+                                op: Operator::Range | Operator::RangeInclusive,
+                                ..
+                            },
+                            Value::Array(range),
+                        ) => values.extend(range),
+                        (_, value) => values.push(value),
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            NodeKind::StructLiteral(fields) => {
+                let mut values: Vec<(String, Value)> = Vec::new();
+                for field in fields {
+                    values.push((field.name.text.clone(), self.eval(&field.value)?));
+                }
+                Ok(Value::Struct(values))
+            }
+            NodeKind::Unary {
+                op: UnaryOp::Ref,
+                operand,
+            } => {
+                // A reference to a variable shares its slot, anything else gets a new one
+                let slot: Option<Slot> = match &operand.kind {
+                    NodeKind::Ident(name) => self.variable(name),
+                    _ => None,
+                };
+                match slot {
+                    Some(slot) => Ok(Value::Ref(slot)),
+                    None => Ok(Value::Ref(Rc::new(RefCell::new(self.eval(operand)?)))),
+                }
+            }
+            NodeKind::Unary { op, operand } => {
+                let value: Value = self.eval(operand)?;
+                unary(*op, value, node.span)
+            }
+            NodeKind::Postfix { op, operand } => {
+                let old: Value = self.eval(operand)?;
+                let op: Operator = match op {
+                    PostfixOp::Increment => Operator::Plus,
+                    PostfixOp::Decrement => Operator::Minus,
+                };
+                let new: Value = binary(op, old.clone(), Value::Int(1), node.span)?;
+                self.assign(operand, new)?;
+                Ok(old)
+            }
+            NodeKind::Binary { left, op, right } => {
+                let left_value: Value = self.eval(left)?;
+                match (op, &left_value) {
+                    (Operator::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                    (Operator::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                let right_value: Value = self.eval(right)?;
+                binary(*op, left_value, right_value, node.span)
+            }
+            NodeKind::Assign { target, op, value } => {
+                let value: Value = self.eval(value)?;
+                let value: Value = match (op, assign_operator(*op)) {
+                    (AssignOp::BitNot, _) => unary(UnaryOp::BitNot, value, node.span)?,
+                    (_, Some(op)) => binary(op, self.eval(target)?, value, node.span)?,
+                    (_, None) => value,
+                };
+                self.assign(target, value.clone())?;
+                Ok(value)
+            }
+            NodeKind::Ternary {
+                condition,
+                then_value,
+                else_value,
+            } => {
+                if self.condition(condition)? {
+                    self.eval(then_value)
+                } else {
+                    self.eval(else_value)
+                }
+            }
+            NodeKind::Call { callee, args } => self.eval_call(callee, args, node.span),
+            NodeKind::Index { base, index } => {
+                let (base, index) = (self.eval(base)?, self.eval(index)?);
+                let items: Vec<Value> = match base {
+                    Value::Array(values) | Value::Tuple(values) => values,
+                    Value::Str(text) => text.chars().map(Value::Char).collect(),
+                    value => {
+                        return fault(
+                            format!("Can't index into a `{}`", value.type_name()),
+                            node.span,
+                        );
+                    }
+                };
+                match index {
+                    Value::Int(index) => usize::try_from(index)
+                        .ok()
+                        .and_then(|index| items.into_iter().nth(index))
+                        .ok_or_else(|| builtin_error("IndexOutOfBounds", node.span)),
+                    value => fault(
+                        format!("Expected an integer index, found a `{}`", value.type_name()),
+                        node.span,
+                    ),
+                }
+            }
+            NodeKind::Field { base, name } => {
+                let mut value: Value = self.eval(base)?;
+                // Fields are read through references, i.e. of a `&Person` parameter
+                while let Value::Ref(slot) = value {
+                    value = slot.borrow().clone();
+                }
+                field(&mut value, name, node.span).map(|value| value.clone())
+            }
+            NodeKind::Path(_) | NodeKind::Macro(_) | NodeKind::Error => {
+                fault("Can't run this expression yet".to_string(), node.span)
+            }
+        }
+    }
+
+    /// # Eval All
+    ///
+    /// Evaluates expressions in order.
+    fn eval_all(&mut self, nodes: &'a [Node]) -> Eval<Vec<Value>> {
+        nodes.iter().map(|node| self.eval(node)).collect()
+    }
+
+    /// # Eval Call
+    ///
+    /// Calls a function by its name, i.e. `add(1, 2)`, or a compile time function by its macro,
+    /// i.e. `@get_datetime()`.
+    fn eval_call(&mut self, callee: &'a Node, args: &'a [Node], span: Span) -> Eval<Value> {
+        let name: &str = match &callee.kind {
+            NodeKind::Ident(name) | NodeKind::Macro(name) => name,
+            NodeKind::Path(path) => {
+                return fault(
+                    format!("Unknown function `{}`", path.join("::")),
+                    callee.span,
+                );
+            }
+            NodeKind::Field { name, .. } => {
+                return fault(
+                    format!("Can't run method calls like `.{}()` yet", name),
+                    callee.span,
+                );
+            }
+            _ => return fault("Can't call this expression".to_string(), callee.span),
+        };
+        match self.modules[self.file_id().0 as usize].scope.get(name) {
+            Some(Symbol::Std(std_name)) => self.call_std(std_name, args, span),
+            Some(Symbol::Item { file_id, index }) => {
+                let item: &'a Item = &self.modules[file_id.0 as usize].items[*index];
+                let ItemKind::Fun(fun) = &item.kind else {
+                    return fault(format!("`{}` is not a function", name), callee.span);
+                };
+                let args: Vec<Value> = self.eval_all(args)?;
+                self.call(*file_id, fun, args, span)
+            }
+            None => fault(format!("Unknown function `{}`", name), callee.span),
+        }
+    }
+
+    /// # Call Std
+    ///
+    /// Calls a function of the standard library: `print` or `println`. If the first argument
+    /// is a string literal it is a format string for the others.
+    fn call_std(&mut self, name: &str, args: &'a [Node], span: Span) -> Eval<Value> {
+        let mut text: String = match args.split_first() {
+            Some((
+                Node {
+                    kind: NodeKind::Str(format),
+                    span,
+                },
+                args,
+            )) => {
+                let values: Vec<Value> = self.eval_all(args)?;
+                self.format(&FormatString::from_value(format, *span), &values)?
+            }
+            _ => {
+                let values: Vec<String> =
+                    self.eval_all(args)?.iter().map(Value::to_string).collect();
+                values.join(" ")
+            }
+        };
+        if name == "println" {
+            text.push('\n');
+        }
+        match self.out.write_all(text.as_bytes()) {
+            Ok(()) => Ok(Value::Void),
+            Err(error) => fault(format!("Failed to print: {}", error), span),
+        }
+    }
+
+    /// # Format
+    ///
+    /// Prints values into a format string: `{}` and `%d` take the next value, `{0}` takes the
+    /// first one and `{name}` prints the variable `name`.
+    fn format(&self, format: &FormatString, values: &[Value]) -> Eval<String> {
+        let mut text: String = String::new();
+        let mut next: usize = 0;
+        let mut argument = |index: Option<usize>, span: Span| -> Eval<Value> {
+            let index: usize = index.unwrap_or_else(|| {
+                next += 1;
+                next - 1
+            });
+            match values.get(index) {
+                Some(value) => Ok(value.clone()),
+                None => fault("Placeholder has no matching argument".to_string(), span),
+            }
+        };
+        for piece in &format.pieces {
+            match &piece.kind {
+                PieceKind::Text(literal) => text.push_str(literal),
+                PieceKind::Placeholder {
+                    argument: name,
+                    spec,
+                } => {
+                    let value: Value = match name {
+                        Some(name) => match name.parse::<usize>() {
+                            Ok(index) => argument(Some(index), piece.span)?,
+                            Err(_) => match self.variable(name) {
+                                Some(slot) => slot.borrow().clone(),
+                                None => {
+                                    return fault(
+                                        format!("Unknown variable `{}`", name),
+                                        piece.span,
+                                    );
+                                }
+                            },
+                        },
+                        None => argument(None, piece.span)?,
+                    };
+                    let printed: String = match (&value, spec.precision) {
+                        (Value::Float(value), Some(precision)) => {
+                            format!("{:.*}", precision, value)
+                        }
+                        (Value::Str(value), Some(precision)) => {
+                            value.chars().take(precision).collect()
+                        }
+                        (value, _) => value.to_string(),
+                    };
+                    let align: Align = match value {
+                        Value::Int(_) | Value::Float(_) => Align::Right,
+                        _ => Align::Left,
+                    };
+                    text.push_str(&spec.pad(&printed, align));
+                }
+                PieceKind::Specifier { conversion, spec } => {
+                    let value: Value = argument(None, piece.span)?;
+                    let printed: String = match (conversion, value) {
+                        ('d', Value::Int(value)) => value.to_string(),
+                        ('f', Value::Int(value)) => {
+                            format!("{:.*}", spec.precision.unwrap_or(6), value as f64)
+                        }
+                        ('f', Value::Float(value)) => {
+                            format!("{:.*}", spec.precision.unwrap_or(6), value)
+                        }
+                        ('s', value) => {
+                            let value: String = value.to_string();
+                            match spec.precision {
+                                Some(precision) => value.chars().take(precision).collect(),
+                                None => value,
+                            }
+                        }
+                        (conversion, value) => {
+                            return fault(
+                                format!("`%{}` can't print a `{}`", conversion, value.type_name()),
+                                piece.span,
+                            );
+                        }
+                    };
+                    text.push_str(&spec.pad(&printed, Align::Right));
+                }
+            }
+        }
+        Ok(text)
+    }
+
+    /// # Variant
+    ///
+    /// Evaluates an enum variant path like `IoError::Closed`. Any variant of the built in
+    /// `Error` can be used without being declared.
+    fn variant(&self, path: &[String], span: Span) -> Eval<Value> {
+        if path[0] == BUILTIN_ERROR {
+            return Ok(Value::Variant {
+                enum_id: None,
+                enum_name: path[0].clone(),
+                name: path[1].clone(),
+            });
+        }
+        match self.resolve(self.file_id(), &path[0]) {
+            Some((id, ItemKind::Enum { name, variants, .. }))
+                if variants.iter().any(|variant| variant.name.text == path[1]) =>
+            {
+                Ok(Value::Variant {
+                    enum_id: Some(id),
+                    enum_name: name.text.clone(),
+                    name: path[1].clone(),
+                })
+            }
+            _ => fault(format!("Unknown path `{}`", path.join("::")), span),
+        }
+    }
+
+    /// # Assign
+    ///
+    /// Stores a value in a variable, a field or an element, or through a reference.
+    fn assign(&mut self, target: &'a Node, value: Value) -> Eval<()> {
+        let mut value: Option<Value> = Some(value);
+        self.update(target, &mut |place: &mut Value| {
+            *place = value.take().unwrap_or(Value::Null);
+            Ok(())
+        })
+    }
+
+    /// # Update
+    ///
+    /// Finds the place an assignment target refers to and changes it with `change`.
+    fn update(
+        &mut self,
+        target: &'a Node,
+        change: &mut dyn FnMut(&mut Value) -> Eval<()>,
+    ) -> Eval<()> {
+        match &target.kind {
+            NodeKind::Ident(name) => match self.variable(name) {
+                Some(slot) => change(&mut slot.borrow_mut()),
+                None => fault(format!("Unknown variable `{}`", name), target.span),
+            },
+            NodeKind::Field { base, name } => {
+                self.update(base, &mut |value: &mut Value| match value {
+                    Value::Ref(slot) => change(field(&mut slot.borrow_mut(), name, target.span)?),
+                    value => change(field(value, name, target.span)?),
+                })
+            }
+            NodeKind::Index { base, index } => {
+                let index: Value = self.eval(index)?;
+                self.update(base, &mut |value: &mut Value| {
+                    let element: Option<&mut Value> = match (value, &index) {
+                        (Value::Array(values), Value::Int(index)) => usize::try_from(*index)
+                            .ok()
+                            .and_then(|index| values.get_mut(index)),
+                        _ => return fault("Can't assign to this index".to_string(), target.span),
+                    };
+                    match element {
+                        Some(element) => change(element),
+                        None => Err(builtin_error("IndexOutOfBounds", target.span)),
+                    }
+                })
+            }
+            NodeKind::Unary {
+                op: UnaryOp::Deref,
+                operand,
+            } => match self.eval(operand)? {
+                Value::Ref(slot) => change(&mut slot.borrow_mut()),
+                value => fault(
+                    format!("Can't dereference a `{}`", value.type_name()),
+                    operand.span,
+                ),
+            },
+            _ => fault("Can't assign to this expression".to_string(), target.span),
+        }
+    }
+
+    /// # Declare
+    ///
+    /// Adds a variable to the innermost scope.
+    fn declare(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.frame().scopes.last_mut() {
+            scope.insert(name.to_string(), Rc::new(RefCell::new(value)));
+        }
+    }
+
+    /// # Variable
+    ///
+    /// Looks a variable up in the scopes of the running call, innermost first.
+    fn variable(&self, name: &str) -> Option<Slot> {
+        self.frames
+            .last()?
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+    }
+
+    /// # Frame
+    ///
+    /// Returns the running call.
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("a function is running")
+    }
+
+    /// # File Id
+    ///
+    /// Returns the module of the running function.
+    fn file_id(&self) -> FileId {
+        self.frames
+            .last()
+            .map(|frame| frame.file_id)
+            .unwrap_or_default()
+    }
+
+    /// # Resolve
+    ///
+    /// Looks a name up in the namespace of `file_id`, returning the item it refers to.
+    fn resolve(&self, file_id: FileId, name: &str) -> Option<(ItemId, &'a ItemKind)> {
+        match self.modules[file_id.0 as usize].scope.get(name)? {
+            Symbol::Item { file_id, index } => {
+                let item: &'a Item = &self.modules[file_id.0 as usize].items[*index];
+                Some(((*file_id, *index), &item.kind))
+            }
+            Symbol::Std(_) => None,
+        }
+    }
+}
+
+/// # Fault
+///
+/// Stops the program with a message.
+fn fault<T>(message: String, span: Span) -> Eval<T> {
+    Err(Unwind::Fault(RunError { message, span }))
+}
+
+/// # Builtin Error
+///
+/// Throws a variant of the built in `Error`, i.e. `Error::DivisionByZero`.
+fn builtin_error(name: &str, span: Span) -> Unwind {
+    let value: Value = Value::Variant {
+        enum_id: None,
+        enum_name: BUILTIN_ERROR.to_string(),
+        name: name.to_string(),
+    };
+    Unwind::Throw(value, span)
+}
+
+/// # Field
+///
+/// Returns a field of a struct, or a position of a tuple like `pair.0`.
+fn field<'v>(value: &'v mut Value, name: &str, span: Span) -> Eval<&'v mut Value> {
+    let type_name: String = value.type_name().to_string();
+    let found: Option<&'v mut Value> = match value {
+        Value::Struct(fields) => fields
+            .iter_mut()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value),
+        Value::Tuple(values) => name
+            .parse()
+            .ok()
+            .and_then(|index: usize| values.get_mut(index)),
+        _ => None,
+    };
+    match found {
+        Some(value) => Ok(value),
+        None => fault(format!("A `{}` has no field `{}`", type_name, name), span),
+    }
+}
+
+/// # Assign Operator
+///
+/// Returns the operator a compound assignment applies, i.e. `Plus` for `+=`.
+fn assign_operator(op: AssignOp) -> Option<Operator> {
+    match op {
+        AssignOp::Assign | AssignOp::BitNot => None,
+        AssignOp::Plus => Some(Operator::Plus),
+        AssignOp::Minus => Some(Operator::Minus),
+        AssignOp::Multiply => Some(Operator::Multiply),
+        AssignOp::Divide => Some(Operator::Divide),
+        AssignOp::Modulus => Some(Operator::Modulus),
+        AssignOp::BitAnd => Some(Operator::BitAnd),
+        AssignOp::BitOr => Some(Operator::BitOr),
+        AssignOp::BitXor => Some(Operator::BitXor),
+        AssignOp::LeftShift => Some(Operator::LeftShift),
+        AssignOp::RightShift => Some(Operator::RightShift),
+    }
+}
+
+/// # Unary
+///
+/// Applies a prefix operator other than `&`.
+fn unary(op: UnaryOp, value: Value, span: Span) -> Eval<Value> {
+    match (op, value) {
+        (UnaryOp::Negate, Value::Int(value)) => match value.checked_neg() {
+            Some(value) => Ok(Value::Int(value)),
+            None => Err(builtin_error("Overflow", span)),
+        },
+        (UnaryOp::Negate, Value::Float(value)) => Ok(Value::Float(-value)),
+        (UnaryOp::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+        (UnaryOp::BitNot, Value::Int(value)) => Ok(Value::Int(!value)),
+        (UnaryOp::Deref, Value::Ref(slot)) => Ok(slot.borrow().clone()),
+        (op, value) => fault(
+            format!("Can't apply `{:?}` to a `{}`", op, value.type_name()),
+            span,
+        ),
+    }
+}
+
+/// # Binary
+///
+/// Applies a binary operator other than `&&` and `||`, which short circuit. Integers mixed
+/// with floats are treated as floats. Dividing an integer by zero throws
+/// `Error::DivisionByZero`, and a result too large to hold throws `Error::Overflow`.
+fn binary(op: Operator, left: Value, right: Value, span: Span) -> Eval<Value> {
+    let overflow = |value: Option<i128>| match value {
+        Some(value) => Ok(Value::Int(value)),
+        None => Err(builtin_error("Overflow", span)),
+    };
+    match (op, &left, &right) {
+        (Operator::Equals, ..) => Ok(Value::Bool(left == right)),
+        (Operator::NotEquals, ..) => Ok(Value::Bool(left != right)),
+        (Operator::Range | Operator::RangeInclusive, Value::Int(start), Value::Int(end)) => {
+            let end: i128 = if op == Operator::RangeInclusive {
+                *end + 1
+            } else {
+                *end
+            };
+            Ok(Value::Array((*start..end).map(Value::Int).collect()))
+        }
+        (Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual, ..) => {
+            let ordering: Option<std::cmp::Ordering> = match (&left, &right) {
+                (Value::Int(left), Value::Int(right)) => left.partial_cmp(right),
+                (Value::Int(left), Value::Float(right)) => (*left as f64).partial_cmp(right),
+                (Value::Float(left), Value::Int(right)) => left.partial_cmp(&(*right as f64)),
+                (Value::Float(left), Value::Float(right)) => left.partial_cmp(right),
+                (Value::Char(left), Value::Char(right)) => left.partial_cmp(right),
+                (Value::Str(left), Value::Str(right)) => left.partial_cmp(right),
+                _ => return cant_apply(op, &left, &right, span),
+            };
+            Ok(Value::Bool(ordering.is_some_and(|ordering| match op {
+                Operator::Less => ordering.is_lt(),
+                Operator::LessEqual => ordering.is_le(),
+                Operator::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })))
+        }
+        (_, Value::Int(left), Value::Int(right)) => {
+            let (left, right): (i128, i128) = (*left, *right);
+            match op {
+                Operator::Plus => overflow(left.checked_add(right)),
+                Operator::Minus => overflow(left.checked_sub(right)),
+                Operator::Multiply => overflow(left.checked_mul(right)),
+                Operator::Divide | Operator::Modulus if right == 0 => {
+                    Err(builtin_error("DivisionByZero", span))
+                }
+                Operator::Divide => overflow(left.checked_div(right)),
+                Operator::Modulus => overflow(left.checked_rem(right)),
+                Operator::BitAnd => Ok(Value::Int(left & right)),
+                Operator::BitOr => Ok(Value::Int(left | right)),
+                Operator::BitXor => Ok(Value::Int(left ^ right)),
+                Operator::LeftShift => overflow(
+                    u32::try_from(right)
+                        .ok()
+                        .and_then(|by| left.checked_shl(by)),
+                ),
+                Operator::RightShift => overflow(
+                    u32::try_from(right)
+                        .ok()
+                        .and_then(|by| left.checked_shr(by)),
+                ),
+                _ => cant_apply(op, &Value::Int(left), &Value::Int(right), span),
+            }
+        }
+        (
+            Operator::Plus
+            | Operator::Minus
+            | Operator::Multiply
+            | Operator::Divide
+            | Operator::Modulus,
+            Value::Int(_) | Value::Float(_),
+            Value::Int(_) | Value::Float(_),
+        ) => {
+            let float = |value: &Value| match value {
+                Value::Int(value) => *value as f64,
+                Value::Float(value) => *value,
+                _ => 0.0,
+            };
+            let (left, right): (f64, f64) = (float(&left), float(&right));
+            Ok(Value::Float(match op {
+                Operator::Plus => left + right,
+                Operator::Minus => left - right,
+                Operator::Multiply => left * right,
+                Operator::Divide => left / right,
+                _ => left % right,
+            }))
+        }
+        (Operator::Plus, Value::Str(left), Value::Str(right)) => {
+            Ok(Value::Str(format!("{}{}", left, right)))
+        }
+        (Operator::BitAnd, Value::Bool(left), Value::Bool(right)) => {
+            Ok(Value::Bool(*left & *right))
+        }
+        (Operator::BitOr, Value::Bool(left), Value::Bool(right)) => Ok(Value::Bool(*left | *right)),
+        (Operator::BitXor, Value::Bool(left), Value::Bool(right)) => {
+            Ok(Value::Bool(*left ^ *right))
+        }
+        (Operator::And | Operator::Or, Value::Bool(_), Value::Bool(right)) => {
+            Ok(Value::Bool(*right))
+        }
+        _ => cant_apply(op, &left, &right, span),
+    }
+}
+
+/// # Cant Apply
+///
+/// Stops the program for an operator used on values it doesn't work on.
+fn cant_apply(op: Operator, left: &Value, right: &Value, span: Span) -> Eval<Value> {
+    fault(
+        format!(
+            "Can't apply `{:?}` to a `{}` and a `{}`",
+            op,
+            left.type_name(),
+            right.type_name()
+        ),
+        span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::desugar;
+    use crate::modules::Loader;
+    use crate::parser::Parser;
+    use crate::test_files::TestDir;
+    use crate::tokenizer::Tokenizer;
+
+    /// Writes `files` to a new directory, loads the first one and runs it, returning what it
+    /// printed and how it ended.
+    fn run_files(test: &str, files: &[(&str, &str)]) -> (String, Result<Option<i128>, RunError>) {
+        let dir: TestDir = TestDir::new(&format!("interpret-{}", test), files);
+        let mut loader: Loader = Loader::new();
+        loader
+            .load_root(
+                &dir.path().join(files[0].0),
+                files[0].1,
+                &mut |_, src, file_id| {
+                    let c_settings: CSettings = CSettings::default();
+                    let tokens = Tokenizer::new(src.to_string(), &c_settings)
+                        .with_file_id(file_id)
+                        .tokenize();
+                    let mut parser: Parser = Parser::new(tokens, src);
+                    let items: Vec<Item> = parser.parse();
+                    assert_eq!(parser.errors(), &[]);
+                    Ok(items)
+                },
+            )
+            .unwrap();
+        assert_eq!(loader.errors(), &[]);
+        for index in 0..loader.modules().len() {
+            let file_id: FileId = FileId(index as u32);
+            let declarations: desugar::Declarations =
+                desugar::Declarations::new(loader.resolve(file_id));
+            desugar::desugar(loader.items_mut(file_id), &declarations);
+        }
+        let mut out: Vec<u8> = Vec::new();
+        let result: Result<Option<i128>, RunError> = run(loader.modules(), &mut out);
+        (String::from_utf8(out).unwrap(), result)
+    }
+
+    /// Runs a single file.
+    fn run_main(test: &str, src: &str) -> (String, Result<Option<i128>, RunError>) {
+        run_files(test, &[("main.zc", src)])
+    }
+
+    #[test]
+    fn runs_statements_and_prints() {
+        let (out, result) = run_main(
+            "statements",
+            "dive { print, println } from std::io;\n\
+             struct Person { Name: string = \"Unknown\", Age: i32 = 0 }\n\
+             fun main() -> i32 {\n\
+                 total: i32 = 0;\n\
+                 for i in [1..5] { total += i; }\n\
+                 while total > 8 { total--; }\n\
+                 do { total = total * 2; } while total < 20;\n\
+                 p: Person = { Name = \"Tallen\" };\n\
+                 p.Age = 17;\n\
+                 println(\"{} is {} and {:>5.1}|{total}\", p.Name, p.Age, 1.25);\n\
+                 print(\"%s %d %.2f\\n\", \"c\", 7, 2);\n\
+                 switch total { case 0..10: println(\"small\"); default: println(\"big\"); }\n\
+                 return total;\n\
+             }",
+        );
+        assert_eq!(out, "Tallen is 17 and   1.2|32\nc 7 2.00\nbig\n");
+        assert_eq!(result, Ok(Some(32)));
+    }
+
+    #[test]
+    fn writes_through_references() {
+        let (out, result) = run_main(
+            "references",
+            "dive { println } from std::io;\n\
+             struct Point { x: i32 = 0, y: i32 = 0 }\n\
+             fun change_val(str: &string, new_value: string) -> void { *str = new_value; }\n\
+             fun move_right(p: &Point) { p.x += 1; }\n\
+             fun main() {\n\
+                 s: string = \"old\";\n\
+                 change_val(&s, \"new\");\n\
+                 p: Point = { y = 2 };\n\
+                 move_right(&p);\n\
+                 println(\"{} {} {}\", s, p, p.x);\n\
+             }",
+        );
+        assert_eq!(out, "new { y = 2, x = 1 } 1\n");
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn throws_unwind_to_the_matching_catch() {
+        let (out, result) = run_main(
+            "unwind",
+            "dive { println } from std::io;\n\
+             enum IoError: Error { Closed }\n\
+             enum ParseError: Error { Bad }\n\
+             fun read() -> i32 { throw IoError::Closed; println(\"unreachable\"); }\n\
+             fun parse() -> i32 {\n\
+                 try { return read(); } catch (e: ParseError) { return 1; }\n\
+                 finally { println(\"parse finally\"); }\n\
+             }\n\
+             fun main() -> i32 {\n\
+                 try { parse(); } catch (e: IoError) { println(\"caught {}\", e); }\n\
+                 try { x: i32 = 1 / 0; } catch { println(\"divided by zero\"); }\n\
+                 return 0;\n\
+             }",
+        );
+        assert_eq!(
+            out,
+            "parse finally\ncaught IoError::Closed\ndivided by zero\n"
+        );
+        assert_eq!(result, Ok(Some(0)));
+    }
+
+    #[test]
+    fn finally_runs_and_can_replace_the_outcome() {
+        let (out, result) = run_main(
+            "finally",
+            "dive { println } from std::io;\n\
+             fun returns() -> i32 {\n\
+                 try { return 1; } finally { println(\"cleanup\"); }\n\
+             }\n\
+             fun overrides() -> i32 {\n\
+                 try { throw Error::Oops; } finally { return 2; }\n\
+             }\n\
+             fun rethrows() -> i32 {\n\
+                 try { throw Error::First; } catch { throw Error::Second; }\n\
+                 finally { println(\"still runs\"); }\n\
+             }\n\
+             fun main() -> i32 {\n\
+                 try { rethrows(); } catch (e: Error) { println(\"{}\", e); }\n\
+                 return returns() * 10 + overrides();\n\
+             }",
+        );
+        assert_eq!(out, "still runs\nError::Second\ncleanup\n");
+        assert_eq!(result, Ok(Some(12)));
+    }
+
+    #[test]
+    fn uncaught_errors_end_the_program() {
+        let (out, result) = run_main(
+            "uncaught",
+            "dive { println } from std::io;\n\
+             fun divide(x: i32, y: i32) -> i32 { return x / y; }\n\
+             fun main() {\n\
+                 try { divide(1, 0); } finally { println(\"finally\"); }\n\
+                 println(\"unreachable\");\n\
+             }",
+        );
+        assert_eq!(out, "finally\n");
+        let error: RunError = result.unwrap_err();
+        assert_eq!(
+            (error.message.as_str(), error.span.line, error.span.column),
+            (
+                "Uncaught error `Error::DivisionByZero` ended the program",
+                2,
+                44
+            )
+        );
+    }
+
+    #[test]
+    fn errors_unwind_across_modules() {
+        let (out, result) = run_files(
+            "modules",
+            &[
+                (
+                    "main.zc",
+                    "dive { println } from std::io;\n\
+                     dive { IoError, open } from \"./lib.zc\";\n\
+                     enum Closed: Error { Closed }\n\
+                     fun main() {\n\
+                         try { open(); } catch (e: Closed) { println(\"wrong\"); }\n\
+                         catch (e: IoError) { println(\"caught {}\", e); }\n\
+                     }",
+                ),
+                (
+                    "lib.zc",
+                    "enum IoError: Error { Closed }\n\
+                     enum Closed: Error { Closed }\n\
+                     fun open() { throw IoError::Closed; }",
+                ),
+            ],
+        );
+        assert_eq!(out, "caught IoError::Closed\n");
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn deep_recursion_throws_a_stack_overflow() {
+        let (_, result) = run_main(
+            "recursion",
+            "fun forever(n: i32) -> i32 { return forever(n + 1); }\n\
+             fun main() -> i32 {\n\
+                 try { forever(0); } catch { return 1; }\n\
+                 return 0;\n\
+             }",
+        );
+        assert_eq!(result, Ok(Some(1)));
+    }
+}
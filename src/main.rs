@@ -1,10 +1,23 @@
 use colored::Colorize;
+use span::FileId;
 use std::env;
 use std::fs::File;
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use token_dump::TokenFormat;
 include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
 
+mod c_header;
+mod check;
+mod desugar;
+mod format_string;
+mod interpret;
+mod modules;
 mod parser;
+mod span;
+#[cfg(test)]
+mod test_files;
+mod token_dump;
 mod tokenizer;
 mod zlog;
 
@@ -17,7 +30,12 @@ const NAME: &str = "zinc";
 struct CSettings {
     is_verbose: bool,
     is_print_tokens: bool,
+    token_format: TokenFormat,
+    output_path: Option<String>,
     is_no_color: bool,
+    is_lint_confusables: bool,
+    is_run: bool,               // Run `main` after checking, instead of only checking
+    include_paths: Vec<String>, // Where `bellyflop` looks for C headers before the system paths
 }
 
 fn main() -> std::io::Result<()> {
@@ -28,10 +46,11 @@ fn main() -> std::io::Result<()> {
     if args.len() >= 2 {
         let mut input_file_str: String = String::new();
 
-        for arg in &args {
+        let mut arg_iter = args.iter();
+        while let Some(arg) = arg_iter.next() {
             if arg == "--help" || arg == "-h" {
                 println!(
-                    "Usage: zinc [options] file\nOptions:\n\t-h,\t--help\t\t\tDisplay this information.\n\t-v,\t--version\t\tPrint the version of ZINC\n\t--vb,\t--verbose\t\tPrint verbose logs.\n\t--pt,\t--print-tokens\t\tPrints the output of the tokenizer.\n\t--no-color\t\t\tDisable color output."
+                    "Usage: zinc [options] file\nOptions:\n\t-h,\t--help\t\t\tDisplay this information.\n\t-v,\t--version\t\tPrint the version of ZINC\n\t--vb,\t--verbose\t\tPrint verbose logs.\n\t--pt,\t--print-tokens\t\tPrints the output of the tokenizer.\n\t--print-tokens=<json|compact>\tWrites the tokens to stdout (or the -o file) in a machine readable format.\n\t-o,\t--output <file>\t\tWrite output to <file>.\n\t--no-color\t\t\tDisable color output.\n\t--lint-confusables\t\tWarn about identifiers that mix scripts.\n\t-I,\t--include <dir>\t\tLook for C headers in <dir>.\n\t--run\t\t\t\tRun the program after checking it."
                 );
                 return Ok(());
             } else if arg == "--version" || arg == "-v" {
@@ -41,8 +60,38 @@ fn main() -> std::io::Result<()> {
                 c_settings.is_verbose = true;
             } else if arg == "--print-tokens" || arg == "--pt" {
                 c_settings.is_print_tokens = true;
+            } else if let Some(format_name) = arg
+                .strip_prefix("--print-tokens=")
+                .or(arg.strip_prefix("--pt="))
+            {
+                match TokenFormat::from_name(format_name) {
+                    Some(format) => {
+                        c_settings.is_print_tokens = true;
+                        c_settings.token_format = format;
+                    }
+                    None => zlog::warn(
+                        &format!("Unknown token format `{}`", format_name),
+                        &c_settings,
+                    ),
+                }
+            } else if arg == "-o" || arg == "--output" {
+                match arg_iter.next() {
+                    Some(path) => c_settings.output_path = Some(path.to_string()),
+                    None => zlog::warn(&format!("Missing file after `{}`", arg), &c_settings),
+                }
+            } else if arg == "-I" || arg == "--include" {
+                match arg_iter.next() {
+                    Some(dir) => c_settings.include_paths.push(dir.to_string()),
+                    None => zlog::warn(&format!("Missing directory after `{}`", arg), &c_settings),
+                }
+            } else if let Some(dir) = arg.strip_prefix("-I") {
+                c_settings.include_paths.push(dir.to_string());
             } else if arg == "--no-color" || arg == "--nc" {
                 c_settings.is_no_color = true;
+            } else if arg == "--lint-confusables" {
+                c_settings.is_lint_confusables = true;
+            } else if arg == "--run" {
+                c_settings.is_run = true;
             } else if arg.starts_with('-') {
                 zlog::warn(&format!("Unknown argument `{}`", arg), &c_settings);
             } else {
@@ -62,7 +111,7 @@ fn main() -> std::io::Result<()> {
             }
         }
 
-        if input_file_str != "" {
+        if !input_file_str.is_empty() {
             let src_path: String;
             let cwd = std::env::current_dir().unwrap();
 
@@ -76,7 +125,7 @@ fn main() -> std::io::Result<()> {
             }
 
             zlog::verbose(
-                &format!("Absolute source file path: {}", src_path.to_string()),
+                &format!("Absolute source file path: {}", src_path),
                 &c_settings,
             );
 
@@ -91,25 +140,75 @@ fn main() -> std::io::Result<()> {
                 }
             }
 
-            let mut tokenizer: tokenizer::Tokenizer = tokenizer::Tokenizer::new(src, &c_settings);
-            match tokenizer.tokenize() {
-                Ok(tokens) => {
-                    if c_settings.is_print_tokens {
-                        // Print the tokens out
-                        let all_token_string: String = tokens
-                            .iter()
-                            .map(|token| format!("{:#?}", token))
-                            .collect::<Vec<String>>()
-                            .join("\n");
-                        zlog::log(&all_token_string, &c_settings);
-                    }
+            let mut loader: modules::Loader = modules::Loader::new()
+                .with_include_paths(c_settings.include_paths.iter().map(PathBuf::from).collect());
+            loader.load_root(Path::new(&src_path), &src, &mut |path, src, file_id| {
+                parse_file(path, src, file_id, &c_settings)
+            })?;
+            if !loader.errors().is_empty() {
+                for error in loader.errors() {
+                    zlog::err(&error.to_string(), &c_settings);
                 }
-                Err(e) => {
+                return Err(std::io::Error::other(format!(
+                    "Failed to load imported modules: {} error(s)",
+                    loader.errors().len()
+                )));
+            }
+            zlog::verbose(
+                &format!("Loading Completed. Modules: {}", loader.modules().len()),
+                &c_settings,
+            );
+
+            for index in 0..loader.modules().len() {
+                let file_id: FileId = FileId(index as u32);
+                let declarations: desugar::Declarations =
+                    desugar::Declarations::new(loader.resolve(file_id));
+                desugar::desugar(loader.items_mut(file_id), &declarations);
+            }
+
+            let mut check_errors: usize = 0;
+            for module in loader.modules() {
+                let mut checker: check::Checker =
+                    check::Checker::new(&module.items, loader.resolve(module.file_id));
+                checker.check();
+                for error in checker.errors() {
                     zlog::err(
-                        &format!("Failed to tokenize source file contents due to error {}", e),
+                        &located(&module.path, module.file_id, error.to_string()),
                         &c_settings,
                     );
-                    return Err(std::io::Error::new(std::io::ErrorKind::Other, e));
+                }
+                check_errors += checker.errors().len();
+            }
+            for warning in check::check_uncaught_errors(loader.modules()) {
+                zlog::warn(&warning.to_string(), &c_settings);
+            }
+            if check_errors > 0 {
+                return Err(std::io::Error::other(format!(
+                    "Failed to check source file contents: {} error(s)",
+                    check_errors
+                )));
+            }
+
+            if c_settings.is_run {
+                let mut stdout: std::io::Stdout = std::io::stdout();
+                let result: Result<Option<i128>, interpret::RunError> =
+                    interpret::run(loader.modules(), &mut stdout);
+                stdout.flush()?;
+                match result {
+                    // `main` returning a number sets the exit code, like in C
+                    Ok(Some(code)) if code != 0 => {
+                        std::process::exit(code as i32);
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        let module: &modules::Module =
+                            &loader.modules()[error.span.file_id.0 as usize];
+                        zlog::err(
+                            &located(&module.path, module.file_id, error.to_string()),
+                            &c_settings,
+                        );
+                        return Err(std::io::Error::other("Failed to run the program"));
+                    }
                 }
             }
         } else {
@@ -134,6 +233,103 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }
 
+/// # Parse File
+///
+/// Runs the front end on a source file: tokenizing, lexing format strings and parsing. Errors
+/// are logged as they are found. Tokens are only printed for the file being compiled, not for
+/// the files it imports.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file, for error messages.
+/// * `src` - The contents of the file.
+/// * `file_id` - The id the module loader gave the file.
+/// * `c_settings` - The compiler settings.
+///
+/// # Returns
+///
+/// * `std::io::Result<Vec<parser::Item>>` - The parsed items, or an error saying how many
+///   problems were found.
+fn parse_file(
+    path: &Path,
+    src: &str,
+    file_id: FileId,
+    c_settings: &CSettings,
+) -> std::io::Result<Vec<parser::Item>> {
+    let mut tokenizer: tokenizer::Tokenizer =
+        tokenizer::Tokenizer::new(src.to_string(), c_settings).with_file_id(file_id);
+    let tokens: Vec<tokenizer::Token> = tokenizer.tokenize();
+    if c_settings.is_print_tokens && file_id == FileId::default() {
+        // Print the tokens out
+        let all_token_string: String =
+            token_dump::dump_tokens(&tokens, src, c_settings.token_format);
+        match (c_settings.token_format, &c_settings.output_path) {
+            (_, Some(output_path)) => std::fs::write(output_path, all_token_string)?,
+            (TokenFormat::Debug, None) => zlog::log(&all_token_string, c_settings),
+            (_, None) => print!("{}", all_token_string),
+        }
+    }
+    for warning in tokenizer.warnings() {
+        zlog::warn(&located(path, file_id, warning.to_string()), c_settings);
+    }
+    let mut errors: Vec<tokenizer::LexError> = tokenizer.errors().to_vec();
+    let format_strings: Vec<format_string::FormatString> =
+        format_string::lex_format_calls(&tokens, src, &mut errors);
+    if !errors.is_empty() {
+        for error in &errors {
+            zlog::err(&located(path, file_id, error.to_string()), c_settings);
+        }
+        return Err(std::io::Error::other(format!(
+            "Failed to tokenize source file contents: {} error(s)",
+            errors.len()
+        )));
+    }
+
+    let mut parser: parser::Parser = parser::Parser::new(tokens, src);
+    let items: Vec<parser::Item> = parser.parse();
+    if !parser.errors().is_empty() {
+        for error in parser.errors() {
+            zlog::err(&located(path, file_id, error.to_string()), c_settings);
+        }
+        return Err(std::io::Error::other(format!(
+            "Failed to parse source file contents: {} error(s)",
+            parser.errors().len()
+        )));
+    }
+    let format_errors: Vec<tokenizer::LexError> =
+        format_string::check_format_calls(&items, &format_strings);
+    if !format_errors.is_empty() {
+        for error in &format_errors {
+            zlog::err(&located(path, file_id, error.to_string()), c_settings);
+        }
+        return Err(std::io::Error::other(format!(
+            "Failed to check format strings: {} error(s)",
+            format_errors.len()
+        )));
+    }
+    zlog::verbose(
+        &format!(
+            "Parsing Completed. File: {}, Items: {}",
+            path.display(),
+            items.len()
+        ),
+        c_settings,
+    );
+    Ok(items)
+}
+
+/// # Located
+///
+/// Prefixes a diagnostic with the path of the file it is in, unless it is in the file being
+/// compiled.
+fn located(path: &Path, file_id: FileId, message: String) -> String {
+    if file_id == FileId::default() {
+        message
+    } else {
+        format!("{}: {}", path.display(), message)
+    }
+}
+
 /// # Read File to String
 ///
 /// Reads the contents of a file into a specified string.
@@ -0,0 +1,904 @@
+// Module loading
+use crate::c_header::{self, CDecl, Header};
+use crate::format_string::FORMAT_FUNCTIONS;
+use crate::parser::{Import, ImportSource, Item, ItemKind, Name};
+use crate::span::{FileId, Span};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The modules of the standard library and the names they export. They are built into the
+/// compiler, so there are no files to load for them.
+const STD_MODULES: &[(&str, &[&str])] = &[("std::io", FORMAT_FUNCTIONS)];
+
+/// Runs the front end on the source of a module: tokenizing and parsing. Any errors are
+/// reported by the function itself, and returning `Err` stops the loading.
+pub type ParseFn<'p> = dyn FnMut(&Path, &str, FileId) -> io::Result<Vec<Item>> + 'p;
+
+/// # Module Error
+///
+/// A problem with an import, i.e. a file that doesn't exist or a name it doesn't export.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ModuleError {
+    pub message: String,
+    pub path: PathBuf, // The file the problem is in
+    pub span: Span,
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: [Line {}, Column {}] {}",
+            self.path.display(),
+            self.span.line,
+            self.span.column,
+            self.message
+        )
+    }
+}
+
+/// # Symbol
+///
+/// What a name in a module's namespace refers to.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Symbol {
+    Item { file_id: FileId, index: usize }, // An item of a loaded module, by its index
+    Std(&'static str),                      // A built in name from a `STD_MODULES` module
+}
+
+/// # Module
+///
+/// A loaded source file. Every file has its own namespace: the items it declares and the names
+/// it imports, so the same name can be declared in different files without colliding.
+#[derive(Debug)]
+pub struct Module {
+    pub file_id: FileId,
+    pub path: PathBuf,
+    pub items: Vec<Item>,
+    pub scope: HashMap<String, Symbol>,
+}
+
+/// # Loader
+///
+/// Loads a file and every file it imports with `dive { ... } from "./file.zc";`, once each.
+/// Relative paths are resolved against the directory of the importing file.
+///
+/// Every top-level item of a file is exported, except for `main` and items whose name starts
+/// with `_`. Imports aren't passed on: a file has to import a name from the file that declares
+/// it.
+///
+/// C headers are imported with `bellyflop { ... } from <header.h>;`. The imported declarations
+/// become extern items appended to the importing file's items.
+pub struct Loader {
+    modules: Vec<Module>,
+    by_path: HashMap<PathBuf, FileId>, // Canonical paths of the loaded files
+    loading: Vec<FileId>,              // The files whose imports are being loaded
+    include_paths: Vec<PathBuf>,       // Where C headers are looked for, in order
+    headers: HashMap<String, Option<Header>>, // Parsed C headers, `None` if they can't be found
+    c_imports: HashMap<(FileId, String), CDecl>, // The C declarations each file imported
+    errors: Vec<ModuleError>,
+}
+
+impl Loader {
+    /// # New
+    ///
+    /// Create a new loader without any modules.
+    pub fn new() -> Self {
+        Loader {
+            modules: Vec::new(),
+            by_path: HashMap::new(),
+            loading: Vec::new(),
+            include_paths: c_header::system_include_paths(),
+            headers: HashMap::new(),
+            c_imports: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// # With Include Paths
+    ///
+    /// Looks for C headers in `paths` before the system include directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The directories passed with `-I`, in order.
+    pub fn with_include_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.include_paths = paths
+            .into_iter()
+            .chain(c_header::system_include_paths())
+            .collect();
+        self
+    }
+
+    /// # Load Root
+    ///
+    /// Loads the file being compiled, then the files it imports. The root file gets
+    /// `FileId(0)`. Problems with imports end up in [`Loader::errors`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `src` - The contents of the file.
+    /// * `parse` - The front end to run on every loaded file.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - The first error returned by `parse`.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut loader: modules::Loader = modules::Loader::new();
+    /// loader.load_root(Path::new("main.zc"), &src, &mut |path, src, file_id| {
+    ///     parse_file(path, src, file_id, &c_settings)
+    /// })?;
+    /// ```
+    pub fn load_root(&mut self, path: &Path, src: &str, parse: &mut ParseFn) -> io::Result<()> {
+        let key: PathBuf = fs::canonicalize(path)?;
+        self.load(normalize(path), key, src, parse)?;
+        Ok(())
+    }
+
+    /// # Modules
+    ///
+    /// Returns the loaded modules, indexed by their `FileId`.
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    /// # Items Mut
+    ///
+    /// Returns the items of a loaded module, to be changed in place, i.e. by desugaring.
+    pub fn items_mut(&mut self, file_id: FileId) -> &mut Vec<Item> {
+        &mut self.modules[file_id.0 as usize].items
+    }
+
+    /// # Resolve
+    ///
+    /// Returns the items a module's namespace refers to, by the name the module knows them
+    /// by: the ones it declares and the ones it imports. Names from the standard library
+    /// aren't items, so they are left out.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut checker: check::Checker =
+    ///     check::Checker::new(&module.items, loader.resolve(module.file_id));
+    /// ```
+    pub fn resolve(&self, file_id: FileId) -> Vec<(&str, &Item)> {
+        self.modules[file_id.0 as usize]
+            .scope
+            .iter()
+            .filter_map(|(name, symbol)| match symbol {
+                Symbol::Item { file_id, index } => Some((
+                    name.as_str(),
+                    &self.modules[file_id.0 as usize].items[*index],
+                )),
+                Symbol::Std(_) => None,
+            })
+            .collect()
+    }
+
+    /// # Errors
+    ///
+    /// Returns the problems found with imports.
+    pub fn errors(&self) -> &[ModuleError] {
+        &self.errors
+    }
+
+    /// # Load
+    ///
+    /// Parses a file, then loads its imports and fills in its namespace.
+    fn load(
+        &mut self,
+        path: PathBuf,
+        key: PathBuf,
+        src: &str,
+        parse: &mut ParseFn,
+    ) -> io::Result<FileId> {
+        let file_id: FileId = FileId(self.modules.len() as u32);
+        let items: Vec<Item> = parse(&path, src, file_id)?;
+        let imports: Vec<(Import, Span)> = items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Import(import) => Some((import.clone(), item.span)),
+                _ => None,
+            })
+            .collect();
+        self.by_path.insert(key, file_id);
+        self.modules.push(Module {
+            file_id,
+            path,
+            items,
+            scope: HashMap::new(),
+        });
+
+        let mut scope: HashMap<String, Symbol> = HashMap::new();
+        for (index, item) in self.modules[file_id.0 as usize].items.iter().enumerate() {
+            if let Some(name) = item.name() {
+                let symbol: Symbol = Symbol::Item { file_id, index };
+                if let Some(error) = self.declare(file_id, &mut scope, name, symbol) {
+                    self.errors.push(error);
+                }
+            }
+        }
+        self.loading.push(file_id);
+        for (import, span) in imports {
+            self.load_import(file_id, &import, span, &mut scope, parse)?;
+        }
+        self.loading.pop();
+        self.modules[file_id.0 as usize].scope = scope;
+        Ok(file_id)
+    }
+
+    /// # Load Import
+    ///
+    /// Loads the file an import names, if it isn't loaded yet, and adds the imported names to
+    /// the importing file's namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - The importing file.
+    /// * `import` - The import.
+    /// * `span` - The span of the whole import item.
+    /// * `scope` - The namespace of the importing file.
+    /// * `parse` - The front end to run on newly loaded files.
+    fn load_import(
+        &mut self,
+        file_id: FileId,
+        import: &Import,
+        span: Span,
+        scope: &mut HashMap<String, Symbol>,
+        parse: &mut ParseFn,
+    ) -> io::Result<()> {
+        if import.is_c_header != matches!(import.source, ImportSource::Header(_)) {
+            let message: &str = if import.is_c_header {
+                "`bellyflop` imports C headers, i.e. `from <time.h>`"
+            } else {
+                "C headers are imported with `bellyflop` instead of `dive`"
+            };
+            self.error(file_id, message.to_string(), span);
+            return Ok(());
+        }
+        let relative: &str = match &import.source {
+            ImportSource::File(relative) => relative,
+            ImportSource::Module(path) => {
+                let module: String = path.join("::");
+                match STD_MODULES.iter().find(|(name, _)| *name == module) {
+                    Some((_, exports)) => {
+                        let names: Vec<&'static str> = exports.to_vec();
+                        self.import_names(file_id, import, &module, scope, |name| {
+                            names
+                                .iter()
+                                .find(|export| **export == name)
+                                .map(|export| (Symbol::Std(export), true))
+                        });
+                        if import.is_glob {
+                            for export in names {
+                                let name: Name = Name {
+                                    text: export.to_string(),
+                                    span,
+                                };
+                                let symbol: Symbol = Symbol::Std(export);
+                                if let Some(error) = self.declare(file_id, scope, &name, symbol) {
+                                    self.errors.push(error);
+                                }
+                            }
+                        }
+                    }
+                    None => self.error(file_id, format!("Unknown module `{}`", module), span),
+                }
+                return Ok(());
+            }
+            ImportSource::Header(header) => {
+                self.load_header(file_id, import, header, span, scope);
+                return Ok(());
+            }
+        };
+
+        let importer: &Path = &self.modules[file_id.0 as usize].path;
+        let path: PathBuf = normalize(&importer.parent().unwrap_or(Path::new("")).join(relative));
+        let Ok(key) = fs::canonicalize(&path) else {
+            self.error(
+                file_id,
+                format!("Can't find `{}` at `{}`", relative, path.display()),
+                span,
+            );
+            return Ok(());
+        };
+        let target: FileId = match self.by_path.get(&key).copied() {
+            Some(target) if self.loading.contains(&target) => {
+                let cycle: Vec<String> = self
+                    .loading
+                    .iter()
+                    .skip_while(|loading| **loading != target)
+                    .chain([&target])
+                    .map(|loading| self.modules[loading.0 as usize].path.display().to_string())
+                    .collect();
+                self.error(
+                    file_id,
+                    format!("Import cycle: {}", cycle.join(" -> ")),
+                    span,
+                );
+                return Ok(());
+            }
+            Some(target) => target,
+            None => match fs::read_to_string(&path) {
+                Ok(src) => self.load(path, key, &src, parse)?,
+                Err(error) => {
+                    let message: String = format!("Can't read `{}`: {}", path.display(), error);
+                    self.error(file_id, message, span);
+                    return Ok(());
+                }
+            },
+        };
+
+        let module: &Module = &self.modules[target.0 as usize];
+        let module_name: String = module.path.display().to_string();
+        let found: Vec<(String, Symbol, bool)> = module
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let name: &Name = item.name()?;
+                let symbol: Symbol = Symbol::Item {
+                    file_id: target,
+                    index,
+                };
+                Some((name.text.clone(), symbol, is_exported(&name.text)))
+            })
+            .collect();
+        self.import_names(file_id, import, &module_name, scope, |name| {
+            found
+                .iter()
+                .find(|(found, _, _)| found == name)
+                .map(|(_, symbol, is_exported)| (*symbol, *is_exported))
+        });
+        if import.is_glob {
+            for (name, symbol, is_exported) in found {
+                if is_exported {
+                    let name: Name = Name { text: name, span };
+                    if let Some(error) = self.declare(file_id, scope, &name, symbol) {
+                        self.errors.push(error);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// # Load Header
+    ///
+    /// Parses a C header, if it isn't parsed yet, and appends the declarations an import names
+    /// to the importing file's items as extern items. Names the file already declares aren't
+    /// replaced by a glob import, since headers declare many names. Importing the same
+    /// declaration twice, i.e. from a header and a header it includes, is allowed.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - The importing file.
+    /// * `import` - The import.
+    /// * `header` - The header, i.e. `time.h` for `<time.h>`.
+    /// * `span` - The span of the whole import item.
+    /// * `scope` - The namespace of the importing file.
+    fn load_header(
+        &mut self,
+        file_id: FileId,
+        import: &Import,
+        header: &str,
+        span: Span,
+        scope: &mut HashMap<String, Symbol>,
+    ) {
+        if !self.headers.contains_key(header) {
+            let loaded: Option<Header> = Header::load(header, &self.include_paths);
+            self.headers.insert(header.to_string(), loaded);
+        }
+        let Some(Some(loaded)) = self.headers.get(header) else {
+            self.error(
+                file_id,
+                format!("Can't find the C header `<{}>`", header),
+                span,
+            );
+            return;
+        };
+        let mut items: Vec<(CDecl, Item)> = Vec::new();
+        let mut missing: Vec<&Name> = Vec::new();
+        if import.is_glob {
+            items.extend(
+                loaded
+                    .exported()
+                    .into_iter()
+                    .filter(|decl| !scope.contains_key(decl.name()))
+                    .map(|decl| (decl.clone(), loaded.extern_item(decl, span))),
+            );
+        }
+        for name in &import.names {
+            match loaded.decl(&name.text) {
+                Some(decl) => items.push((decl.clone(), loaded.extern_item(decl, name.span))),
+                None => missing.push(name),
+            }
+        }
+        for name in missing {
+            self.error(
+                file_id,
+                format!("`<{}>` has no declaration named `{}`", header, name.text),
+                name.span,
+            );
+        }
+        for (decl, item) in items {
+            let Some(name) = item.name().cloned() else {
+                continue;
+            };
+            let key: (FileId, String) = (file_id, name.text.clone());
+            if self.c_imports.get(&key) == Some(&decl) {
+                continue;
+            }
+            let module: &mut Module = &mut self.modules[file_id.0 as usize];
+            let symbol: Symbol = Symbol::Item {
+                file_id,
+                index: module.items.len(),
+            };
+            module.items.push(item);
+            match self.declare(file_id, scope, &name, symbol) {
+                Some(error) => self.errors.push(error),
+                None => {
+                    self.c_imports.insert(key, decl);
+                }
+            }
+        }
+    }
+
+    /// # Import Names
+    ///
+    /// Adds the names listed in an import to the importing file's namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - The importing file.
+    /// * `import` - The import.
+    /// * `module` - The name of the imported module, for error messages.
+    /// * `scope` - The namespace of the importing file.
+    /// * `lookup` - Finds a name in the imported module, and whether it is exported.
+    fn import_names(
+        &mut self,
+        file_id: FileId,
+        import: &Import,
+        module: &str,
+        scope: &mut HashMap<String, Symbol>,
+        lookup: impl Fn(&str) -> Option<(Symbol, bool)>,
+    ) {
+        for name in &import.names {
+            match lookup(&name.text) {
+                Some((symbol, true)) => {
+                    if let Some(error) = self.declare(file_id, scope, name, symbol) {
+                        self.errors.push(error);
+                    }
+                }
+                Some((_, false)) => self.error(
+                    file_id,
+                    format!("`{}` is private to `{}`", name.text, module),
+                    name.span,
+                ),
+                None => self.error(
+                    file_id,
+                    format!("`{}` has no item named `{}`", module, name.text),
+                    name.span,
+                ),
+            }
+        }
+    }
+
+    /// # Declare
+    ///
+    /// Adds a name to a namespace. Importing the same item twice is allowed.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<ModuleError>` - An error if the name already refers to something else.
+    fn declare(
+        &self,
+        file_id: FileId,
+        scope: &mut HashMap<String, Symbol>,
+        name: &Name,
+        symbol: Symbol,
+    ) -> Option<ModuleError> {
+        match scope.get(&name.text) {
+            Some(existing) if *existing == symbol => None,
+            Some(_) => Some(ModuleError {
+                message: format!("`{}` is already declared in this file", name.text),
+                path: self.modules[file_id.0 as usize].path.clone(),
+                span: name.span,
+            }),
+            None => {
+                scope.insert(name.text.clone(), symbol);
+                None
+            }
+        }
+    }
+
+    fn error(&mut self, file_id: FileId, message: String, span: Span) {
+        self.errors.push(ModuleError {
+            message,
+            path: self.modules[file_id.0 as usize].path.clone(),
+            span,
+        });
+    }
+}
+
+/// # Is Exported
+///
+/// Checks whether other files can import a top-level item with this name.
+fn is_exported(name: &str) -> bool {
+    name != "main" && !name.starts_with('_')
+}
+
+/// # Normalize
+///
+/// Removes the `.` components of a path, and the `..` components that follow a directory, so
+/// `demos/./lib/../colors.zc` becomes `demos/colors.zc`. Doesn't touch the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal: PathBuf = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(normal.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::check::{Checker, check_uncaught_errors};
+    use crate::desugar;
+    use crate::parser::{NodeKind, Parser, StmtKind};
+    use crate::test_files::TestDir;
+    use crate::tokenizer::Tokenizer;
+
+    /// Writes `files` to a new directory and loads the first one, returning the loader, the
+    /// directory and the paths that were parsed in order. C headers are looked for in the
+    /// directory too.
+    fn load(test: &str, files: &[(&str, &str)]) -> (Loader, TestDir, Vec<PathBuf>) {
+        let dir: TestDir = TestDir::new(&format!("modules-{}", test), files);
+        let mut parsed: Vec<PathBuf> = Vec::new();
+        let mut loader: Loader = Loader::new().with_include_paths(vec![dir.path().to_path_buf()]);
+        loader
+            .load_root(
+                &dir.path().join(files[0].0),
+                files[0].1,
+                &mut |path, src, file_id| {
+                    parsed.push(path.to_path_buf());
+                    let c_settings: CSettings = CSettings::default();
+                    let tokens = Tokenizer::new(src.to_string(), &c_settings)
+                        .with_file_id(file_id)
+                        .tokenize();
+                    let mut parser: Parser = Parser::new(tokens, src);
+                    let items: Vec<Item> = parser.parse();
+                    assert_eq!(parser.errors(), &[]);
+                    Ok(items)
+                },
+            )
+            .unwrap();
+        (loader, dir, parsed)
+    }
+
+    #[test]
+    fn loads_every_module_once() {
+        let (loader, dir, parsed) = load(
+            "once",
+            &[
+                (
+                    "main.zc",
+                    "dive { Colors, helper } from \"./colors.zc\";\n\
+                     dive { paint } from \"./lib/paint.zc\";\n\
+                     dive { * } from std::io;\n\
+                     fun main() { paint(); }",
+                ),
+                (
+                    "colors.zc",
+                    "enum Colors { Red }\nfun helper() {}\nfun _private() {}",
+                ),
+                (
+                    "lib/paint.zc",
+                    "dive { Colors } from \"../colors.zc\";\n\
+                     fun helper() {}\n\
+                     fun paint() {}",
+                ),
+            ],
+        );
+        assert_eq!(loader.errors(), &[]);
+        assert_eq!(
+            parsed,
+            vec![
+                dir.path().join("main.zc"),
+                dir.path().join("colors.zc"),
+                dir.path().join("lib/paint.zc")
+            ]
+        );
+        let modules: &[Module] = loader.modules();
+        assert_eq!(
+            modules[0].scope.get("helper"),
+            Some(&Symbol::Item {
+                file_id: FileId(1),
+                index: 1
+            })
+        );
+        assert_eq!(
+            modules[2].scope.get("helper"),
+            Some(&Symbol::Item {
+                file_id: FileId(2),
+                index: 1
+            })
+        );
+        assert_eq!(
+            modules[2].scope.get("Colors"),
+            modules[0].scope.get("Colors")
+        );
+        assert_eq!(
+            modules[0].scope.get("println"),
+            Some(&Symbol::Std("println"))
+        );
+        assert_eq!(modules[1].scope.get("paint"), None);
+    }
+
+    #[test]
+    fn import_errors() {
+        let (loader, _, _) = load(
+            "errors",
+            &[
+                (
+                    "main.zc",
+                    "dive { a } from \"./a.zc\";\n\
+                     dive { missing, _hidden } from \"./b.zc\";\n\
+                     dive { nothing } from \"./nowhere.zc\";\n\
+                     dive { print } from std::fs;\n\
+                     dive { b } from \"./b.zc\";\n\
+                     fun b() {}",
+                ),
+                ("a.zc", "dive { b } from \"./b.zc\";\nfun a() {}"),
+                (
+                    "b.zc",
+                    "dive { a } from \"./a.zc\";\nfun b() {}\nfun _hidden() {}\nfun main() {}",
+                ),
+            ],
+        );
+        let errors: Vec<(String, String, u32)> = loader
+            .errors()
+            .iter()
+            .map(|error| {
+                let file: String = error
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string();
+                let message: String = match error.message.split_once(" at `") {
+                    Some((message, _)) => message.to_string(),
+                    None => error.message.clone(),
+                };
+                (file, message, error.span.line)
+            })
+            .collect();
+        let cycle: String = format!(
+            "Import cycle: {} -> {} -> {}",
+            loader.modules()[1].path.display(),
+            loader.modules()[2].path.display(),
+            loader.modules()[1].path.display()
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ("b.zc".to_string(), cycle, 1),
+                (
+                    "main.zc".to_string(),
+                    format!(
+                        "`{}` has no item named `missing`",
+                        loader.modules()[2].path.display()
+                    ),
+                    2
+                ),
+                (
+                    "main.zc".to_string(),
+                    format!(
+                        "`_hidden` is private to `{}`",
+                        loader.modules()[2].path.display()
+                    ),
+                    2
+                ),
+                (
+                    "main.zc".to_string(),
+                    "Can't find `./nowhere.zc`".to_string(),
+                    3
+                ),
+                (
+                    "main.zc".to_string(),
+                    "Unknown module `std::fs`".to_string(),
+                    4
+                ),
+                (
+                    "main.zc".to_string(),
+                    "`b` is already declared in this file".to_string(),
+                    5
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(
+            normalize(Path::new("demos/./lib/../colors.zc")),
+            PathBuf::from("demos/colors.zc")
+        );
+        assert_eq!(
+            normalize(Path::new("../a/./b.zc")),
+            PathBuf::from("../a/b.zc")
+        );
+    }
+
+    #[test]
+    fn c_header_imports() {
+        let (loader, _, _) = load(
+            "headers",
+            &[
+                (
+                    "main.zc",
+                    "bellyflop { * } from <clock.h>;\n\
+                     bellyflop { tick, missing } from <sys/tick.h>;\n\
+                     bellyflop { x } from <nowhere.h>;\n\
+                     dive { y } from <clock.h>;\n\
+                     fun now() {}",
+                ),
+                (
+                    "clock.h",
+                    "#include <sys/tick.h>\n\
+                     long now(void);\n\
+                     const char *_name(void);\n\
+                     char *clock_name(int id);\n",
+                ),
+                (
+                    "sys/tick.h",
+                    "typedef long time_t;\nvoid tick(time_t *out);\n",
+                ),
+            ],
+        );
+        let messages: Vec<(&str, u32)> = loader
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("`<sys/tick.h>` has no declaration named `missing`", 2),
+                ("Can't find the C header `<nowhere.h>`", 3),
+                (
+                    "C headers are imported with `bellyflop` instead of `dive`",
+                    4
+                ),
+            ]
+        );
+        let module: &Module = &loader.modules()[0];
+        let externs: Vec<String> = module
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Fun(fun) if fun.is_extern => Some(fun.name.text.clone()),
+                _ => None,
+            })
+            .collect();
+        // `now` is declared in the file, so the glob import doesn't replace it
+        assert_eq!(externs, vec!["tick", "clock_name"]);
+        assert_eq!(
+            module.scope.get("now"),
+            Some(&Symbol::Item {
+                file_id: FileId(0),
+                index: 4
+            })
+        );
+        assert!(module.scope.contains_key("time_t"));
+        assert!(!module.scope.contains_key("_name"));
+    }
+
+    #[test]
+    fn imported_items_are_checked() {
+        let (mut loader, _, _) = load(
+            "checked",
+            &[
+                (
+                    "main.zc",
+                    "dive { Person, Color } from \"./types.zc\";\n\
+                     fun main() {\n\
+                         p: Person = { Name = \"Tallen\" };\n\
+                         q: Person = { Gender = 0.1 };\n\
+                         c: Color = Color::Red;\n\
+                         switch c {\n\
+                             case Color::Red: {}\n\
+                         }\n\
+                         run();\n\
+                     }\n\
+                     dive { run } from \"./lib.zc\";\n\
+                     fun helper() {}",
+                ),
+                (
+                    "lib.zc",
+                    "enum IoError: Error { Closed }\n\
+                     fun helper() { throw IoError::Closed; }\n\
+                     fun run() { helper(); }",
+                ),
+                (
+                    "types.zc",
+                    "struct Person { Name: string = \"Unknown\", Age: i32 = 0, Gender: f32 }\n\
+                     enum Color { Red, Green }",
+                ),
+            ],
+        );
+        assert_eq!(loader.errors(), &[]);
+        for index in 0..loader.modules().len() {
+            let file_id: FileId = FileId(index as u32);
+            let declarations: desugar::Declarations =
+                desugar::Declarations::new(loader.resolve(file_id));
+            desugar::desugar(loader.items_mut(file_id), &declarations);
+        }
+        let module: &Module = &loader.modules()[0];
+        let mut checker: Checker = Checker::new(&module.items, loader.resolve(module.file_id));
+        checker.check();
+        let messages: Vec<(&str, u32)> = checker
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "`Person` needs a value for `Gender`, which has no default",
+                    3
+                ),
+                (
+                    "The switch over `Color` doesn't handle `Color::Green`, add a case for it or a `default`",
+                    6
+                ),
+            ]
+        );
+        // The imported defaults are filled in too
+        let ItemKind::Fun(main) = &module.items[1].kind else {
+            panic!("expected `main`");
+        };
+        let StmtKind::Let {
+            value: Some(value), ..
+        } = &main.body.stmts[1].kind
+        else {
+            panic!("expected a declaration");
+        };
+        let NodeKind::StructLiteral(inits) = &value.kind else {
+            panic!("expected a struct literal");
+        };
+        let names: Vec<&str> = inits.iter().map(|init| init.name.text.as_str()).collect();
+        assert_eq!(names, vec!["Gender", "Name", "Age"]);
+
+        // `run` calls the `helper` of `lib.zc`, not the one in `main.zc`
+        let warnings: Vec<(String, u32)> = check_uncaught_errors(loader.modules())
+            .iter()
+            .map(|warning| (warning.message.clone(), warning.span.line))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![(
+                "This error is never caught and will end the program when it leaves `main`"
+                    .to_string(),
+                9
+            )]
+        );
+    }
+}
@@ -1,37 +1,2127 @@
-use crate::tokenizer::{Token, TokenType};
+use crate::span::Span;
+use crate::tokenizer::{NumKind, Token, TokenType};
+use std::fmt;
 
+/// Binary operators. Assignments are [`AssignOp`]s and live in [`NodeKind::Assign`].
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Operator {
     Plus,
     Minus,
     Multiply,
     Divide,
     Modulus,
+    Equals,         // '=='
+    NotEquals,      // '!='
+    Less,           // '<'
+    LessEqual,      // '<='
+    Greater,        // '>'
+    GreaterEqual,   // '>='
+    And,            // '&&'
+    Or,             // '||'
+    BitAnd,         // '&'
+    BitOr,          // '|'
+    BitXor,         // '^'
+    LeftShift,      // '<<'
+    RightShift,     // '>>'
+    Range,          // '..' (end exclusive)
+    RangeInclusive, // '...'
 }
 
-pub enum Node {
-    List(Vec<Node>),
-    Int(i32),
-    Float(f32),
+/// Prefix operators.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum UnaryOp {
+    Negate, // '-x'
+    Not,    // '!x'
+    BitNot, // '~x'
+    Ref,    // '&x'
+    Deref,  // '*x'
+}
+
+/// Postfix operators.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PostfixOp {
+    Increment, // 'x++'
+    Decrement, // 'x--'
+}
+
+/// Assignment operators. Every compound assignment `x op= y` stands for `x = x op y`, and
+/// `x ~= y` for `x = ~y`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum AssignOp {
+    Assign,     // '='
+    Plus,       // '+='
+    Minus,      // '-='
+    Multiply,   // '*='
+    Divide,     // '/='
+    Modulus,    // '%='
+    BitAnd,     // '&='
+    BitOr,      // '|='
+    BitXor,     // '^='
+    BitNot,     // '~='
+    LeftShift,  // '<<='
+    RightShift, // '>>='
+}
+
+/// # Node
+///
+/// A node in the syntax tree. Every node keeps the span of the source it was parsed from so
+/// later phases can point diagnostics at it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Span,
+}
+
+impl Node {
+    /// # Children
+    ///
+    /// Returns the nodes directly inside this one, i.e. both operands of a `Binary`, in source
+    /// order.
+    pub fn children(&self) -> Vec<&Node> {
+        match &self.kind {
+            NodeKind::Tuple(items) | NodeKind::Array(items) => items.iter().collect(),
+            NodeKind::StructLiteral(fields) => fields.iter().map(|field| &field.value).collect(),
+            NodeKind::Unary { operand, .. } | NodeKind::Postfix { operand, .. } => vec![operand],
+            NodeKind::Binary { left, right, .. } => vec![left, right],
+            NodeKind::Assign { target, value, .. } => vec![target, value],
+            NodeKind::Ternary {
+                condition,
+                then_value,
+                else_value,
+            } => vec![condition, then_value, else_value],
+            NodeKind::Call { callee, args } => {
+                let mut children: Vec<&Node> = vec![callee];
+                children.extend(args.iter());
+                children
+            }
+            NodeKind::Index { base, index } => vec![base, index],
+            NodeKind::Field { base, .. } => vec![base],
+            NodeKind::Int(..)
+            | NodeKind::Float(..)
+            | NodeKind::Bool(_)
+            | NodeKind::Char(_)
+            | NodeKind::Str(_)
+            | NodeKind::Null
+            | NodeKind::Ident(_)
+            | NodeKind::Path(_)
+            | NodeKind::Macro(_)
+            | NodeKind::Error => Vec::new(),
+        }
+    }
+
+    /// # Children Mut
+    ///
+    /// [`Node::children`], but mutable.
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match &mut self.kind {
+            NodeKind::Tuple(items) | NodeKind::Array(items) => items.iter_mut().collect(),
+            NodeKind::StructLiteral(fields) => {
+                fields.iter_mut().map(|field| &mut field.value).collect()
+            }
+            NodeKind::Unary { operand, .. } | NodeKind::Postfix { operand, .. } => vec![operand],
+            NodeKind::Binary { left, right, .. } => vec![left, right],
+            NodeKind::Assign { target, value, .. } => vec![target, value],
+            NodeKind::Ternary {
+                condition,
+                then_value,
+                else_value,
+            } => vec![condition, then_value, else_value],
+            NodeKind::Call { callee, args } => {
+                let mut children: Vec<&mut Node> = vec![callee];
+                children.extend(args.iter_mut());
+                children
+            }
+            NodeKind::Index { base, index } => vec![base, index],
+            NodeKind::Field { base, .. } => vec![base],
+            NodeKind::Int(..)
+            | NodeKind::Float(..)
+            | NodeKind::Bool(_)
+            | NodeKind::Char(_)
+            | NodeKind::Str(_)
+            | NodeKind::Null
+            | NodeKind::Ident(_)
+            | NodeKind::Path(_)
+            | NodeKind::Macro(_)
+            | NodeKind::Error => Vec::new(),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum NodeKind {
+    Int(u128, Option<Primitive>), // '42', '42u8': the value and its type suffix
+    Float(f64, Option<Primitive>),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    Null,
+    Ident(String),
+    Path(Vec<String>),             // 'Colors::Red'
+    Macro(String),                 // '@get_datetime', called like a function
+    Tuple(Vec<Node>),              // '(x + y, x - y)'
+    Array(Vec<Node>),              // '[2..10]'
+    StructLiteral(Vec<FieldInit>), // '{ Name = "Tallen", Age = 17 }', typed by where it's used
+    Unary {
+        op: UnaryOp,
+        operand: Box<Node>,
+    },
+    Postfix {
+        op: PostfixOp,
+        operand: Box<Node>,
+    },
     Binary {
         left: Box<Node>,
         op: Operator,
         right: Box<Node>,
     },
+    Assign {
+        target: Box<Node>,
+        op: AssignOp,
+        value: Box<Node>,
+    },
+    Ternary {
+        condition: Box<Node>, // 'cond ? then_value : else_value'
+        then_value: Box<Node>,
+        else_value: Box<Node>,
+    },
+    Call {
+        callee: Box<Node>,
+        args: Vec<Node>,
+    },
+    Index {
+        base: Box<Node>,
+        index: Box<Node>,
+    },
+    Field {
+        base: Box<Node>,
+        name: String, // A field name, or a position for tuples ('pair.0')
+    },
+    Error, // Source that could not be parsed. The error has already been reported
+}
+
+/// # Field Init
+///
+/// A `Name = "Tallen"` field value in a struct literal.
+#[derive(PartialEq, Debug, Clone)]
+pub struct FieldInit {
+    pub name: Name,
+    pub value: Node,
+    pub span: Span,
+}
+
+/// # Name
+///
+/// A name written in the source, i.e. the name of a function or of an imported item, with the
+/// span it was written at.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Name {
+    pub text: String,
+    pub span: Span,
+}
+
+/// The built in types.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Primitive {
+    Char,
+    String,
+    I64,
+    U64,
+    I32,
+    U32,
+    I16,
+    U16,
+    I8,
+    U8,
+    Bool,
+    F32,
+    F64,
+    Void,
+}
+
+impl Primitive {
+    /// # Name
+    ///
+    /// The keyword the type is written as, i.e. `i32`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::Char => "char",
+            Primitive::String => "string",
+            Primitive::I64 => "i64",
+            Primitive::U64 => "u64",
+            Primitive::I32 => "i32",
+            Primitive::U32 => "u32",
+            Primitive::I16 => "i16",
+            Primitive::U16 => "u16",
+            Primitive::I8 => "i8",
+            Primitive::U8 => "u8",
+            Primitive::Bool => "bool",
+            Primitive::F32 => "f32",
+            Primitive::F64 => "f64",
+            Primitive::Void => "void",
+        }
+    }
+}
+
+/// # Type
+///
+/// A type written in the source, i.e. the `Vec<string>` in `args: Vec<string>`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum TypeKind {
+    Primitive(Primitive),
+    Named {
+        path: Vec<String>, // 'Person', 'std::io::File'
+        args: Vec<Type>,   // The generic arguments, i.e. `string` in 'Vec<string>'
+    },
+    Ref(Box<Type>),      // '&string'
+    Pointer(Box<Type>),  // '*char', for C interop
+    Optional(Box<Type>), // 'i32?'
+    Tuple(Vec<Type>),    // '(i32, i32)'
+}
+
+/// # Item
+///
+/// A top-level declaration. `docs` holds the `///` doc comments written above it, one entry
+/// per line.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub docs: Vec<String>,
+    pub span: Span,
+}
+
+impl Item {
+    /// # Name
+    ///
+    /// Returns the name the item declares, or `None` for an import.
+    pub fn name(&self) -> Option<&Name> {
+        match &self.kind {
+            ItemKind::Fun(fun) => Some(&fun.name),
+            ItemKind::Struct { name, .. }
+            | ItemKind::Enum { name, .. }
+            | ItemKind::Const { name, .. }
+            | ItemKind::Alias { name, .. } => Some(name),
+            ItemKind::Import(_) => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ItemKind {
+    Fun(Fun),
+    Struct {
+        name: Name,
+        fields: Vec<StructField>,
+    },
+    Enum {
+        name: Name,
+        variants: Vec<Variant>,
+        is_error: bool, // Declared as an error type, `enum IoError: Error { ... }`
+    },
+    Const {
+        name: Name,
+        ty: Type,
+        value: Node,
+    },
+    Alias {
+        name: Name,
+        ty: Type,
+    },
+    Import(Import),
+}
+
+/// # Fun
+///
+/// A function declaration: `fun name(params) -> T { ... }`. A `dive` function is run at
+/// compile time and called with `@name()`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Fun {
+    pub name: Name,
+    pub params: Vec<Param>,
+    pub return_type: Option<Type>,
+    pub is_strict_return: bool, // `: T` instead of `-> T`: every path has to return a value
+    pub is_comptime: bool,      // Declared with `dive` instead of `fun`
+    pub is_extern: bool,        // Imported from a C header with `bellyflop`, without a body
+    pub is_variadic: bool,      // Takes more arguments after `params`, like C's `printf`
+    pub body: Block,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Param {
+    pub name: Name,
+    pub ty: Type,
+}
+
+/// A struct field, i.e. `Name: string = "Unknown"`. Fields without a default have to be given
+/// a value whenever the struct is created.
+#[derive(PartialEq, Debug, Clone)]
+pub struct StructField {
+    pub docs: Vec<String>,
+    pub name: Name,
+    pub ty: Type,
+    pub default: Option<Node>,
+    pub span: Span,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Variant {
+    pub docs: Vec<String>,
+    pub name: Name,
+    pub value: Option<Node>, // An explicit discriminant, i.e. `test1 = 1`
+    pub span: Span,
+}
+
+/// # Import
+///
+/// `dive { names } from source;` or, for C headers, `bellyflop { names } from <header.h>;`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Import {
+    pub is_c_header: bool, // `bellyflop` instead of `dive`
+    pub names: Vec<Name>,  // Empty for a glob import
+    pub is_glob: bool,     // `{ * }`
+    pub source: ImportSource,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ImportSource {
+    File(String),        // '"./colors.zc"'
+    Module(Vec<String>), // 'std::io'
+    Header(String),      // '<time.h>'
+}
+
+/// # Block
+///
+/// A `{ ... }` block. Every block is its own scope: variables declared in it go away at the
+/// closing brace.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub span: Span, // Includes the braces
+}
+
+/// # Stmt
+///
+/// A statement inside a function body.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+impl Stmt {
+    /// # Children
+    ///
+    /// Returns the expressions directly in this statement, i.e. the condition of an `if`, and
+    /// the statements directly inside it, i.e. the ones in its blocks, in source order.
+    pub fn children(&self) -> (Vec<&Node>, Vec<&Stmt>) {
+        fn stmts(block: &Block) -> Vec<&Stmt> {
+            block.stmts.iter().collect()
+        }
+        match &self.kind {
+            StmtKind::Let { value, .. } | StmtKind::Return(value) => {
+                (value.iter().collect(), Vec::new())
+            }
+            StmtKind::Expr(node) | StmtKind::Throw(node) => (vec![node], Vec::new()),
+            StmtKind::Block(block) => (Vec::new(), stmts(block)),
+            StmtKind::If {
+                condition,
+                then_block,
+                else_branch,
+                ..
+            } => {
+                let mut children: Vec<&Stmt> = stmts(then_block);
+                children.extend(else_branch.as_deref());
+                (vec![condition], children)
+            }
+            StmtKind::While { condition, body } | StmtKind::DoWhile { body, condition } => {
+                (vec![condition], stmts(body))
+            }
+            StmtKind::For { iterable, body, .. } => (vec![iterable], stmts(body)),
+            StmtKind::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                let mut children: Vec<&Stmt> = stmts(body);
+                for catch in catches {
+                    children.extend(stmts(&catch.body));
+                }
+                children.extend(finally.iter().flat_map(stmts));
+                (Vec::new(), children)
+            }
+            StmtKind::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                let mut nodes: Vec<&Node> = vec![value];
+                let mut children: Vec<&Stmt> = Vec::new();
+                for case in cases {
+                    nodes.extend(case.patterns.iter());
+                    children.extend(stmts(&case.body));
+                }
+                children.extend(default.iter().flat_map(stmts));
+                (nodes, children)
+            }
+            StmtKind::Guarded { stmt, guard } => (vec![&guard.condition], vec![stmt]),
+            StmtKind::Break | StmtKind::Continue => (Vec::new(), Vec::new()),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum StmtKind {
+    Let {
+        name: Name,
+        ty: Type,
+        value: Option<Node>, // `current_time: time_t;` declares without a value
+        is_const: bool,
+    },
+    Expr(Node), // 'foo::bar();'
+    Block(Block),
+    If {
+        condition: Node,
+        then_block: Block,
+        else_branch: Option<Box<Stmt>>, // A `Block` for `else { }`, an `If` for `else if`
+        guard: Option<Span>,            // The `when debug` clause, for an `if` lowered from a guard
+    },
+    While {
+        condition: Node,
+        body: Block,
+    },
+    DoWhile {
+        body: Block,
+        condition: Node,
+    },
+    For {
+        binding: Name,
+        iterable: Node, // A range or a list, i.e. '[2..10]'
+        body: Block,
+    },
+    Break,
+    Continue,
+    Return(Option<Node>),
+    Throw(Node), // 'throw Error::DivisionByZero;'
+    Try {
+        body: Block,
+        catches: Vec<Catch>,
+        finally: Option<Block>, // Runs after the body and any catch, whether they threw or not
+    },
+    Switch {
+        value: Node,
+        cases: Vec<Case>,
+        default: Option<Block>,
+    },
+    Guarded {
+        stmt: Box<Stmt>, // The statement without its guard
+        guard: Guard,
+    },
+}
+
+/// # Catch
+///
+/// A `catch (const e: Error) { ... }` clause of a `try`. It catches errors of the binding's
+/// type; a `catch { ... }` without a binding catches every error.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Catch {
+    pub binding: Option<Param>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// # Case
+///
+/// A `case 1, 3..5: ...` arm of a `switch`. It runs when the switched value equals one of the
+/// patterns: a value, a range or an enum variant path like `Test::test1`. Cases don't fall
+/// through, so the body is just the statements up to the next `case`, `default` or `}`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Case {
+    pub patterns: Vec<Node>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// # Guard
+///
+/// A postfix `when cond` or `if cond` on a statement. The desugaring pass lowers guarded
+/// statements to ordinary `if`s, so both spellings mean the same thing.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Guard {
+    pub is_when: bool, // Written with `when` rather than `if`
+    pub condition: Node,
+    pub span: Span, // The whole guard clause, i.e. `when debug`
+}
+
+/// # Parse Error
+///
+/// A problem found while parsing, i.e. a missing `)` or a token that can't start an expression.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span, // The token the parser stopped at
+}
+
+impl Type {
+    /// # Simple Name
+    ///
+    /// Returns the name of a type written as a single name without generic arguments, i.e.
+    /// `Person`, which is how declared structs and enums are referred to.
+    pub fn simple_name(&self) -> Option<&str> {
+        match &self.kind {
+            TypeKind::Named { path, args } if path.len() == 1 && args.is_empty() => Some(&path[0]),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |types: &[Type]| {
+            types
+                .iter()
+                .map(|ty| ty.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match &self.kind {
+            TypeKind::Primitive(primitive) => write!(f, "{}", primitive.name()),
+            TypeKind::Named { path, args } if args.is_empty() => write!(f, "{}", path.join("::")),
+            TypeKind::Named { path, args } => write!(f, "{}<{}>", path.join("::"), join(args)),
+            TypeKind::Ref(inner) => write!(f, "&{}", inner),
+            TypeKind::Pointer(inner) => write!(f, "*{}", inner),
+            TypeKind::Optional(inner) => write!(f, "{}?", inner),
+            TypeKind::Tuple(items) => write!(f, "({})", join(items)),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[Line {}, Column {}] {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+/// What an infix token builds once both of its operands are parsed.
+enum Infix {
+    Binary(Operator),
+    Assign(AssignOp),
+    Ternary, // `?`, which also takes a `:` and a third operand
+}
+
+/// # Binding Powers
+///
+/// How tightly each operator binds, from loosest to tightest. The parser keeps extending an
+/// expression while the next operator binds at least as tightly as the one it is parsing the
+/// operand of.
+///
+/// | Operators                                        | Associativity |
+/// |--------------------------------------------------|---------------|
+/// | `=` `+=` `-=` `*=` `/=` `%=` `&=` `\|=` `^=` `~=` `<<=` `>>=` | right |
+/// | `? :`                                            | right         |
+/// | `..` `...`                                       | left          |
+/// | `\|\|`                                           | left          |
+/// | `&&`                                             | left          |
+/// | `==` `!=` `<` `>` `<=` `>=`                      | none          |
+/// | `\|`                                             | left          |
+/// | `^`                                              | left          |
+/// | `&`                                              | left          |
+/// | `<<` `>>`                                        | left          |
+/// | `+` `-`                                          | left          |
+/// | `*` `/` `%`                                      | left          |
+/// | prefix `-` `!` `~` `&` `*`                       | prefix        |
+/// | `++` `--` calls, indexing and `.field`           | postfix       |
+///
+/// Comparisons don't chain: `a < b < c` is an error rather than `(a < b) < c`. The middle of a
+/// ternary runs up to its `:` like a parenthesized expression, so `a ? b = c : d` is fine, and
+/// a ternary in the last operand nests: `a ? b : c ? d : e` is `a ? b : (c ? d : e)`.
+const ASSIGNMENT: u8 = 10;
+const TERNARY: u8 = 15;
+const RANGE: u8 = 20;
+const OR: u8 = 30;
+const AND: u8 = 40;
+const COMPARISON: u8 = 50;
+const BIT_OR: u8 = 60;
+const BIT_XOR: u8 = 70;
+const BIT_AND: u8 = 80;
+const SHIFT: u8 = 90;
+const SUM: u8 = 100;
+const PRODUCT: u8 = 110;
+const PREFIX: u8 = 120;
+const POSTFIX: u8 = 130;
+
+/// # Infix Binding Power
+///
+/// Returns what an infix token builds and its left and right binding powers, or `None` if the
+/// token isn't an infix operator. A left associative operator binds tighter on its right, so
+/// `a - b - c` is `(a - b) - c`; a right associative one binds tighter on its left.
+fn infix_binding_power(tok_type: &TokenType) -> Option<(Infix, u8, u8)> {
+    let left = |op: Operator, power: u8| Some((Infix::Binary(op), power, power + 1));
+    let assign = |op: AssignOp| Some((Infix::Assign(op), ASSIGNMENT + 1, ASSIGNMENT));
+    match tok_type {
+        TokenType::TokAssign => assign(AssignOp::Assign),
+        TokenType::TokPlusEqual => assign(AssignOp::Plus),
+        TokenType::TokMinusEqual => assign(AssignOp::Minus),
+        TokenType::TokTimesEqual => assign(AssignOp::Multiply),
+        TokenType::TokDivideEqual => assign(AssignOp::Divide),
+        TokenType::TokModuloEqual => assign(AssignOp::Modulus),
+        TokenType::TokBitAndEqual => assign(AssignOp::BitAnd),
+        TokenType::TokBitOrEqual => assign(AssignOp::BitOr),
+        TokenType::TokBitXorEqual => assign(AssignOp::BitXor),
+        TokenType::TokBitNotEqual => assign(AssignOp::BitNot),
+        TokenType::TokLeftShiftEqual => assign(AssignOp::LeftShift),
+        TokenType::TokRightShiftEqual => assign(AssignOp::RightShift),
+        TokenType::TokQuestion => Some((Infix::Ternary, TERNARY + 1, TERNARY)),
+        TokenType::TokRange => left(Operator::Range, RANGE),
+        TokenType::TokEllipsis => left(Operator::RangeInclusive, RANGE),
+        TokenType::TokOr => left(Operator::Or, OR),
+        TokenType::TokAnd => left(Operator::And, AND),
+        TokenType::TokEquals => left(Operator::Equals, COMPARISON),
+        TokenType::TokNotEquals => left(Operator::NotEquals, COMPARISON),
+        TokenType::TokLeftAngle => left(Operator::Less, COMPARISON),
+        TokenType::TokLessEqual => left(Operator::LessEqual, COMPARISON),
+        TokenType::TokRightAngle => left(Operator::Greater, COMPARISON),
+        TokenType::TokGreaterEqual => left(Operator::GreaterEqual, COMPARISON),
+        TokenType::TokBitOr => left(Operator::BitOr, BIT_OR),
+        TokenType::TokBitXor => left(Operator::BitXor, BIT_XOR),
+        TokenType::TokAmpersand => left(Operator::BitAnd, BIT_AND),
+        TokenType::TokLeftShift => left(Operator::LeftShift, SHIFT),
+        TokenType::TokRightShift => left(Operator::RightShift, SHIFT),
+        TokenType::TokPlus => left(Operator::Plus, SUM),
+        TokenType::TokMinus => left(Operator::Minus, SUM),
+        TokenType::TokAsterisk => left(Operator::Multiply, PRODUCT),
+        TokenType::TokDivide => left(Operator::Divide, PRODUCT),
+        TokenType::TokModulo => left(Operator::Modulus, PRODUCT),
+        _ => None,
+    }
+}
+
+/// # Prefix Op
+///
+/// Returns the unary operator a token stands for in front of an expression.
+fn prefix_op(tok_type: &TokenType) -> Option<UnaryOp> {
+    match tok_type {
+        TokenType::TokMinus => Some(UnaryOp::Negate),
+        TokenType::TokBang => Some(UnaryOp::Not),
+        TokenType::TokBitNot => Some(UnaryOp::BitNot),
+        TokenType::TokAmpersand => Some(UnaryOp::Ref),
+        TokenType::TokAsterisk => Some(UnaryOp::Deref),
+        _ => None,
+    }
+}
+
+/// # Primitive Of
+///
+/// Returns the built in type a type keyword or number suffix token stands for.
+fn primitive_of(tok_type: &TokenType) -> Option<Primitive> {
+    match tok_type {
+        TokenType::TokTypeChar => Some(Primitive::Char),
+        TokenType::TokTypeString => Some(Primitive::String),
+        TokenType::TokTypei64 => Some(Primitive::I64),
+        TokenType::TokTypeu64 => Some(Primitive::U64),
+        TokenType::TokTypei32 => Some(Primitive::I32),
+        TokenType::TokTypeu32 => Some(Primitive::U32),
+        TokenType::TokTypei16 => Some(Primitive::I16),
+        TokenType::TokTypeu16 => Some(Primitive::U16),
+        TokenType::TokTypei8 => Some(Primitive::I8),
+        TokenType::TokTypeu8 => Some(Primitive::U8),
+        TokenType::TokTypeBool => Some(Primitive::Bool),
+        TokenType::TokTypef32 => Some(Primitive::F32),
+        TokenType::TokTypef64 => Some(Primitive::F64),
+        TokenType::TokTypeVoid => Some(Primitive::Void),
+        _ => None,
+    }
 }
 
 pub struct Parser {
     tokens: Vec<Token>,
+    src: String, // The source the tokens were lexed from
     index: usize,
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, index: 0 }
+    /// # New
+    ///
+    /// Create a new parser. Newline tokens are dropped, statements end at `;` so line breaks
+    /// carry no meaning.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The tokens of a file, as returned by `Tokenizer::tokenize`.
+    /// * `src` - The source of the file, for syntax that is read as text, i.e. `<string.h>`.
+    pub fn new(tokens: Vec<Token>, src: &str) -> Self {
+        Parser {
+            tokens: tokens
+                .into_iter()
+                .filter(|token| token.tok_type != TokenType::TokNewline)
+                .collect(),
+            src: src.to_string(),
+            index: 0,
+            errors: Vec::new(),
+        }
     }
 
-    fn parse() {
-        let mut program: Vec<Node> = Vec::new();
-        program.push(Node::List(Vec::new()));
+    /// # Parse
+    ///
+    /// Parses a whole file into its top-level items. An item that fails to parse is reported in
+    /// [`Parser::errors`] and skipped, and parsing carries on with the next item.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut parser: parser::Parser = parser::Parser::new(tokens, src);
+    /// let items: Vec<parser::Item> = parser.parse();
+    /// for error in parser.errors() {
+    ///     zlog::err(&error.to_string(), &c_settings);
+    /// }
+    /// ```
+    pub fn parse(&mut self) -> Vec<Item> {
+        let mut items: Vec<Item> = Vec::new();
+        loop {
+            let docs: Vec<String> = self.take_doc_comments();
+            match self.peek(0).map(|token| token.tok_type.clone()) {
+                None | Some(TokenType::TokEOF) => break,
+                Some(TokenType::TokInnerDocComment) => self.consume(1),
+                Some(_) => match self.parse_item(docs) {
+                    Ok(item) => items.push(item),
+                    Err(error) => {
+                        self.errors.push(error);
+                        self.synchronize_item();
+                    }
+                },
+            }
+        }
+        items
+    }
+
+    /// # Errors
+    ///
+    /// Returns the errors found while parsing.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// # Parse Item
+    ///
+    /// Parses the top-level item starting at the current token.
+    ///
+    /// # Arguments
+    ///
+    /// * `docs` - The doc comments written above the item.
+    fn parse_item(&mut self, docs: Vec<String>) -> Result<Item, ParseError> {
+        let start: Span = self.span();
+        let kind: ItemKind = match self.peek(0).map(|token| token.tok_type.clone()) {
+            Some(TokenType::TokFun) => ItemKind::Fun(self.parse_fun(false)?),
+            Some(TokenType::TokDive)
+                if self.peek(1).map(|token| &token.tok_type) == Some(&TokenType::TokLeftBrace) =>
+            {
+                ItemKind::Import(self.parse_import()?)
+            }
+            Some(TokenType::TokDive) => ItemKind::Fun(self.parse_fun(true)?),
+            Some(TokenType::TokBellyflop) => ItemKind::Import(self.parse_import()?),
+            Some(TokenType::TokStruct) => self.parse_struct()?,
+            Some(TokenType::TokEnum) => self.parse_enum()?,
+            Some(TokenType::TokConst) => {
+                self.consume(1);
+                let name: Name = self.expect_name("Expected a constant name after `const`")?;
+                self.expect(
+                    TokenType::TokColon,
+                    "Expected `:` and a type after the name",
+                )?;
+                let ty: Type = self.parse_type()?;
+                self.expect(
+                    TokenType::TokAssign,
+                    "Expected `=` and a value after the type",
+                )?;
+                let value: Node = self.parse_expression()?;
+                self.expect(TokenType::TokSemi, "Expected `;` after the constant")?;
+                ItemKind::Const { name, ty, value }
+            }
+            Some(TokenType::TokAlias) => {
+                self.consume(1);
+                let name: Name = self.expect_name("Expected a type name after `type`")?;
+                self.expect(TokenType::TokAssign, "Expected `=` after the type name")?;
+                let ty: Type = self.parse_type()?;
+                self.expect(TokenType::TokSemi, "Expected `;` after the type alias")?;
+                ItemKind::Alias { name, ty }
+            }
+            _ => {
+                return Err(self.error(
+                    "Expected an item: `fun`, `struct`, `enum`, `const`, `type`, `dive` or `bellyflop`",
+                ));
+            }
+        };
+        Ok(Item {
+            kind,
+            docs,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    /// # Parse Fun
+    ///
+    /// Parses `fun name(params) -> T { ... }`, or the same with `dive` for a compile time
+    /// function. The return type may also be written `: T`, or left out for `void`.
+    fn parse_fun(&mut self, is_comptime: bool) -> Result<Fun, ParseError> {
+        self.consume(1);
+        let name: Name = match self.peek(0) {
+            Some(token) if token.tok_type == TokenType::TokMain => {
+                let name: Name = Name {
+                    text: "main".to_string(),
+                    span: token.span,
+                };
+                self.consume(1);
+                name
+            }
+            _ => self.expect_name("Expected a function name")?,
+        };
+        self.expect(
+            TokenType::TokLeftParen,
+            "Expected `(` after the function name",
+        )?;
+        let mut params: Vec<Param> = Vec::new();
+        while !self.match_token(TokenType::TokRightParen) {
+            let name: Name = self.expect_name("Expected a parameter name")?;
+            self.expect(
+                TokenType::TokColon,
+                "Expected `:` and a type after the parameter",
+            )?;
+            let ty: Type = self.parse_type()?;
+            params.push(Param { name, ty });
+            if self.match_token(TokenType::TokComma) {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        self.expect(
+            TokenType::TokRightParen,
+            "Expected `)` after the parameters",
+        )?;
+        let is_strict_return: bool = self.match_token(TokenType::TokColon);
+        let return_type: Option<Type> = if is_strict_return || self.match_token(TokenType::TokArrow)
+        {
+            self.consume(1);
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        let body: Block = self.parse_block()?;
+        Ok(Fun {
+            name,
+            params,
+            return_type,
+            is_strict_return,
+            is_comptime,
+            is_extern: false,
+            is_variadic: false,
+            body,
+        })
+    }
+
+    /// # Parse Struct
+    ///
+    /// Parses `struct Name { Field: T = default, ... }`.
+    fn parse_struct(&mut self) -> Result<ItemKind, ParseError> {
+        self.consume(1);
+        let name: Name = self.expect_name("Expected a struct name after `struct`")?;
+        self.expect(
+            TokenType::TokLeftBrace,
+            "Expected `{` after the struct name",
+        )?;
+        let mut fields: Vec<StructField> = Vec::new();
+        loop {
+            let docs: Vec<String> = self.take_doc_comments();
+            if self.match_token(TokenType::TokRightBrace) {
+                break;
+            }
+            let name: Name = self.expect_name("Expected a field name or `}`")?;
+            self.expect(
+                TokenType::TokColon,
+                "Expected `:` and a type after the field name",
+            )?;
+            let ty: Type = self.parse_type()?;
+            let default: Option<Node> = if self.match_token(TokenType::TokAssign) {
+                self.consume(1);
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            fields.push(StructField {
+                docs,
+                span: name.span.to(self.previous_span()),
+                name,
+                ty,
+                default,
+            });
+            if self.match_token(TokenType::TokComma) {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        self.expect(
+            TokenType::TokRightBrace,
+            "Expected `,` or `}` after the field",
+        )?;
+        self.skip_semi();
+        Ok(ItemKind::Struct { name, fields })
+    }
+
+    /// # Parse Enum
+    ///
+    /// Parses `enum Name { variant, variant = value, ... }`, or `enum Name: Error { ... }` for
+    /// an error type.
+    fn parse_enum(&mut self) -> Result<ItemKind, ParseError> {
+        self.consume(1);
+        let name: Name = self.expect_name("Expected an enum name after `enum`")?;
+        let is_error: bool = self.match_token(TokenType::TokColon);
+        if is_error {
+            self.consume(1);
+            let error: Name = self.expect_name("Expected `Error` after `:`")?;
+            if error.text != "Error" {
+                return Err(ParseError {
+                    message: format!(
+                        "An enum can only be declared as an `Error`, not `{}`",
+                        error.text
+                    ),
+                    span: error.span,
+                });
+            }
+        }
+        self.expect(TokenType::TokLeftBrace, "Expected `{` after the enum name")?;
+        let mut variants: Vec<Variant> = Vec::new();
+        loop {
+            let docs: Vec<String> = self.take_doc_comments();
+            if self.match_token(TokenType::TokRightBrace) {
+                break;
+            }
+            let name: Name = self.expect_name("Expected a variant name or `}`")?;
+            let value: Option<Node> = if self.match_token(TokenType::TokAssign) {
+                self.consume(1);
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            variants.push(Variant {
+                docs,
+                span: name.span.to(self.previous_span()),
+                name,
+                value,
+            });
+            if self.match_token(TokenType::TokComma) {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        self.expect(
+            TokenType::TokRightBrace,
+            "Expected `,` or `}` after the variant",
+        )?;
+        self.skip_semi();
+        Ok(ItemKind::Enum {
+            name,
+            variants,
+            is_error,
+        })
+    }
+
+    /// # Parse Import
+    ///
+    /// Parses `dive { names } from source;` and `bellyflop { names } from <header.h>;`. The
+    /// source is a file path string, a module path like `std::io` or a C header in `<...>`.
+    fn parse_import(&mut self) -> Result<Import, ParseError> {
+        let is_c_header: bool = self.match_token(TokenType::TokBellyflop);
+        self.consume(1);
+        self.expect(
+            TokenType::TokLeftBrace,
+            "Expected `{` and the names to import",
+        )?;
+        let mut names: Vec<Name> = Vec::new();
+        let is_glob: bool = self.match_token(TokenType::TokAsterisk);
+        if is_glob {
+            self.consume(1);
+        } else {
+            while !self.match_token(TokenType::TokRightBrace) {
+                names.push(self.expect_name("Expected a name to import")?);
+                if self.match_token(TokenType::TokComma) {
+                    self.consume(1);
+                } else {
+                    break;
+                }
+            }
+        }
+        self.expect(
+            TokenType::TokRightBrace,
+            "Expected `,` or `}` after the name",
+        )?;
+        self.expect(
+            TokenType::TokFrom,
+            "Expected `from` after the imported names",
+        )?;
+        let source: ImportSource = match self.peek(0).cloned() {
+            Some(Token {
+                tok_type: TokenType::TokStringLiteral,
+                value,
+                ..
+            }) => {
+                self.consume(1);
+                ImportSource::File(value.unwrap_or_default())
+            }
+            Some(Token {
+                tok_type: TokenType::TokLeftAngle,
+                span,
+                ..
+            }) => {
+                // The header name is taken as written, since parts of it like `string` in
+                // `<string.h>` lex as keywords
+                self.consume(1);
+                while self.peek(0).is_some_and(|token| {
+                    !matches!(
+                        token.tok_type,
+                        TokenType::TokRightAngle | TokenType::TokSemi | TokenType::TokEOF
+                    )
+                }) {
+                    self.consume(1);
+                }
+                let end: usize = match self.peek(0) {
+                    Some(token) if token.tok_type == TokenType::TokRightAngle => token.span.start,
+                    _ => return Err(self.error("Expected `>` after the header name")),
+                };
+                let header: &str = self.src[span.end..end].trim();
+                if header.is_empty() || header.contains(char::is_whitespace) {
+                    return Err(self.error("Expected a header name like `<time.h>`"));
+                }
+                let header: String = header.to_string();
+                self.consume(1);
+                ImportSource::Header(header)
+            }
+            _ => {
+                let path: Vec<String> = self.parse_path_names(
+                    "Expected a file path string, a module path or a `<header>`",
+                )?;
+                ImportSource::Module(path)
+            }
+        };
+        self.expect(TokenType::TokSemi, "Expected `;` after the import")?;
+        Ok(Import {
+            is_c_header,
+            names,
+            is_glob,
+            source,
+        })
+    }
+
+    /// # Parse Type
+    ///
+    /// Parses a type: a built in type, a (generic) named type, `&T`, `*T` or a tuple `(T, U)`.
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
+        let Some(token) = self.peek(0).cloned() else {
+            return Err(self.error("Expected a type"));
+        };
+        let primitive: Option<Primitive> = primitive_of(&token.tok_type);
+        let kind: TypeKind = if let Some(primitive) = primitive {
+            self.consume(1);
+            TypeKind::Primitive(primitive)
+        } else {
+            match token.tok_type {
+                TokenType::TokAmpersand | TokenType::TokAsterisk => {
+                    self.consume(1);
+                    let inner: Box<Type> = Box::new(self.parse_type()?);
+                    if token.tok_type == TokenType::TokAmpersand {
+                        TypeKind::Ref(inner)
+                    } else {
+                        TypeKind::Pointer(inner)
+                    }
+                }
+                TokenType::TokLeftParen => {
+                    self.consume(1);
+                    let mut items: Vec<Type> = Vec::new();
+                    while !self.match_token(TokenType::TokRightParen) {
+                        items.push(self.parse_type()?);
+                        if self.match_token(TokenType::TokComma) {
+                            self.consume(1);
+                        } else {
+                            break;
+                        }
+                    }
+                    self.expect(
+                        TokenType::TokRightParen,
+                        "Expected `,` or `)` in the tuple type",
+                    )?;
+                    TypeKind::Tuple(items)
+                }
+                TokenType::TokIdentifier => {
+                    let path: Vec<String> = self.parse_path_names("Expected a type")?;
+                    let mut args: Vec<Type> = Vec::new();
+                    if self.match_token(TokenType::TokLeftAngle) {
+                        self.consume(1);
+                        while !self.match_token(TokenType::TokRightAngle) {
+                            args.push(self.parse_type()?);
+                            if self.match_token(TokenType::TokComma) {
+                                self.consume(1);
+                            } else {
+                                break;
+                            }
+                        }
+                        self.split_right_shift();
+                        self.expect(
+                            TokenType::TokRightAngle,
+                            "Expected `,` or `>` after the type",
+                        )?;
+                    }
+                    TypeKind::Named { path, args }
+                }
+                _ => return Err(self.error("Expected a type")),
+            }
+        };
+        let mut ty: Type = Type {
+            kind,
+            span: token.span.to(self.previous_span()),
+        };
+        // A `?` after a type always makes it optional. In an expression it starts a ternary,
+        // and the two never meet because types and expressions are parsed separately.
+        while self.match_token(TokenType::TokQuestion) {
+            self.consume(1);
+            ty = Type {
+                span: ty.span.to(self.previous_span()),
+                kind: TypeKind::Optional(Box::new(ty)),
+            };
+        }
+        Ok(ty)
+    }
+
+    /// # Split Right Shift
+    ///
+    /// Splits a `>>` at the current position into two `>`s, so the closing brackets of nested
+    /// generics like `Vec<Vec<i32>>` are read one at a time.
+    fn split_right_shift(&mut self) {
+        let Some(token) = self.tokens.get(self.index) else {
+            return;
+        };
+        if token.tok_type != TokenType::TokRightShift {
+            return;
+        }
+        let mut first: Token = token.clone();
+        first.tok_type = TokenType::TokRightAngle;
+        first.span.end = first.span.start + 1;
+        let mut second: Token = first.clone();
+        second.span.start += 1;
+        second.span.end += 1;
+        second.span.column += 1;
+        first.trailing_trivia.clear();
+        second.leading_trivia.clear();
+        self.tokens
+            .splice(self.index..self.index + 1, [first, second]);
+    }
+
+    /// # Parse Block
+    ///
+    /// Parses a `{ ... }` block of statements. A statement that fails to parse is reported in
+    /// [`Parser::errors`] and skipped, and parsing carries on with the next statement.
+    fn parse_block(&mut self) -> Result<Block, ParseError> {
+        let open: Span = self.expect(TokenType::TokLeftBrace, "Expected `{` to start a block")?;
+        let mut stmts: Vec<Stmt> = Vec::new();
+        loop {
+            self.take_doc_comments();
+            match self.peek(0).map(|token| token.tok_type.clone()) {
+                Some(TokenType::TokRightBrace) => break,
+                None | Some(TokenType::TokEOF) => {
+                    return Err(ParseError {
+                        message: "Unclosed `{`: expected a matching `}`".to_string(),
+                        span: open,
+                    });
+                }
+                Some(_) => match self.parse_stmt() {
+                    Ok(stmt) => stmts.push(stmt),
+                    Err(error) => {
+                        self.errors.push(error);
+                        self.synchronize_stmt();
+                    }
+                },
+            }
+        }
+        let close: Span = self.span();
+        self.consume(1);
+        Ok(Block {
+            stmts,
+            span: open.to(close),
+        })
+    }
+
+    /// # Parse Stmt
+    ///
+    /// Parses the statement starting at the current token.
+    fn parse_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let start: Span = self.span();
+        let tok_type: Option<TokenType> = self.peek(0).map(|token| token.tok_type.clone());
+        let kind: StmtKind = match tok_type {
+            Some(TokenType::TokLeftBrace) => StmtKind::Block(self.parse_block()?),
+            Some(TokenType::TokIf) => return self.parse_if(),
+            Some(TokenType::TokWhile) => {
+                self.consume(1);
+                let condition: Node = self.parse_head_expression()?;
+                let body: Block = self.parse_block()?;
+                StmtKind::While { condition, body }
+            }
+            Some(TokenType::TokDo) => {
+                self.consume(1);
+                let body: Block = self.parse_block()?;
+                self.expect(TokenType::TokWhile, "Expected `while` after the `do` block")?;
+                let condition: Node = self.parse_expression()?;
+                self.expect(
+                    TokenType::TokSemi,
+                    "Expected `;` after the `do`-`while` condition",
+                )?;
+                StmtKind::DoWhile { body, condition }
+            }
+            Some(TokenType::TokTry) => self.parse_try()?,
+            Some(TokenType::TokSwitch) => self.parse_switch()?,
+            Some(TokenType::TokFor) => {
+                self.consume(1);
+                let binding: Name = self.expect_name("Expected a loop variable after `for`")?;
+                self.expect(TokenType::TokIn, "Expected `in` after the loop variable")?;
+                let iterable: Node = self.parse_head_expression()?;
+                let body: Block = self.parse_block()?;
+                StmtKind::For {
+                    binding,
+                    iterable,
+                    body,
+                }
+            }
+            _ => return self.parse_simple_stmt(),
+        };
+        Ok(Stmt {
+            kind,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    /// # Parse Simple Stmt
+    ///
+    /// Parses a statement that ends in `;`: a declaration, `break`, `continue`, `return` or an
+    /// expression. Any of them but a declaration may be followed by a `when` or `if` guard,
+    /// i.e. `println("Debug is on") when debug;`.
+    fn parse_simple_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let start: Span = self.span();
+        let tok_type: Option<TokenType> = self.peek(0).map(|token| token.tok_type.clone());
+        let (kind, message): (StmtKind, &str) = match tok_type {
+            Some(TokenType::TokBreak) | Some(TokenType::TokContinue) => {
+                self.consume(1);
+                let kind: StmtKind = if tok_type == Some(TokenType::TokBreak) {
+                    StmtKind::Break
+                } else {
+                    StmtKind::Continue
+                };
+                (kind, "Expected `;` after the jump")
+            }
+            Some(TokenType::TokThrow) => {
+                self.consume(1);
+                (
+                    StmtKind::Throw(self.parse_expression()?),
+                    "Expected `;` after the thrown value",
+                )
+            }
+            Some(TokenType::TokReturn) => {
+                self.consume(1);
+                let value: Option<Node> = if self.match_token(TokenType::TokSemi)
+                    || self.match_token(TokenType::TokWhen)
+                    || self.match_token(TokenType::TokIf)
+                {
+                    None
+                } else {
+                    Some(self.parse_expression()?)
+                };
+                (
+                    StmtKind::Return(value),
+                    "Expected `;` after the return value",
+                )
+            }
+            Some(TokenType::TokConst) => {
+                self.consume(1);
+                (self.parse_let(true)?, "Expected `;` after the declaration")
+            }
+            Some(TokenType::TokIdentifier)
+                if self.peek(1).map(|token| &token.tok_type) == Some(&TokenType::TokColon) =>
+            {
+                (self.parse_let(false)?, "Expected `;` after the declaration")
+            }
+            _ => (
+                StmtKind::Expr(self.parse_expression()?),
+                "Expected `;` after the expression",
+            ),
+        };
+        let stmt: Stmt = Stmt {
+            kind,
+            span: start.to(self.previous_span()),
+        };
+
+        let guard: Option<Guard> = match self.peek(0).map(|token| token.tok_type.clone()) {
+            Some(TokenType::TokWhen) | Some(TokenType::TokIf) => {
+                let guard_start: Span = self.span();
+                let is_when: bool = self.match_token(TokenType::TokWhen);
+                self.consume(1);
+                let condition: Node = self.parse_expression()?;
+                let guard: Guard = Guard {
+                    is_when,
+                    span: guard_start.to(condition.span),
+                    condition,
+                };
+                if matches!(stmt.kind, StmtKind::Let { .. }) {
+                    return Err(ParseError {
+                        message: "A declaration can't be guarded, declare the variable first"
+                            .to_string(),
+                        span: guard.span,
+                    });
+                }
+                Some(guard)
+            }
+            _ => None,
+        };
+        self.expect(TokenType::TokSemi, message)?;
+        Ok(match guard {
+            Some(guard) => Stmt {
+                kind: StmtKind::Guarded {
+                    stmt: Box::new(stmt),
+                    guard,
+                },
+                span: start.to(self.previous_span()),
+            },
+            None => Stmt {
+                span: start.to(self.previous_span()),
+                ..stmt
+            },
+        })
+    }
+
+    /// # Parse Try
+    ///
+    /// Parses `try { } catch (const e: Error) { } finally { }`. A `try` takes any number of
+    /// catches and an optional `finally`, but needs at least one of the two.
+    fn parse_try(&mut self) -> Result<StmtKind, ParseError> {
+        self.consume(1);
+        let body: Block = self.parse_block()?;
+        let mut catches: Vec<Catch> = Vec::new();
+        while self.match_token(TokenType::TokCatch) {
+            let start: Span = self.span();
+            self.consume(1);
+            let binding: Option<Param> = if self.match_token(TokenType::TokLeftParen) {
+                self.consume(1);
+                if self.match_token(TokenType::TokConst) {
+                    self.consume(1);
+                }
+                let name: Name = self.expect_name("Expected a name for the caught error")?;
+                self.expect(
+                    TokenType::TokColon,
+                    "Expected `:` and the error type to catch",
+                )?;
+                let ty: Type = self.parse_type()?;
+                self.expect(
+                    TokenType::TokRightParen,
+                    "Expected `)` after the error type",
+                )?;
+                Some(Param { name, ty })
+            } else {
+                None
+            };
+            let body: Block = self.parse_block()?;
+            catches.push(Catch {
+                binding,
+                span: start.to(body.span),
+                body,
+            });
+        }
+        let finally: Option<Block> = if self.match_token(TokenType::TokFinally) {
+            self.consume(1);
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+        if catches.is_empty() && finally.is_none() {
+            return Err(self.error("Expected `catch` or `finally` after the `try` block"));
+        }
+        Ok(StmtKind::Try {
+            body,
+            catches,
+            finally,
+        })
+    }
+
+    /// # Parse Switch
+    ///
+    /// Parses `switch value { case a, b: ... case 1..5: ... default: ... }`. There may be at most
+    /// one `default`, and it has to come last. A case that fails to parse skips the rest of the
+    /// switch.
+    fn parse_switch(&mut self) -> Result<StmtKind, ParseError> {
+        self.consume(1);
+        let value: Node = self.parse_head_expression()?;
+        let open: Span = self.expect(
+            TokenType::TokLeftBrace,
+            "Expected `{` to start the switch cases",
+        )?;
+        let mut cases: Vec<Case> = Vec::new();
+        let mut default: Option<Block> = None;
+        loop {
+            match self.peek(0).map(|token| token.tok_type.clone()) {
+                Some(TokenType::TokRightBrace) => break,
+                None | Some(TokenType::TokEOF) => {
+                    return Err(ParseError {
+                        message: "Unclosed `{`: expected a matching `}`".to_string(),
+                        span: open,
+                    });
+                }
+                Some(_) => {
+                    if let Err(error) = self.parse_case(&mut cases, &mut default) {
+                        self.errors.push(error);
+                        while !self.match_token(TokenType::TokRightBrace)
+                            && !self.match_token(TokenType::TokEOF)
+                            && self.peek(0).is_some()
+                        {
+                            self.synchronize_stmt();
+                        }
+                    }
+                }
+            }
+        }
+        self.consume(1);
+        Ok(StmtKind::Switch {
+            value,
+            cases,
+            default,
+        })
+    }
+
+    /// # Parse Case
+    ///
+    /// Parses a `case ...:` or `default:` and its statements, adding it to the switch.
+    fn parse_case(
+        &mut self,
+        cases: &mut Vec<Case>,
+        default: &mut Option<Block>,
+    ) -> Result<(), ParseError> {
+        let start: Span = self.span();
+        match self.peek(0).map(|token| token.tok_type.clone()) {
+            Some(TokenType::TokCase) | Some(TokenType::TokDefault) if default.is_some() => {
+                Err(self.error("The `default` case must be the last one in a switch"))
+            }
+            Some(TokenType::TokCase) => {
+                self.consume(1);
+                let (patterns, _): (Vec<Node>, Span) =
+                    self.parse_comma_list(TokenType::TokColon, "Expected `:` after the case")?;
+                if patterns.is_empty() {
+                    return Err(ParseError {
+                        message: "Expected a value to match after `case`".to_string(),
+                        span: start,
+                    });
+                }
+                let body: Block = self.parse_case_body()?;
+                cases.push(Case {
+                    patterns,
+                    span: start.to(self.previous_span()),
+                    body,
+                });
+                Ok(())
+            }
+            Some(TokenType::TokDefault) => {
+                self.consume(1);
+                self.expect(TokenType::TokColon, "Expected `:` after `default`")?;
+                *default = Some(self.parse_case_body()?);
+                Ok(())
+            }
+            _ => Err(self.error("Expected `case`, `default` or `}`")),
+        }
+    }
+
+    /// # Parse Case Body
+    ///
+    /// Parses the statements after a `case ...:` or `default:`, up to the next case or the end
+    /// of the switch. The block's span covers the statements, or is empty if there are none.
+    fn parse_case_body(&mut self) -> Result<Block, ParseError> {
+        let mut stmts: Vec<Stmt> = Vec::new();
+        let start: Span = self.span();
+        loop {
+            self.take_doc_comments();
+            match self.peek(0).map(|token| token.tok_type.clone()) {
+                None
+                | Some(TokenType::TokEOF)
+                | Some(TokenType::TokRightBrace)
+                | Some(TokenType::TokCase)
+                | Some(TokenType::TokDefault) => break,
+                Some(_) => match self.parse_stmt() {
+                    Ok(stmt) => stmts.push(stmt),
+                    Err(error) => {
+                        self.errors.push(error);
+                        self.synchronize_stmt();
+                    }
+                },
+            }
+        }
+        let span: Span = match (stmts.first(), stmts.last()) {
+            (Some(first), Some(last)) => first.span.to(last.span),
+            _ => Span {
+                end: start.start,
+                ..start
+            },
+        };
+        Ok(Block { stmts, span })
+    }
+
+    /// # Parse Let
+    ///
+    /// Parses a variable declaration `name: T = value`, up to the `;`. The value may be left out.
+    fn parse_let(&mut self, is_const: bool) -> Result<StmtKind, ParseError> {
+        let name: Name = self.expect_name("Expected a variable name")?;
+        self.expect(
+            TokenType::TokColon,
+            "Expected `:` and a type after the name",
+        )?;
+        let ty: Type = self.parse_type()?;
+        let value: Option<Node> = if self.match_token(TokenType::TokAssign) {
+            self.consume(1);
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        Ok(StmtKind::Let {
+            name,
+            ty,
+            value,
+            is_const,
+        })
+    }
+
+    /// # Parse If
+    ///
+    /// Parses `if cond { } else if cond { } else { }`. Each `else if` becomes an `If` in the
+    /// `else_branch` of the one before it.
+    fn parse_if(&mut self) -> Result<Stmt, ParseError> {
+        let start: Span = self.span();
+        self.consume(1);
+        let condition: Node = self.parse_head_expression()?;
+        let then_block: Block = self.parse_block()?;
+        let else_branch: Option<Box<Stmt>> = if self.match_token(TokenType::TokElse) {
+            self.consume(1);
+            if self.match_token(TokenType::TokIf) {
+                Some(Box::new(self.parse_if()?))
+            } else {
+                let block: Block = self.parse_block()?;
+                Some(Box::new(Stmt {
+                    span: block.span,
+                    kind: StmtKind::Block(block),
+                }))
+            }
+        } else {
+            None
+        };
+        Ok(Stmt {
+            kind: StmtKind::If {
+                condition,
+                then_block,
+                else_branch,
+                guard: None,
+            },
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    /// # Synchronize Stmt
+    ///
+    /// Skips past the rest of a statement that failed to parse: up to and including the next
+    /// `;` outside of any braces, or up to the `}` that closes the enclosing block.
+    fn synchronize_stmt(&mut self) {
+        let mut depth: usize = 0;
+        while let Some(token) = self.peek(0) {
+            match token.tok_type {
+                TokenType::TokEOF => return,
+                TokenType::TokLeftBrace => depth += 1,
+                TokenType::TokRightBrace if depth == 0 => return,
+                TokenType::TokRightBrace => depth -= 1,
+                TokenType::TokSemi if depth == 0 => {
+                    self.consume(1);
+                    return;
+                }
+                _ => {}
+            }
+            self.consume(1);
+        }
+    }
+
+    /// # Synchronize Item
+    ///
+    /// Skips past the rest of an item that failed to parse: up to the next `;` or closing `}`
+    /// outside of any braces, or up to the keyword that starts the next item.
+    fn synchronize_item(&mut self) {
+        let start: usize = self.index;
+        let mut depth: usize = 0;
+        while let Some(token) = self.peek(0) {
+            match token.tok_type {
+                TokenType::TokEOF => return,
+                TokenType::TokFun
+                | TokenType::TokStruct
+                | TokenType::TokEnum
+                | TokenType::TokConst
+                | TokenType::TokAlias
+                | TokenType::TokDive
+                | TokenType::TokBellyflop
+                | TokenType::TokDocComment
+                    if depth == 0 && self.index > start =>
+                {
+                    return;
+                }
+                TokenType::TokLeftBrace => depth += 1,
+                TokenType::TokRightBrace if depth <= 1 => {
+                    self.consume(1);
+                    self.skip_semi();
+                    return;
+                }
+                TokenType::TokRightBrace => depth -= 1,
+                TokenType::TokSemi if depth == 0 => {
+                    self.consume(1);
+                    return;
+                }
+                _ => {}
+            }
+            self.consume(1);
+        }
+    }
+
+    /// # Skip Semi
+    ///
+    /// Consumes an optional `;`, as allowed after the `}` of a struct or enum.
+    fn skip_semi(&mut self) {
+        if self.match_token(TokenType::TokSemi) {
+            self.consume(1);
+        }
+    }
+
+    /// # Parse Expression
+    ///
+    /// Parses an expression starting at the current token. See [`ASSIGNMENT`] and the constants
+    /// after it for the precedence of every operator.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Node, ParseError>` - The expression, or an error pointing at the token the
+    ///   parser stopped at.
+    fn parse_expression(&mut self) -> Result<Node, ParseError> {
+        self.parse_expression_with(0)
+    }
+
+    /// # Parse Head Expression
+    ///
+    /// Parses the expression before the block of an `if`, `while`, `for` or `switch`. A `{`
+    /// there starts the block, not a struct literal, so `while { }` is missing its condition.
+    fn parse_head_expression(&mut self) -> Result<Node, ParseError> {
+        if self.match_token(TokenType::TokLeftBrace) {
+            return Err(self.error("Expected an expression"));
+        }
+        self.parse_expression()
+    }
+
+    /// # Parse Expression With
+    ///
+    /// The Pratt loop behind [`Parser::parse_expression`]. Parses a prefix expression and then
+    /// keeps taking operators whose left binding power is at least `min_power`.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_power` - The loosest operator the expression may extend over.
+    fn parse_expression_with(&mut self, min_power: u8) -> Result<Node, ParseError> {
+        let mut left: Node = self.parse_prefix()?;
+        while let Some(token) = self.peek(0) {
+            if POSTFIX >= min_power && self.is_postfix(&token.tok_type) {
+                left = self.parse_postfix(left)?;
+                continue;
+            }
+            let Some((infix, left_power, right_power)) = infix_binding_power(&token.tok_type)
+            else {
+                break;
+            };
+            if left_power < min_power {
+                break;
+            }
+            self.consume(1);
+            if let Infix::Ternary = infix {
+                let then_value: Node = self.parse_expression()?;
+                self.expect(
+                    TokenType::TokColon,
+                    "Expected `:` and the value for when the condition is false",
+                )?;
+                let else_value: Node = self.parse_expression_with(right_power)?;
+                left = Node {
+                    span: left.span.to(else_value.span),
+                    kind: NodeKind::Ternary {
+                        condition: Box::new(left),
+                        then_value: Box::new(then_value),
+                        else_value: Box::new(else_value),
+                    },
+                };
+                continue;
+            }
+            let right: Node = self.parse_expression_with(right_power)?;
+            let span: Span = left.span.to(right.span);
+            let kind: NodeKind = match infix {
+                Infix::Binary(op) => NodeKind::Binary {
+                    left: Box::new(left),
+                    op,
+                    right: Box::new(right),
+                },
+                Infix::Assign(op) => NodeKind::Assign {
+                    target: Box::new(left),
+                    op,
+                    value: Box::new(right),
+                },
+                Infix::Ternary => unreachable!("ternaries are built above"),
+            };
+            left = Node { kind, span };
+
+            if left_power == COMPARISON
+                && let Some(next) = self.peek(0)
+                && matches!(
+                    infix_binding_power(&next.tok_type),
+                    Some((_, COMPARISON, _))
+                )
+            {
+                return Err(self.error("Comparison operators cannot be chained, use `&&`"));
+            }
+        }
+        Ok(left)
+    }
+
+    /// # Parse Prefix
+    ///
+    /// Parses a prefix operator applied to an expression, or a primary expression: a literal,
+    /// a name or path, or a parenthesized, tuple, array or struct literal expression.
+    fn parse_prefix(&mut self) -> Result<Node, ParseError> {
+        let Some(token) = self.peek(0).cloned() else {
+            return Err(self.error("Expected an expression"));
+        };
+        if let Some(op) = prefix_op(&token.tok_type) {
+            self.consume(1);
+            let operand: Node = self.parse_expression_with(PREFIX)?;
+            return Ok(Node {
+                span: token.span.to(operand.span),
+                kind: NodeKind::Unary {
+                    op,
+                    operand: Box::new(operand),
+                },
+            });
+        }
+
+        let kind: NodeKind = match token.tok_type {
+            TokenType::TokNumLiteral => {
+                let Some(number) = &token.number else {
+                    return Err(self.error("Expected an expression"));
+                };
+                let suffix: Option<Primitive> = number.suffix.as_ref().and_then(primitive_of);
+                match number.kind {
+                    NumKind::Int => match number.int_value() {
+                        Some(value) => NodeKind::Int(value, suffix),
+                        None => return Err(self.error("Integer literal is too large")),
+                    },
+                    NumKind::Float => {
+                        NodeKind::Float(number.float_value().unwrap_or_default(), suffix)
+                    }
+                }
+            }
+            TokenType::TokStringLiteral => NodeKind::Str(token.value.clone().unwrap_or_default()),
+            TokenType::TokCharLiteral => NodeKind::Char(
+                token
+                    .value
+                    .as_deref()
+                    .and_then(|value| value.chars().next())
+                    .unwrap_or_default(),
+            ),
+            TokenType::TokTrue => NodeKind::Bool(true),
+            TokenType::TokFalse => NodeKind::Bool(false),
+            TokenType::TokNull => NodeKind::Null,
+            TokenType::TokError => NodeKind::Error,
+            TokenType::TokIdentifier => return self.parse_path(),
+            TokenType::TokAt => {
+                self.consume(1);
+                let name: String = self.expect_identifier("Expected a macro name after `@`")?;
+                return Ok(Node {
+                    kind: NodeKind::Macro(name),
+                    span: token.span.to(self.previous_span()),
+                });
+            }
+            TokenType::TokLeftParen => return self.parse_group(),
+            TokenType::TokLeftBracket => {
+                self.consume(1);
+                let (items, end) =
+                    self.parse_comma_list(TokenType::TokRightBracket, "Expected `]`")?;
+                return Ok(Node {
+                    kind: NodeKind::Array(items),
+                    span: token.span.to(end),
+                });
+            }
+            TokenType::TokLeftBrace => return self.parse_struct_literal(),
+            _ => return Err(self.error("Expected an expression")),
+        };
+        self.consume(1);
+        Ok(Node {
+            kind,
+            span: token.span,
+        })
+    }
+
+    /// # Parse Struct Literal
+    ///
+    /// Parses `{ Name = "Tallen", Age = 17 }`. A trailing comma is allowed.
+    fn parse_struct_literal(&mut self) -> Result<Node, ParseError> {
+        let start: Span = self.span();
+        self.consume(1);
+        let mut fields: Vec<FieldInit> = Vec::new();
+        while !self.match_token(TokenType::TokRightBrace) {
+            let name: Name = self.expect_name("Expected a field name or `}`")?;
+            self.expect(
+                TokenType::TokAssign,
+                "Expected `=` and a value after the field name",
+            )?;
+            let value: Node = self.parse_expression()?;
+            fields.push(FieldInit {
+                span: name.span.to(value.span),
+                name,
+                value,
+            });
+            if self.match_token(TokenType::TokComma) {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        let end: Span = self.expect(
+            TokenType::TokRightBrace,
+            "Expected `,` or `}` after the field value",
+        )?;
+        Ok(Node {
+            kind: NodeKind::StructLiteral(fields),
+            span: start.to(end),
+        })
+    }
+
+    /// # Parse Path
+    ///
+    /// Parses a name, or a path of names joined by `::` such as `Colors::Red`.
+    fn parse_path(&mut self) -> Result<Node, ParseError> {
+        let start: Span = self.span();
+        let mut names: Vec<String> = self.parse_path_names("Expected a name")?;
+        let kind: NodeKind = if names.len() == 1 {
+            NodeKind::Ident(names.remove(0))
+        } else {
+            NodeKind::Path(names)
+        };
+        Ok(Node {
+            kind,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    /// # Parse Path Names
+    ///
+    /// Parses names joined by `::` and returns them in order.
+    fn parse_path_names(&mut self, message: &str) -> Result<Vec<String>, ParseError> {
+        let mut names: Vec<String> = vec![self.expect_identifier(message)?];
+        while self.match_token(TokenType::TokDoubleColon) {
+            self.consume(1);
+            names.push(self.expect_identifier("Expected a name after `::`")?);
+        }
+        Ok(names)
+    }
+
+    /// # Parse Group
+    ///
+    /// Parses the expression after a `(`. A single expression is just grouped, `()` and a comma
+    /// separated list are tuples.
+    fn parse_group(&mut self) -> Result<Node, ParseError> {
+        let start: Span = self.span();
+        self.consume(1);
+        let is_empty: bool = self.match_token(TokenType::TokRightParen);
+        let (mut items, end) = self.parse_comma_list(TokenType::TokRightParen, "Expected `)`")?;
+        let is_trailing_comma: bool = self.tokens[self.index - 2].tok_type == TokenType::TokComma;
+        let span: Span = start.to(end);
+        if items.len() == 1 && !is_trailing_comma && !is_empty {
+            let mut inner: Node = items.remove(0);
+            inner.span = span;
+            return Ok(inner);
+        }
+        Ok(Node {
+            kind: NodeKind::Tuple(items),
+            span,
+        })
+    }
+
+    /// # Is Postfix
+    ///
+    /// Checks whether a token continues the expression before it as a postfix operator.
+    fn is_postfix(&self, tok_type: &TokenType) -> bool {
+        matches!(
+            tok_type,
+            TokenType::TokIncrement
+                | TokenType::TokDecrement
+                | TokenType::TokLeftParen
+                | TokenType::TokLeftBracket
+                | TokenType::TokDot
+        )
+    }
+
+    /// # Parse Postfix
+    ///
+    /// Applies the postfix operator at the current token to `operand`: `++`, `--`, a call,
+    /// an index or a field access.
+    fn parse_postfix(&mut self, operand: Node) -> Result<Node, ParseError> {
+        let Some(token) = self.peek(0).cloned() else {
+            return Ok(operand);
+        };
+        let start: Span = operand.span;
+        self.consume(1);
+        let (kind, end): (NodeKind, Span) = match token.tok_type {
+            TokenType::TokIncrement | TokenType::TokDecrement => {
+                let op: PostfixOp = if token.tok_type == TokenType::TokIncrement {
+                    PostfixOp::Increment
+                } else {
+                    PostfixOp::Decrement
+                };
+                let kind: NodeKind = NodeKind::Postfix {
+                    op,
+                    operand: Box::new(operand),
+                };
+                (kind, token.span)
+            }
+            TokenType::TokLeftParen => {
+                let (args, end) = self
+                    .parse_comma_list(TokenType::TokRightParen, "Expected `)` after arguments")?;
+                let kind: NodeKind = NodeKind::Call {
+                    callee: Box::new(operand),
+                    args,
+                };
+                (kind, end)
+            }
+            TokenType::TokLeftBracket => {
+                let index: Node = self.parse_expression()?;
+                let end: Span = self.expect(TokenType::TokRightBracket, "Expected `]`")?;
+                let kind: NodeKind = NodeKind::Index {
+                    base: Box::new(operand),
+                    index: Box::new(index),
+                };
+                (kind, end)
+            }
+            _ => {
+                let name: String = match self.peek(0) {
+                    Some(Token {
+                        tok_type: TokenType::TokNumLiteral,
+                        number: Some(number),
+                        ..
+                    }) if number.kind == NumKind::Int => number.digits.clone(),
+                    Some(Token {
+                        tok_type: TokenType::TokIdentifier,
+                        value: Some(name),
+                        ..
+                    }) => name.clone(),
+                    _ => return Err(self.error("Expected a field name after `.`")),
+                };
+                self.consume(1);
+                let kind: NodeKind = NodeKind::Field {
+                    base: Box::new(operand),
+                    name,
+                };
+                (kind, self.previous_span())
+            }
+        };
+        Ok(Node {
+            kind,
+            span: start.to(end),
+        })
+    }
+
+    /// # Parse Comma List
+    ///
+    /// Parses comma separated expressions up to and including `close`. A trailing comma is
+    /// allowed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(Vec<Node>, Span), ParseError>` - The expressions and the span of `close`.
+    fn parse_comma_list(
+        &mut self,
+        close: TokenType,
+        message: &str,
+    ) -> Result<(Vec<Node>, Span), ParseError> {
+        let mut items: Vec<Node> = Vec::new();
+        while !self.match_token(close.clone()) {
+            items.push(self.parse_expression()?);
+            if self.match_token(TokenType::TokComma) {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        let end: Span = self.expect(close, message)?;
+        Ok((items, end))
+    }
+
+    /// # Expect
+    ///
+    /// Consumes the current token if it has the expected type.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Span, ParseError>` - The span of the token, or an error with `message`.
+    fn expect(&mut self, expected: TokenType, message: &str) -> Result<Span, ParseError> {
+        if self.match_token(expected) {
+            let span: Span = self.span();
+            self.consume(1);
+            Ok(span)
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    /// # Expect Identifier
+    ///
+    /// Consumes the current token if it is an identifier and returns its name.
+    fn expect_identifier(&mut self, message: &str) -> Result<String, ParseError> {
+        match self.peek(0) {
+            Some(Token {
+                tok_type: TokenType::TokIdentifier,
+                value: Some(name),
+                ..
+            }) => {
+                let name: String = name.clone();
+                self.consume(1);
+                Ok(name)
+            }
+            _ => Err(self.error(message)),
+        }
+    }
+
+    /// # Expect Name
+    ///
+    /// Consumes the current token if it is an identifier and returns it as a [`Name`].
+    fn expect_name(&mut self, message: &str) -> Result<Name, ParseError> {
+        let span: Span = self.span();
+        let text: String = self.expect_identifier(message)?;
+        Ok(Name { text, span })
+    }
+
+    /// # Error
+    ///
+    /// Builds an error pointing at the current token.
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            span: self.span(),
+        }
     }
 
     fn match_token(&self, expected: TokenType) -> bool {
@@ -46,9 +2136,829 @@ impl Parser {
         }
     }
 
+    /// # Take Doc Comments
+    ///
+    /// Consumes the `///` doc comments (and the newlines between them) at the current position
+    /// and returns their text, one entry per line. The caller attaches them to the `fun`,
+    /// `struct`, `enum` or `const` item that follows.
+    fn take_doc_comments(&mut self) -> Vec<String> {
+        let mut docs: Vec<String> = Vec::new();
+        while let Some(token) = self.peek(0) {
+            match token.tok_type {
+                TokenType::TokDocComment => docs.push(token.value.clone().unwrap_or_default()),
+                TokenType::TokNewline => {}
+                _ => break,
+            }
+            self.consume(1);
+        }
+        docs
+    }
+
+    /// # Span
+    ///
+    /// Returns the span of the current token, or an empty span if there are no tokens left.
+    fn span(&self) -> Span {
+        self.peek(0).map(|token| token.span).unwrap_or_default()
+    }
+
+    /// # Previous Span
+    ///
+    /// Returns the span of the last consumed token.
+    fn previous_span(&self) -> Span {
+        self.index
+            .checked_sub(1)
+            .and_then(|index| self.tokens.get(index))
+            .map(|token| token.span)
+            .unwrap_or_default()
+    }
+
     fn consume(&mut self, amount: usize) {
         if self.index + amount <= self.tokens.len() {
             self.index += amount
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::tokenizer::Tokenizer;
+
+    fn parser(src: &str) -> Parser {
+        let c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        let tokens: Vec<Token> = tokenizer.tokenize();
+        assert!(tokenizer.errors().is_empty());
+        Parser::new(tokens, src)
+    }
+
+    /// Parses a whole source as one expression and prints it as an s-expression, so the
+    /// grouping the parser picked is easy to read.
+    fn expr(src: &str) -> String {
+        let mut parser: Parser = parser(src);
+        let node: Node = parser.parse_expression().unwrap();
+        assert!(
+            parser.match_token(TokenType::TokEOF),
+            "{} was not fully parsed",
+            src
+        );
+        sexpr(&node)
+    }
+
+    fn expr_error(src: &str) -> String {
+        parser(src).parse_expression().unwrap_err().message
+    }
+
+    fn sexpr(node: &Node) -> String {
+        let list = |head: String, nodes: &[&Node]| {
+            let parts: Vec<String> = nodes.iter().map(|node| sexpr(node)).collect();
+            format!("({} {})", head, parts.join(" "))
+        };
+        match &node.kind {
+            NodeKind::Int(value, _) => value.to_string(),
+            NodeKind::Float(value, _) => format!("{:?}", value),
+            NodeKind::Bool(value) => value.to_string(),
+            NodeKind::Char(value) => format!("{:?}", value),
+            NodeKind::Str(value) => format!("{:?}", value),
+            NodeKind::Null => "Null".to_string(),
+            NodeKind::Ident(name) => name.clone(),
+            NodeKind::Path(names) => names.join("::"),
+            NodeKind::Macro(name) => format!("@{}", name),
+            NodeKind::Tuple(items) | NodeKind::Array(items) => {
+                list("list".to_string(), &items.iter().collect::<Vec<&Node>>())
+            }
+            NodeKind::StructLiteral(fields) => {
+                let parts: Vec<String> = fields
+                    .iter()
+                    .map(|field| format!("(= {} {})", field.name.text, sexpr(&field.value)))
+                    .collect();
+                format!("(struct {})", parts.join(" "))
+            }
+            NodeKind::Unary { op, operand } => list(format!("{:?}", op), &[operand.as_ref()]),
+            NodeKind::Postfix { op, operand } => list(format!("Post{:?}", op), &[operand.as_ref()]),
+            NodeKind::Binary { left, op, right } => {
+                list(format!("{:?}", op), &[left.as_ref(), right.as_ref()])
+            }
+            NodeKind::Assign { target, op, value } => {
+                list(format!("{:?}=", op), &[target.as_ref(), value.as_ref()])
+            }
+            NodeKind::Ternary {
+                condition,
+                then_value,
+                else_value,
+            } => list(
+                "?".to_string(),
+                &[condition.as_ref(), then_value.as_ref(), else_value.as_ref()],
+            ),
+            NodeKind::Call { callee, args } => {
+                let mut nodes: Vec<&Node> = vec![callee];
+                nodes.extend(args.iter());
+                list("call".to_string(), &nodes)
+            }
+            NodeKind::Index { base, index } => {
+                list("index".to_string(), &[base.as_ref(), index.as_ref()])
+            }
+            NodeKind::Field { base, name } => list(format!(".{}", name), &[base.as_ref()]),
+            NodeKind::Error => "error".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_ast_test_demo() {
+        let mut parser: Parser = parser(include_str!("../demos/ast_test.zc"));
+        while !parser.match_token(TokenType::TokReturn) {
+            parser.consume(1);
+        }
+        parser.consume(1);
+        let node: Node = parser.parse_expression().unwrap();
+        assert!(parser.match_token(TokenType::TokSemi));
+        assert_eq!(sexpr(&node), "(Plus 41 1)");
+        assert_eq!(
+            (node.span.line, node.span.column, node.span.len()),
+            (3, 12, 4)
+        );
+    }
+
+    #[test]
+    fn binary_precedence() {
+        assert_eq!(expr("1 + 2 * 3"), "(Plus 1 (Multiply 2 3))");
+        assert_eq!(expr("1 * 2 + 3 % 4"), "(Plus (Multiply 1 2) (Modulus 3 4))");
+        assert_eq!(expr("1 - 2 - 3"), "(Minus (Minus 1 2) 3)");
+        assert_eq!(expr("a << 1 + b"), "(LeftShift a (Plus 1 b))");
+        assert_eq!(expr("a & b ^ c | d"), "(BitOr (BitXor (BitAnd a b) c) d)");
+        assert_eq!(expr("a | b == c"), "(Equals (BitOr a b) c)");
+        assert_eq!(
+            expr("a == b && c < d || !e"),
+            "(Or (And (Equals a b) (Less c d)) (Not e))"
+        );
+        assert_eq!(expr("0..n + 1"), "(Range 0 (Plus n 1))");
+        assert_eq!(expr("1...10"), "(RangeInclusive 1 10)");
+        assert_eq!(expr("(1 + 2) * 3"), "(Multiply (Plus 1 2) 3)");
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        assert_eq!(expr("a = b = c + 1"), "(Assign= a (Assign= b (Plus c 1)))");
+        assert_eq!(expr("x <<= y += 2"), "(LeftShift= x (Plus= y 2))");
+        assert_eq!(expr("x ~= y"), "(BitNot= x y)");
+    }
+
+    #[test]
+    fn unary_and_postfix_operators() {
+        assert_eq!(expr("-a * b"), "(Multiply (Negate a) b)");
+        assert_eq!(expr("- -a"), "(Negate (Negate a))");
+        assert_eq!(
+            expr("*p = ~x & y"),
+            "(Assign= (Deref p) (BitAnd (BitNot x) y))"
+        );
+        assert_eq!(expr("&x.y"), "(Ref (.y x))");
+        assert_eq!(expr("-i++"), "(Negate (PostIncrement i))");
+        assert_eq!(expr("i-- - 1"), "(Minus (PostDecrement i) 1)");
+        assert_eq!(expr("a * -b"), "(Multiply a (Negate b))");
+    }
+
+    #[test]
+    fn calls_paths_and_literals() {
+        assert_eq!(expr("foo::bar()"), "(call foo::bar)");
+        assert_eq!(
+            expr("ctime(&t).to_string()"),
+            "(call (.to_string (call ctime (Ref t))))"
+        );
+        assert_eq!(expr("xs[i + 1].0"), "(.0 (index xs (Plus i 1)))");
+        assert_eq!(expr("(x + y, x - y)"), "(list (Plus x y) (Minus x y))");
+        assert_eq!(expr("(x,)"), "(list x)");
+        assert_eq!(expr("[2..10]"), "(list (Range 2 10))");
+        assert_eq!(
+            expr("f(1.5, 'c', \"s\", true, Null,)"),
+            "(call f 1.5 'c' \"s\" true Null)"
+        );
+    }
+
+    #[test]
+    fn expression_errors() {
+        assert_eq!(
+            expr_error("a < b < c"),
+            "Comparison operators cannot be chained, use `&&`"
+        );
+        assert_eq!(expr_error("1 +"), "Expected an expression");
+        assert_eq!(expr_error("f(1, 2"), "Expected `)` after arguments");
+        assert_eq!(expr_error("a.+"), "Expected a field name after `.`");
+        assert_eq!(
+            expr_error("340282366920938463463374607431768211456"),
+            "Integer literal is too large"
+        );
+    }
+
+    fn items(src: &str) -> Vec<Item> {
+        let mut parser: Parser = parser(src);
+        let items: Vec<Item> = parser.parse();
+        assert_eq!(parser.errors(), &[]);
+        items
+    }
+
+    fn type_name(ty: &Type) -> String {
+        match &ty.kind {
+            TypeKind::Primitive(primitive) => format!("{:?}", primitive),
+            TypeKind::Named { path, args } if args.is_empty() => path.join("::"),
+            TypeKind::Named { path, args } => {
+                let args: Vec<String> = args.iter().map(type_name).collect();
+                format!("{}<{}>", path.join("::"), args.join(", "))
+            }
+            TypeKind::Ref(inner) => format!("&{}", type_name(inner)),
+            TypeKind::Pointer(inner) => format!("*{}", type_name(inner)),
+            TypeKind::Optional(inner) => format!("{}?", type_name(inner)),
+            TypeKind::Tuple(items) => {
+                let items: Vec<String> = items.iter().map(type_name).collect();
+                format!("({})", items.join(", "))
+            }
+        }
+    }
+
+    #[test]
+    fn parses_syntax_demo_items() {
+        let mut parser: Parser = parser(include_str!("../demos/syntax.zc"));
+        let items: Vec<Item> = parser.parse();
+        assert_eq!(parser.errors(), &[]);
+        let names: Vec<String> = items
+            .iter()
+            .map(|item| match &item.kind {
+                ItemKind::Fun(fun) if fun.is_comptime => format!("dive {}", fun.name.text),
+                ItemKind::Fun(fun) => format!("fun {}", fun.name.text),
+                ItemKind::Struct { name, .. } => format!("struct {}", name.text),
+                ItemKind::Enum { name, .. } => format!("enum {}", name.text),
+                ItemKind::Const { name, .. } => format!("const {}", name.text),
+                ItemKind::Alias { name, .. } => format!("type {}", name.text),
+                ItemKind::Import(import) => format!("import {:?}", import.source),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "import File(\"./colors.zc\")",
+                "import Module([\"std\", \"io\"])",
+                "import File(\"./time.zc\")",
+                "import Header(\"time.h\")",
+                "dive get_datetime",
+                "const DATE",
+                "enum Test",
+                "struct Person",
+                "fun main",
+                "fun change_val",
+                "fun can_error",
+                "fun void_function",
+                "fun add_tuple",
+                "fun add_tuple_three",
+                "fun colon_return_type",
+                "fun colon_return_tuple",
+                "fun arrow_return",
+            ]
+        );
+        assert_eq!(
+            items[0].docs,
+            vec![
+                "Documentation comments",
+                "# Supports markdown",
+                "It's just like rust!!"
+            ]
+        );
+    }
+
+    #[test]
+    fn header_names_are_read_as_written() {
+        let mut parser: Parser = parser(
+            "bellyflop { strlen } from <string.h>;\n\
+             bellyflop { * } from <sys/types.h>;\n\
+             bellyflop { x } from <string h>;\n\
+             bellyflop { y } from <if.h;",
+        );
+        let items: Vec<Item> = parser.parse();
+        let sources: Vec<&ImportSource> = items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Import(import) => Some(&import.source),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                &ImportSource::Header("string.h".to_string()),
+                &ImportSource::Header("sys/types.h".to_string()),
+            ]
+        );
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("Expected a header name like `<time.h>`", 3),
+                ("Expected `>` after the header name", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_fields_keep_their_defaults() {
+        let items: Vec<Item> = items(
+            "struct Person {\n    /// Shown to users\n    Name: string = \"Unknown\",\n    Gender: f32,\n}",
+        );
+        let ItemKind::Struct { name, fields } = &items[0].kind else {
+            panic!("expected a struct");
+        };
+        assert_eq!(name.text, "Person");
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].docs, vec!["Shown to users"]);
+        assert_eq!(fields[0].name.text, "Name");
+        assert_eq!(type_name(&fields[0].ty), "String");
+        assert_eq!(
+            fields[0].default.as_ref().map(sexpr),
+            Some("\"Unknown\"".to_string())
+        );
+        assert_eq!(fields[1].default, None);
+        assert_eq!((fields[1].span.line, fields[1].span.column), (4, 5));
+    }
+
+    #[test]
+    fn function_signatures() {
+        let items: Vec<Item> = items(
+            "/// Adds\nfun add(a: &i32, b: Vec<Vec<u8>>) -> (i32, *char) { return a; }\n\
+             fun strict(): i32 { { } }\nfun main() {}",
+        );
+        let funs: Vec<&Fun> = items
+            .iter()
+            .map(|item| match &item.kind {
+                ItemKind::Fun(fun) => fun,
+                _ => panic!("expected a function"),
+            })
+            .collect();
+        let params: Vec<(String, String)> = funs[0]
+            .params
+            .iter()
+            .map(|param| (param.name.text.clone(), type_name(&param.ty)))
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                ("a".to_string(), "&I32".to_string()),
+                ("b".to_string(), "Vec<Vec<U8>>".to_string()),
+            ]
+        );
+        assert_eq!(
+            funs[0].return_type.as_ref().map(type_name).unwrap(),
+            "(I32, *Char)"
+        );
+        assert_eq!(funs[0].body.stmts.len(), 1);
+        assert_eq!(items[0].docs, vec!["Adds"]);
+        assert!(!funs[0].is_strict_return);
+        assert!(funs[1].is_strict_return);
+        assert_eq!(funs[1].body.stmts.len(), 1);
+        assert_eq!(funs[2].name.text, "main");
+        assert_eq!(funs[2].return_type, None);
+        assert_eq!(items[1].span.line, 3);
+    }
+
+    #[test]
+    fn enums_consts_and_aliases() {
+        let items: Vec<Item> = items(
+            "enum Test { test1, test2 = 5, };\nconst DATE: string = @get_datetime();\ntype Bytes = Vec<u8>;",
+        );
+        let ItemKind::Enum { variants, .. } = &items[0].kind else {
+            panic!("expected an enum");
+        };
+        let variants: Vec<(&str, Option<String>)> = variants
+            .iter()
+            .map(|variant| {
+                (
+                    variant.name.text.as_str(),
+                    variant.value.as_ref().map(sexpr),
+                )
+            })
+            .collect();
+        assert_eq!(
+            variants,
+            vec![("test1", None), ("test2", Some("5".to_string()))]
+        );
+        let ItemKind::Const { ty, value, .. } = &items[1].kind else {
+            panic!("expected a constant");
+        };
+        assert_eq!(
+            (type_name(ty), sexpr(value)),
+            ("String".to_string(), "(call @get_datetime)".to_string())
+        );
+        let ItemKind::Alias { name, ty } = &items[2].kind else {
+            panic!("expected a type alias");
+        };
+        assert_eq!(
+            (name.text.as_str(), type_name(ty)),
+            ("Bytes", "Vec<U8>".to_string())
+        );
+    }
+
+    #[test]
+    fn enums_can_be_declared_as_errors() {
+        let mut parser: Parser = parser(
+            "enum IoError: Error { Closed }
+enum Color { Red }
+enum Bad: Color { Red }",
+        );
+        let items: Vec<Item> = parser.parse();
+        let errors: Vec<bool> = items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Enum { is_error, .. } => Some(*is_error),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(errors, vec![true, false]);
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![("An enum can only be declared as an `Error`, not `Color`", 3)]
+        );
+    }
+
+    #[test]
+    fn item_errors_skip_to_the_next_item() {
+        let mut parser: Parser =
+            parser("const X: i32 = ;\nstruct A { x i32 }\nfun ok() {}\nlet y = 1;\nenum E { a }");
+        let items: Vec<Item> = parser.parse();
+        assert_eq!(items.len(), 2);
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("Expected an expression", 1),
+                ("Expected `:` and a type after the field name", 2),
+                (
+                    "Expected an item: `fun`, `struct`, `enum`, `const`, `type`, `dive` or `bellyflop`",
+                    4
+                ),
+            ]
+        );
+    }
+
+    /// Parses `body` as the body of a function and returns its statements.
+    fn stmts(body: &str) -> Vec<Stmt> {
+        let items: Vec<Item> = items(&format!("fun f() {{\n{}\n}}", body));
+        match &items[0].kind {
+            ItemKind::Fun(fun) => fun.body.stmts.clone(),
+            _ => panic!("expected a function"),
+        }
+    }
+
+    #[test]
+    fn declarations_and_simple_statements() {
+        let stmts: Vec<Stmt> = stmts(
+            "current_time: time_t;\nconst x: i32 = 1 + 2;\nfoo::bar();\n{ break; continue; }\nreturn;\nreturn x;",
+        );
+        let StmtKind::Let {
+            name,
+            ty,
+            value: None,
+            is_const: false,
+        } = &stmts[0].kind
+        else {
+            panic!("expected a declaration without a value");
+        };
+        assert_eq!(
+            (name.text.as_str(), type_name(ty)),
+            ("current_time", "time_t".to_string())
+        );
+        let StmtKind::Let {
+            value: Some(value),
+            is_const: true,
+            ..
+        } = &stmts[1].kind
+        else {
+            panic!("expected a const declaration");
+        };
+        assert_eq!(sexpr(value), "(Plus 1 2)");
+        assert_eq!((stmts[1].span.line, stmts[1].span.column), (3, 1));
+        assert!(matches!(&stmts[2].kind, StmtKind::Expr(node) if sexpr(node) == "(call foo::bar)"));
+        let StmtKind::Block(block) = &stmts[3].kind else {
+            panic!("expected a block");
+        };
+        assert_eq!(
+            block
+                .stmts
+                .iter()
+                .map(|stmt| stmt.kind.clone())
+                .collect::<Vec<StmtKind>>(),
+            vec![StmtKind::Break, StmtKind::Continue]
+        );
+        assert_eq!(stmts[4].kind, StmtKind::Return(None));
+        assert!(matches!(&stmts[5].kind, StmtKind::Return(Some(node)) if sexpr(node) == "x"));
+    }
+
+    #[test]
+    fn control_flow_statements() {
+        let stmts: Vec<Stmt> = stmts(
+            "if x == 42 { a; } else if y { b; } else { c; }\n\
+             while condition == true { print(i); }\n\
+             do { i++; } while i < 10;\n\
+             for i in [2..10] { print(i); }",
+        );
+        let StmtKind::If {
+            condition,
+            then_block,
+            else_branch: Some(else_if),
+            guard: None,
+        } = &stmts[0].kind
+        else {
+            panic!("expected an if");
+        };
+        assert_eq!(sexpr(condition), "(Equals x 42)");
+        assert_eq!(then_block.stmts.len(), 1);
+        let StmtKind::If {
+            condition,
+            else_branch: Some(else_block),
+            ..
+        } = &else_if.kind
+        else {
+            panic!("expected an else if");
+        };
+        assert_eq!(sexpr(condition), "y");
+        assert!(matches!(&else_block.kind, StmtKind::Block(block) if block.stmts.len() == 1));
+        assert_eq!(
+            stmts[0].span.len(),
+            "if x == 42 { a; } else if y { b; } else { c; }".len()
+        );
+
+        let StmtKind::While { condition, body } = &stmts[1].kind else {
+            panic!("expected a while");
+        };
+        assert_eq!(
+            (sexpr(condition), body.stmts.len()),
+            ("(Equals condition true)".to_string(), 1)
+        );
+        let StmtKind::DoWhile { body, condition } = &stmts[2].kind else {
+            panic!("expected a do-while");
+        };
+        assert_eq!(
+            (sexpr(condition), body.stmts.len()),
+            ("(Less i 10)".to_string(), 1)
+        );
+        let StmtKind::For {
+            binding,
+            iterable,
+            body,
+        } = &stmts[3].kind
+        else {
+            panic!("expected a for");
+        };
+        assert_eq!(binding.text, "i");
+        assert_eq!(sexpr(iterable), "(list (Range 2 10))");
+        assert_eq!((body.span.line, body.span.column), (5, 18));
+    }
+
+    #[test]
+    fn statement_errors_skip_to_the_next_statement() {
+        let mut parser: Parser = parser(
+            "fun f() {\n    x = ;\n    if x { y = (1; }\n    z: i32 = 3;\n    while { }\n}\nfun g() {}",
+        );
+        let items: Vec<Item> = parser.parse();
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("Expected an expression", 2),
+                ("Expected `)`", 3),
+                ("Expected an expression", 5),
+            ]
+        );
+        assert_eq!(items.len(), 2);
+        let ItemKind::Fun(fun) = &items[0].kind else {
+            panic!("expected a function");
+        };
+        assert_eq!(fun.body.stmts.len(), 2);
+    }
+
+    #[test]
+    fn postfix_guards() {
+        let stmts: Vec<Stmt> = stmts(
+            "println(\"Debug is on\") when debug;\nx = 69 if a == b;\nbreak when done;\nreturn if early;",
+        );
+        let guarded: Vec<(&Stmt, &Guard)> = stmts
+            .iter()
+            .map(|stmt| match &stmt.kind {
+                StmtKind::Guarded { stmt, guard } => (stmt.as_ref(), guard),
+                _ => panic!("expected a guarded statement"),
+            })
+            .collect();
+        let guards: Vec<(bool, String, u32)> = guarded
+            .iter()
+            .map(|(_, guard)| (guard.is_when, sexpr(&guard.condition), guard.span.column))
+            .collect();
+        assert_eq!(
+            guards,
+            vec![
+                (true, "debug".to_string(), 24),
+                (false, "(Equals a b)".to_string(), 8),
+                (true, "done".to_string(), 7),
+                (false, "early".to_string(), 8),
+            ]
+        );
+        assert!(
+            matches!(&guarded[1].0.kind, StmtKind::Expr(node) if sexpr(node) == "(Assign= x 69)")
+        );
+        assert_eq!((guarded[1].0.span.len(), stmts[1].span.len()), (6, 17));
+        assert_eq!(guarded[2].0.kind, StmtKind::Break);
+        assert_eq!(guarded[3].0.kind, StmtKind::Return(None));
+
+        let mut parser: Parser = parser("fun f() { x: i32 = 1 when y; }");
+        parser.parse();
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.column))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![(
+                "A declaration can't be guarded, declare the variable first",
+                22
+            )]
+        );
+    }
+
+    #[test]
+    fn ternary_expressions() {
+        assert_eq!(
+            expr("x = statement == \"A funny number\"? 69 : 42"),
+            "(Assign= x (? (Equals statement \"A funny number\") 69 42))"
+        );
+        assert_eq!(expr("a ? b : c ? d : e"), "(? a b (? c d e))");
+        assert_eq!(expr("a ? b ? c : d : e"), "(? a (? b c d) e)");
+        assert_eq!(expr("a || b ? x + 1 : y"), "(? (Or a b) (Plus x 1) y)");
+        assert_eq!(expr("a ? b = 1 : c"), "(? a (Assign= b 1) c)");
+        assert_eq!(expr("a ? 0..n : n..0"), "(? a (Range 0 n) (Range n 0))");
+        assert_eq!(
+            expr_error("a ? b"),
+            "Expected `:` and the value for when the condition is false"
+        );
+    }
+
+    #[test]
+    fn question_mark_in_types_is_optional() {
+        let stmts: Vec<Stmt> = stmts("x: i32? = ok ? 1 : Null;\nflag ? a : b;\ny: Vec<i32?>?;");
+        let StmtKind::Let {
+            ty,
+            value: Some(value),
+            ..
+        } = &stmts[0].kind
+        else {
+            panic!("expected a declaration");
+        };
+        assert_eq!(
+            (type_name(ty), sexpr(value)),
+            ("I32?".to_string(), "(? ok 1 Null)".to_string())
+        );
+        assert!(matches!(&stmts[1].kind, StmtKind::Expr(node) if sexpr(node) == "(? flag a b)"));
+        let StmtKind::Let { ty, .. } = &stmts[2].kind else {
+            panic!("expected a declaration");
+        };
+        assert_eq!(type_name(ty), "Vec<I32?>?");
+    }
+
+    #[test]
+    fn try_catch_finally_and_throw() {
+        let stmts: Vec<Stmt> = stmts(
+            "try {\n    throw Error::DivisionByZero;\n} catch (const e: Error) {\n    println(e);\n} catch {\n} finally {\n    done = true;\n}\ntry { f(); } finally { }",
+        );
+        let StmtKind::Try {
+            body,
+            catches,
+            finally: Some(finally),
+        } = &stmts[0].kind
+        else {
+            panic!("expected a try with a finally");
+        };
+        assert!(
+            matches!(&body.stmts[0].kind, StmtKind::Throw(node) if sexpr(node) == "Error::DivisionByZero")
+        );
+        let bindings: Vec<Option<(String, String)>> = catches
+            .iter()
+            .map(|catch| {
+                catch
+                    .binding
+                    .as_ref()
+                    .map(|binding| (binding.name.text.clone(), type_name(&binding.ty)))
+            })
+            .collect();
+        assert_eq!(
+            bindings,
+            vec![Some(("e".to_string(), "Error".to_string())), None]
+        );
+        assert_eq!((catches[0].span.line, catches[1].span.line), (4, 6));
+        assert_eq!(finally.stmts.len(), 1);
+        assert!(matches!(
+            &stmts[1].kind,
+            StmtKind::Try { catches, finally: Some(_), .. } if catches.is_empty()
+        ));
+
+        let mut parser: Parser = parser("fun f() {\n    try { }\n    x = 1;\n    throw;\n}");
+        parser.parse();
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("Expected `catch` or `finally` after the `try` block", 3),
+                ("Expected an expression", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn switch_cases() {
+        let stmts: Vec<Stmt> = stmts(
+            "switch x {\n    case 1, 2:\n        a();\n        b();\n    case 3..5:\n    case Test::test1:\n        c();\n    default:\n        d();\n}",
+        );
+        let StmtKind::Switch {
+            value,
+            cases,
+            default: Some(default),
+        } = &stmts[0].kind
+        else {
+            panic!("expected a switch with a default");
+        };
+        assert_eq!(sexpr(value), "x");
+        let arms: Vec<(Vec<String>, usize)> = cases
+            .iter()
+            .map(|case| {
+                (
+                    case.patterns.iter().map(sexpr).collect(),
+                    case.body.stmts.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            arms,
+            vec![
+                (vec!["1".to_string(), "2".to_string()], 2),
+                (vec!["(Range 3 5)".to_string()], 0),
+                (vec!["Test::test1".to_string()], 1),
+            ]
+        );
+        assert_eq!((cases[0].span.line, cases[0].body.span.line), (3, 4));
+        assert_eq!(default.stmts.len(), 1);
+
+        let mut parser: Parser = parser(
+            "fun f() {\n    switch x { default: case 1: }\n    switch y { case: }\n    switch z { a(); }\n}",
+        );
+        parser.parse();
+        let errors: Vec<(&str, u32)> = parser
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.span.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("The `default` case must be the last one in a switch", 2),
+                ("Expected a value to match after `case`", 3),
+                ("Expected `case`, `default` or `}`", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_literals() {
+        assert_eq!(
+            expr("{ Name = \"Tallen\", Age = 10 + 7, }"),
+            "(struct (= Name \"Tallen\") (= Age (Plus 10 7)))"
+        );
+        assert_eq!(expr("{}"), "(struct )");
+        assert_eq!(
+            expr("f({ Inner = { X = 1 } })"),
+            "(call f (struct (= Inner (struct (= X 1)))))"
+        );
+        assert_eq!(
+            expr_error("{ Name: \"Tallen\" }"),
+            "Expected `=` and a value after the field name"
+        );
+        assert_eq!(
+            expr_error("{ Name = 1 Age = 2 }"),
+            "Expected `,` or `}` after the field value"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+// Source spans
+use std::fmt;
+
+/// # File ID
+///
+/// Identifies the source file a [`Span`] points into. The driver hands out one id per loaded
+/// file; a single-file compile only ever uses `FileId(0)`.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+pub struct FileId(pub u32);
+
+/// # Span
+///
+/// A range of source code. `start` and `end` are byte offsets into the file (`end` is
+/// exclusive), `line` and `column` are the 1-based position of `start`. Columns count
+/// characters, not bytes.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Span {
+    pub file_id: FileId,
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Span {
+    /// # New
+    ///
+    /// Create a new span.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - The file the span points into.
+    /// * `start` - The byte offset of the first byte in the span.
+    /// * `end` - The byte offset one past the last byte in the span.
+    /// * `line` - The line of `start` (1-based).
+    /// * `column` - The column of `start` (1-based).
+    pub fn new(file_id: FileId, start: usize, end: usize, line: u32, column: u32) -> Self {
+        Span {
+            file_id,
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// # To
+    ///
+    /// Joins two spans into one that covers both of them. The line and column are taken
+    /// from whichever span starts first.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// // `left + right` covers everything from the start of `left` to the end of `right`
+    /// let span = left.span.to(right.span);
+    /// ```
+    pub fn to(self, other: Span) -> Span {
+        let first = if self.start <= other.start {
+            self
+        } else {
+            other
+        };
+        Span {
+            file_id: self.file_id,
+            start: first.start,
+            end: self.end.max(other.end),
+            line: first.line,
+            column: first.column,
+        }
+    }
+
+    /// # Len
+    ///
+    /// The length of the span in bytes.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// # Is Empty
+    ///
+    /// Returns `true` if the span covers no bytes (e.g. the `TokEOF` token).
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// # Text
+    ///
+    /// Returns the source text this span covers.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - The source of the file the span points into.
+    pub fn text<'s>(&self, src: &'s str) -> &'s str {
+        &src[self.start..self.end]
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_covers_both_spans() {
+        let left = Span::new(FileId(0), 4, 6, 1, 5);
+        let right = Span::new(FileId(0), 9, 11, 1, 10);
+        assert_eq!(left.to(right), Span::new(FileId(0), 4, 11, 1, 5));
+        assert_eq!(right.to(left), Span::new(FileId(0), 4, 11, 1, 5));
+    }
+}
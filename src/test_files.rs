@@ -0,0 +1,47 @@
+// Files on disk for tests
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// # Test Dir
+///
+/// A new directory under the system temp directory with files written for a test. The
+/// directory and everything in it is deleted when this is dropped.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// # New
+    ///
+    /// Creates the directory and writes the files into it, creating any directories in their
+    /// names.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Names the directory; has to be unique among the tests.
+    /// * `files` - The `(relative path, contents)` of each file.
+    pub fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("zinc-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        for (file, src) in files {
+            let file: PathBuf = path.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, src).unwrap();
+        }
+        TestDir { path }
+    }
+
+    /// # Path
+    ///
+    /// Returns the path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
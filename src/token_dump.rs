@@ -0,0 +1,184 @@
+// Token dumps for `--print-tokens`
+use crate::span::Span;
+use crate::tokenizer::{NumKind, Token};
+
+/// The formats `--print-tokens` can write the token stream in.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum TokenFormat {
+    #[default]
+    Debug, // `--print-tokens`: Rust debug output through the verbose log
+    Json,    // `--print-tokens=json`: a JSON array with one object per token
+    Compact, // `--print-tokens=compact`: one token per line, `<kind> <text> <line>:<column>`
+}
+
+impl TokenFormat {
+    /// # From Name
+    ///
+    /// Parses the value of `--print-tokens=<format>`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<TokenFormat>` - The format, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<TokenFormat> {
+        match name {
+            "debug" => Some(TokenFormat::Debug),
+            "json" => Some(TokenFormat::Json),
+            "compact" => Some(TokenFormat::Compact),
+            _ => None,
+        }
+    }
+}
+
+/// # Dump Tokens
+///
+/// Formats a token stream for `--print-tokens`.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to format.
+/// * `src` - The source the tokens were lexed from. Used for the raw text of every token.
+/// * `format` - The format to write.
+///
+/// # Example
+///
+/// ```
+/// let json: String = token_dump::dump_tokens(&tokens, &src, TokenFormat::Json);
+/// ```
+pub fn dump_tokens(tokens: &[Token], src: &str, format: TokenFormat) -> String {
+    match format {
+        TokenFormat::Debug => tokens
+            .iter()
+            .map(|token| format!("{:#?}", token))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        TokenFormat::Json => {
+            let objects: Vec<String> = tokens
+                .iter()
+                .map(|token| format!("  {}", token_to_json(token, src)))
+                .collect();
+            format!("[\n{}\n]\n", objects.join(",\n"))
+        }
+        TokenFormat::Compact => tokens
+            .iter()
+            .map(|token| {
+                format!(
+                    "{:?} {:?} {}\n",
+                    token.tok_type,
+                    token.span.text(src),
+                    token.span
+                )
+            })
+            .collect(),
+    }
+}
+
+/// # Token to JSON
+///
+/// Formats a single token as a JSON object, i.e.
+/// `{"kind": "TokIdentifier", "text": "x", "value": "x", "span": {...}}`. Number literals also
+/// get a `"number"` object with their radix, kind, digits and suffix.
+fn token_to_json(token: &Token, src: &str) -> String {
+    let mut fields: Vec<String> = vec![
+        format!(
+            "\"kind\": {}",
+            json_string(&format!("{:?}", token.tok_type))
+        ),
+        format!("\"text\": {}", json_string(token.span.text(src))),
+    ];
+    if let Some(value) = &token.value {
+        fields.push(format!("\"value\": {}", json_string(value)));
+    }
+    if let Some(number) = &token.number {
+        let kind: &str = match number.kind {
+            NumKind::Int => "int",
+            NumKind::Float => "float",
+        };
+        let suffix: String = match number.suffix_text() {
+            Some(suffix) => json_string(suffix),
+            None => "null".to_string(),
+        };
+        fields.push(format!(
+            "\"number\": {{\"radix\": {}, \"kind\": \"{}\", \"digits\": {}, \"suffix\": {}}}",
+            number.radix as u32,
+            kind,
+            json_string(&number.digits),
+            suffix
+        ));
+    }
+    fields.push(format!("\"span\": {}", span_to_json(&token.span)));
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// # Span to JSON
+///
+/// Formats a span as a JSON object.
+fn span_to_json(span: &Span) -> String {
+    format!(
+        "{{\"file\": {}, \"start\": {}, \"end\": {}, \"line\": {}, \"column\": {}}}",
+        span.file_id.0, span.start, span.end, span.line, span.column
+    )
+}
+
+/// # JSON String
+///
+/// Quotes and escapes a string for JSON.
+fn json_string(text: &str) -> String {
+    let mut quoted: String = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CSettings;
+    use crate::tokenizer::Tokenizer;
+
+    fn dump(src: &str, format: TokenFormat) -> String {
+        let c_settings: CSettings = CSettings::default();
+        let tokens: Vec<Token> = Tokenizer::new(src.to_string(), &c_settings).tokenize();
+        dump_tokens(&tokens, src, format)
+    }
+
+    #[test]
+    fn compact_has_one_token_per_line() {
+        assert_eq!(
+            dump("x = \"a\\tb\";\n0xFF", TokenFormat::Compact),
+            "TokIdentifier \"x\" 1:1\n\
+             TokAssign \"=\" 1:3\n\
+             TokStringLiteral \"\\\"a\\\\tb\\\"\" 1:5\n\
+             TokSemi \";\" 1:11\n\
+             TokNewline \"\\n\" 1:12\n\
+             TokNumLiteral \"0xFF\" 2:1\n\
+             TokEOF \"\" 2:5\n"
+        );
+    }
+
+    #[test]
+    fn json_has_one_object_per_token() {
+        assert_eq!(
+            dump("s = \"q\\\"\";\n42u8", TokenFormat::Json),
+            r#"[
+  {"kind": "TokIdentifier", "text": "s", "value": "s", "span": {"file": 0, "start": 0, "end": 1, "line": 1, "column": 1}},
+  {"kind": "TokAssign", "text": "=", "span": {"file": 0, "start": 2, "end": 3, "line": 1, "column": 3}},
+  {"kind": "TokStringLiteral", "text": "\"q\\\"\"", "value": "q\"", "span": {"file": 0, "start": 4, "end": 9, "line": 1, "column": 5}},
+  {"kind": "TokSemi", "text": ";", "span": {"file": 0, "start": 9, "end": 10, "line": 1, "column": 10}},
+  {"kind": "TokNewline", "text": "\n", "span": {"file": 0, "start": 10, "end": 11, "line": 1, "column": 11}},
+  {"kind": "TokNumLiteral", "text": "42u8", "value": "42", "number": {"radix": 10, "kind": "int", "digits": "42", "suffix": "u8"}, "span": {"file": 0, "start": 11, "end": 15, "line": 2, "column": 1}},
+  {"kind": "TokEOF", "text": "", "span": {"file": 0, "start": 15, "end": 15, "line": 2, "column": 5}}
+]
+"#
+        );
+    }
+}
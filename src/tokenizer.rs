@@ -1,62 +1,75 @@
 // Tokenizer
 use crate::CSettings;
+use crate::span::{FileId, Span};
 use crate::zlog::{self};
-use std::{result::Result, usize};
+use std::fmt;
+use std::ops::Range;
+use std::result::Result;
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+use unicode_security::MixedScript;
+use unicode_xid::UnicodeXID;
 
 #[derive(PartialEq, Debug, Clone)]
 #[allow(dead_code)]
 pub enum TokenType {
-    TokTypeChar,      // 'char' ✅
-    TokTypeString,    // 'string' ✅
-    TokTypei64,       // 'i64' ✅
-    TokTypeu64,       // 'u64' ✅
-    TokTypei32,       // 'i32' ✅
-    TokTypeu32,       // 'u32' ✅
-    TokTypei16,       // 'i16' ✅
-    TokTypeu16,       // 'u16' ✅
-    TokTypei8,        // 'i8' ✅
-    TokTypeu8,        // 'u8' ✅
-    TokTypeBool,      // 'bool' ✅
-    TokTypef32,       // 'f32' ✅
-    TokTypef64,       // 'f64' ✅
-    TokStringLiteral, // A string literal i.e. "Hello, world!\n" ✅
-    TokNumLiteral,    // For number literals i.e. '1' or '1.5' ✅
-    TokCharLiteral,   // for character literals i.e. 'a'
-    TokIdentifier,    // The name of the variable ✅
-    TokAssign,        // the '=' sign ✅
-    TokLeftParen,     // '(' ✅
-    TokRightParen,    // ')' ✅
-    TokLeftBrace,     // '{' ✅
-    TokRightBrace,    // '}' ✅
-    TokLeftBracket,   // '[' ✅
-    TokRightBracket,  // ']' ✅
-    TokMain,          // A program entry 'main' ✅
-    TokSemi,          // ';' ✅
-    TokDot,           // '.' ✅
-    TokComma,         // ',' ✅
-    TokIf,            // an if statement 'if' ✅
-    TokElse,          // an else statement 'else' ✅
-    TokDo,            // A do statement 'do' ✅
-    TokWhen,          // A when statement `x = y when z == true` ✅
-    TokWhile,         // a while statement 'while' ✅
-    TokFor,           // A for statement 'for' ✅
-    TokIn,            // A statement used for iteration 'in' ✅
-    TokReturn,        // A return operation 'return' ✅
-    TokBreak,         // A break operation. Breaks out of a loop 'break' ✅
-    TokContinue,      // A continue operation. Continues to the next iteration 'continue' ✅
-    TokTry,           // For try blocks ✅
-    TokCatch,         // To catch errors ✅
-    TokThrow,         // Throw an exception to catch ✅
-    TokFun,           // A 'fun' function declaration ✅
-    TokStruct,        // A struct keyword 'struct' ✅
-    TokEnum,          // A enum keyword 'enum' ✅
-    TokConst,         // A const keyword. Makes variable immutable 'const' ✅
-    TokPound,         // '#' directives
-    TokDive,          // 'dive' used for imports ✅
-    TokBellyflop,     // 'bellyflop' used for C immports ✅
-    TokFrom,          // 'from' token to get an import ✅
-    TokAlias,         // 'type' for type aliases ✅
-    TokAt,            // '@' for calling a macro ✅
+    TokTypeChar,        // 'char' ✅
+    TokTypeString,      // 'string' ✅
+    TokTypei64,         // 'i64' ✅
+    TokTypeu64,         // 'u64' ✅
+    TokTypei32,         // 'i32' ✅
+    TokTypeu32,         // 'u32' ✅
+    TokTypei16,         // 'i16' ✅
+    TokTypeu16,         // 'u16' ✅
+    TokTypei8,          // 'i8' ✅
+    TokTypeu8,          // 'u8' ✅
+    TokTypeBool,        // 'bool' ✅
+    TokTypef32,         // 'f32' ✅
+    TokTypef64,         // 'f64' ✅
+    TokTypeVoid,        // 'void' ✅
+    TokStringLiteral,   // A string literal i.e. "Hello, world!\n" or r"C:\raw" ✅
+    TokNumLiteral,      // For number literals i.e. '1', '1.5', '0xFF' or '42u8' ✅
+    TokCharLiteral,     // for character literals i.e. 'a' ✅
+    TokTrue,            // The boolean literal 'true' ✅
+    TokFalse,           // The boolean literal 'false' ✅
+    TokNull,            // The null pointer literal 'Null' (for C interop) ✅
+    TokIdentifier,      // The name of the variable ✅
+    TokDocComment,      // An outer doc comment '/// Adds two numbers' ✅
+    TokInnerDocComment, // An inner doc comment '//! The math module' ✅
+    TokAssign,          // the '=' sign ✅
+    TokLeftParen,       // '(' ✅
+    TokRightParen,      // ')' ✅
+    TokLeftBrace,       // '{' ✅
+    TokRightBrace,      // '}' ✅
+    TokLeftBracket,     // '[' ✅
+    TokRightBracket,    // ']' ✅
+    TokMain,            // A program entry 'main' ✅
+    TokSemi,            // ';' ✅
+    TokDot,             // '.' ✅
+    TokComma,           // ',' ✅
+    TokIf,              // an if statement 'if' ✅
+    TokElse,            // an else statement 'else' ✅
+    TokDo,              // A do statement 'do' ✅
+    TokWhen,            // A when statement `x = y when z == true` ✅
+    TokWhile,           // a while statement 'while' ✅
+    TokFor,             // A for statement 'for' ✅
+    TokIn,              // A statement used for iteration 'in' ✅
+    TokReturn,          // A return operation 'return' ✅
+    TokBreak,           // A break operation. Breaks out of a loop 'break' ✅
+    TokContinue,        // A continue operation. Continues to the next iteration 'continue' ✅
+    TokTry,             // For try blocks ✅
+    TokCatch,           // To catch errors ✅
+    TokThrow,           // Throw an exception to catch ✅
+    TokFinally,         // Runs after a try block whether it threw or not ✅
+    TokFun,             // A 'fun' function declaration ✅
+    TokStruct,          // A struct keyword 'struct' ✅
+    TokEnum,            // A enum keyword 'enum' ✅
+    TokConst,           // A const keyword. Makes variable immutable 'const' ✅
+    TokPound,           // '#' directives ✅
+    TokDive,            // 'dive' used for imports ✅
+    TokBellyflop,       // 'bellyflop' used for C immports ✅
+    TokFrom,            // 'from' token to get an import ✅
+    TokAlias,           // 'type' for type aliases ✅
+    TokAt,              // '@' for calling a macro ✅
 
     // Operators
     TokAmpersand,       // '&'  (reference operator) ✅
@@ -76,12 +89,12 @@ pub enum TokenType {
     TokMinusEqual,      // '-=' (subtraction assignment) ✅
     TokModuloEqual,     // '%=' (modulo assignment) ✅
     TokNotEquals,       // '!=' (comparison) ✅
-    TokBitAndEqual,     // '&=' (bitwise AND assignment)
-    TokBitOrEqual,      // '|=' (bitwise OR assignment)
+    TokBitAndEqual,     // '&=' (bitwise AND assignment) ✅
+    TokBitOrEqual,      // '|=' (bitwise OR assignment) ✅
     TokBitXorEqual,     // '^=' (bitwise XOR assignment) ✅
-    TokBitNotEqual,     // '~=' (bitwise NOT assignment)
-    TokLeftShiftEqual,  // '<<=' (bitwise left shift assignment)
-    TokRightShiftEqual, // '>>=' (bitwise right shift assignment)
+    TokBitNotEqual,     // '~=' (bitwise NOT assignment) ✅
+    TokLeftShiftEqual,  // '<<=' (bitwise left shift assignment) ✅
+    TokRightShiftEqual, // '>>=' (bitwise right shift assignment) ✅
     TokLeftAngle,       // '<'  (comparison) ✅
     TokRightAngle,      // '>'  (comparison) ✅
     TokLessEqual,       // '<=' (comparison) ✅
@@ -110,20 +123,251 @@ pub enum TokenType {
     // Misc.
     TokEOF,     // End of file token to stop the parser ✅
     TokNewline, // Newline token to track line numbers ✅
+    TokError,   // Source the tokenizer could not lex. Has a matching `LexError` ✅
 }
 
-#[derive(Debug)]
+/// # Number Suffixes
+///
+/// The type suffixes a number literal can end with and their spelling, i.e. `u8` in '42u8'.
+const NUM_SUFFIXES: &[(&str, TokenType)] = &[
+    ("i8", TokenType::TokTypei8),
+    ("u8", TokenType::TokTypeu8),
+    ("i16", TokenType::TokTypei16),
+    ("u16", TokenType::TokTypeu16),
+    ("i32", TokenType::TokTypei32),
+    ("u32", TokenType::TokTypeu32),
+    ("i64", TokenType::TokTypei64),
+    ("u64", TokenType::TokTypeu64),
+    ("f32", TokenType::TokTypef32),
+    ("f64", TokenType::TokTypef64),
+];
+
+/// # Operators
+///
+/// Every operator and punctuation token with its spelling. The table is ordered longest
+/// spelling first, so the first entry that matches the source is also the longest one (maximal
+/// munch): `<<=` is one token, not `<<` followed by `=`.
+const OPERATORS: &[(&str, TokenType)] = &[
+    // Three characters
+    ("<<=", TokenType::TokLeftShiftEqual),
+    (">>=", TokenType::TokRightShiftEqual),
+    ("...", TokenType::TokEllipsis),
+    // Two characters
+    ("->", TokenType::TokArrow),
+    ("::", TokenType::TokDoubleColon),
+    ("++", TokenType::TokIncrement),
+    ("--", TokenType::TokDecrement),
+    ("==", TokenType::TokEquals),
+    ("!=", TokenType::TokNotEquals),
+    ("*=", TokenType::TokTimesEqual),
+    ("/=", TokenType::TokDivideEqual),
+    ("+=", TokenType::TokPlusEqual),
+    ("-=", TokenType::TokMinusEqual),
+    ("%=", TokenType::TokModuloEqual),
+    ("&=", TokenType::TokBitAndEqual),
+    ("|=", TokenType::TokBitOrEqual),
+    ("^=", TokenType::TokBitXorEqual),
+    ("~=", TokenType::TokBitNotEqual),
+    ("<=", TokenType::TokLessEqual),
+    (">=", TokenType::TokGreaterEqual),
+    ("<<", TokenType::TokLeftShift),
+    (">>", TokenType::TokRightShift),
+    ("&&", TokenType::TokAnd),
+    ("||", TokenType::TokOr),
+    ("..", TokenType::TokRange),
+    // One character
+    ("=", TokenType::TokAssign),
+    ("&", TokenType::TokAmpersand),
+    ("*", TokenType::TokAsterisk),
+    (":", TokenType::TokColon),
+    ("%", TokenType::TokModulo),
+    ("+", TokenType::TokPlus),
+    ("-", TokenType::TokMinus),
+    ("/", TokenType::TokDivide),
+    ("<", TokenType::TokLeftAngle),
+    (">", TokenType::TokRightAngle),
+    ("|", TokenType::TokBitOr),
+    ("^", TokenType::TokBitXor),
+    ("~", TokenType::TokBitNot),
+    ("!", TokenType::TokBang),
+    ("?", TokenType::TokQuestion),
+    ("$", TokenType::TokDollar),
+    (".", TokenType::TokDot),
+    (",", TokenType::TokComma),
+    (";", TokenType::TokSemi),
+    ("(", TokenType::TokLeftParen),
+    (")", TokenType::TokRightParen),
+    ("{", TokenType::TokLeftBrace),
+    ("}", TokenType::TokRightBrace),
+    ("[", TokenType::TokLeftBracket),
+    ("]", TokenType::TokRightBracket),
+    ("#", TokenType::TokPound),
+    ("@", TokenType::TokAt),
+];
+
+#[derive(PartialEq, Debug, Clone)]
 #[allow(dead_code)]
 pub struct Token {
     pub tok_type: TokenType,
     pub value: Option<String>,
-    pub line: u32,
+    pub span: Span,
+    pub number: Option<NumLiteral>,   // Only set on `TokNumLiteral`
+    pub leading_trivia: Vec<Trivia>,  // Only set in lossless mode
+    pub trailing_trivia: Vec<Trivia>, // Only set in lossless mode
+}
+
+impl Token {
+    /// # To Source
+    ///
+    /// Returns the source text of the token including its trivia. In lossless mode,
+    /// concatenating this for every token reproduces the source file byte for byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - The source the token was lexed from.
+    #[allow(dead_code)]
+    pub fn to_source(&self, src: &str) -> String {
+        let mut text: String = String::new();
+        for trivia in &self.leading_trivia {
+            text.push_str(trivia.span.text(src));
+        }
+        text.push_str(self.span.text(src));
+        for trivia in &self.trailing_trivia {
+            text.push_str(trivia.span.text(src));
+        }
+        text
+    }
+}
+
+/// The kinds of source that don't make up a token.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TriviaKind {
+    Whitespace,   // A run of spaces, tabs and other non-newline whitespace
+    Newline,      // '\n'
+    LineComment,  // '// ...' (not including the newline)
+    BlockComment, // '/* ... */'
+}
+
+/// # Trivia
+///
+/// Whitespace or a comment that is attached to a token in lossless mode. A token's trailing
+/// trivia is everything after it up to and including the end of its line; everything else is
+/// leading trivia of the next token.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
+
+/// The base a number literal was written in.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Radix {
+    Binary = 2,       // '0b1010'
+    Octal = 8,        // '0o755'
+    Decimal = 10,     // '42'
+    Hexadecimal = 16, // '0xFF'
+}
+
+/// Whether a number literal is an integer or a float.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum NumKind {
+    Int,   // '42', '0xFF', '42u8'
+    Float, // '1.5', '6.02e23', '1f32'
+}
+
+/// # Number Literal
+///
+/// The structured form of a `TokNumLiteral`, so later phases can pick the literal's type
+/// without re-parsing the source text.
+#[derive(PartialEq, Debug, Clone)]
+pub struct NumLiteral {
+    pub radix: Radix,
+    pub kind: NumKind,
+    pub digits: String, // The digits without prefix, separators or suffix
+    pub suffix: Option<TokenType>, // The type suffix, i.e. `TokTypeu8` for '42u8'
+}
+
+impl NumLiteral {
+    /// # Int Value
+    ///
+    /// Returns the value of an integer literal, or `None` if the literal is a float or does
+    /// not fit in a `u128`.
+    pub fn int_value(&self) -> Option<u128> {
+        match self.kind {
+            NumKind::Int => u128::from_str_radix(&self.digits, self.radix as u32).ok(),
+            NumKind::Float => None,
+        }
+    }
+
+    /// # Suffix Text
+    ///
+    /// Returns the type suffix as it is written, i.e. `u8` for '42u8'.
+    pub fn suffix_text(&self) -> Option<&'static str> {
+        let suffix: &TokenType = self.suffix.as_ref()?;
+        NUM_SUFFIXES
+            .iter()
+            .find(|(_, suffix_type)| suffix_type == suffix)
+            .map(|(spelling, _)| *spelling)
+    }
+
+    /// # Float Value
+    ///
+    /// Returns the value of the literal as a float. Integer literals are converted.
+    pub fn float_value(&self) -> Option<f64> {
+        match self.kind {
+            NumKind::Int => self.int_value().map(|value| value as f64),
+            NumKind::Float => self.digits.parse().ok(),
+        }
+    }
+}
+
+/// # Lex Error
+///
+/// A problem found while tokenizing, i.e. an unknown character or an unterminated string.
+#[derive(PartialEq, Debug, Clone)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span, // The source that could not be lexed
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[Line {}, Column {}] {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+/// # Text Edit
+///
+/// An edit to the source for [`Tokenizer::relex`]: the bytes in `range` are replaced with
+/// `replacement`.
+#[derive(PartialEq, Debug, Clone)]
+#[allow(dead_code)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+/// A point in the source the tokenizer has reached. Used to mark where a token starts.
+#[derive(Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: u32,
+    column: u32,
 }
 
 pub struct Tokenizer<'a> {
     src: String,
-    index: usize,
+    offset: usize,
     line: u32,
+    column: u32,
+    file_id: FileId,
+    errors: Vec<LexError>,
+    warnings: Vec<LexError>,
+    is_lossless: bool,
     c_settings: &'a CSettings,
 }
 
@@ -143,18 +387,62 @@ impl<'a> Tokenizer<'a> {
     /// let src = String::from("fun main() -> i32 { return 0; }");
     /// let settings = Settings::default();
     /// let mut tokenizer = Tokenizer::new(src, &settings);
-    /// let tokens = tokenizer.tokenize().unwrap();
+    /// let tokens = tokenizer.tokenize();
     /// ```
     ///
     pub fn new(src: String, c_settings: &'a CSettings) -> Self {
         Tokenizer {
             src,
-            index: 0,
+            offset: 0,
             line: 1,
+            column: 1,
+            file_id: FileId::default(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            is_lossless: false,
             c_settings,
         }
     }
 
+    /// # With File ID
+    ///
+    /// Sets the file id that is stored in the span of every token this tokenizer produces.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - The id of the file being tokenized.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut tokenizer = Tokenizer::new(src, &settings).with_file_id(FileId(1));
+    /// ```
+    pub fn with_file_id(mut self, file_id: FileId) -> Self {
+        self.file_id = file_id;
+        self
+    }
+
+    /// # Lossless
+    ///
+    /// Switches the tokenizer to lossless mode. Whitespace, newlines and comments are attached
+    /// to the tokens as trivia instead of being thrown away (and no `TokNewline` tokens are
+    /// made), so the original file can be rebuilt with [`Token::to_source`]. This is what
+    /// formatters and editor tooling should use.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut tokenizer = Tokenizer::new(src.clone(), &settings).lossless();
+    /// let tokens = tokenizer.tokenize();
+    /// let rebuilt: String = tokens.iter().map(|token| token.to_source(&src)).collect();
+    /// assert_eq!(rebuilt, src);
+    /// ```
+    #[allow(dead_code)]
+    pub fn lossless(mut self) -> Self {
+        self.is_lossless = true;
+        self
+    }
+
     /// # Tokenize
     ///
     /// Tokenizes a string of Zinc source code.
@@ -169,367 +457,817 @@ impl<'a> Tokenizer<'a> {
     /// ```
     /// // Create a new tokenizer first, then tokenize
     /// let mut tokenizer: tokenizer::Tokenizer = tokenizer::Tokenizer::new(src, &c_settings);
-    /// let tokens: Vec<Token> = tokenizer.tokenize()?; // Or you could do a match statement
+    /// let tokens: Vec<Token> = tokenizer.tokenize();
+    /// for error in tokenizer.errors() {
+    ///     zlog::err(&error.to_string(), &c_settings);
+    /// }
     /// ```
     ///
-    pub fn tokenize(&mut self) -> Result<Vec<Token>, String> {
+    /// Lexical errors don't stop tokenization. Each one is recorded in [`Tokenizer::errors`] and
+    /// leaves a `TokError` token in the stream.
+    ///
+    pub fn tokenize(&mut self) -> Vec<Token> {
         let mut tokens: Vec<Token> = Vec::new();
-        while let Some(ch) = self.peek(0) {
-            let mut tok_buf: String;
-
-            if ch.is_whitespace() {
-                if ch == '\n' {
-                    tokens.push(Token {
-                        tok_type: TokenType::TokNewline,
-                        value: None,
-                        line: self.line,
-                    });
-                    self.line += 1;
-                }
-                self.consume(1);
-                continue;
-            } else if ch.is_alphabetic() {
-                tok_buf = String::new();
-                tok_buf.push(ch);
-                self.consume(1);
-                while let Some(next_char) = self.peek(0) {
-                    if next_char.is_alphanumeric() || "_".contains(next_char) {
-                        tok_buf.push(next_char);
-                        self.consume(1);
-                    } else {
-                        break;
-                    }
+        self.lex_into(&mut tokens, Vec::new(), |_| false);
+        zlog::verbose(
+            &format!(
+                "Tokenization Completed. Lines: {}, Tokens: {}, Errors: {}",
+                self.line,
+                tokens.len(),
+                self.errors.len()
+            ),
+            self.c_settings,
+        );
+        tokens
+    }
+
+    /// # Relex
+    ///
+    /// Applies a text edit to the source and updates the tokens from the last call to
+    /// [`Tokenizer::tokenize`] (or `relex`) to match, without lexing the whole file again.
+    ///
+    /// Lexing restarts at the token before the edit and stops as soon as it reaches a token
+    /// that starts at the same place as one of the old tokens after the edit. Lexing from a
+    /// token start only depends on the text after it, so from there on the old tokens are
+    /// reused with their spans shifted. Edits that open or close a string or a block comment
+    /// simply re-lex further before they sync up again. Errors and warnings are updated the
+    /// same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The current tokens for the source.
+    /// * `edit` - The edit to apply. Its range is in byte offsets into the current source and
+    ///   must lie on char boundaries.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// let mut tokenizer = Tokenizer::new(String::from("x = 41;"), &settings);
+    /// let tokens = tokenizer.tokenize();
+    /// let edit = TextEdit { range: 4..6, replacement: String::from("42") };
+    /// let tokens = tokenizer.relex(tokens, &edit);
+    /// ```
+    #[allow(dead_code)]
+    pub fn relex(&mut self, mut tokens: Vec<Token>, edit: &TextEdit) -> Vec<Token> {
+        let old_len: usize = self.src.len();
+        self.src
+            .replace_range(edit.range.clone(), &edit.replacement);
+        let delta: isize = self.src.len() as isize - old_len as isize;
+        let shift = |offset: usize| (offset as isize + delta) as usize;
+
+        // Restart at the start of the token before the first one the edit touches. Token
+        // starts are never inside a comment or string, so lexing can pick up from there.
+        let touched: usize = tokens
+            .iter()
+            .position(|token| token.span.end >= edit.range.start)
+            .unwrap_or(tokens.len());
+        let restart: usize = touched.saturating_sub(1);
+        let mut tail: Vec<Token> = tokens.split_off(restart);
+        let leading_trivia: Vec<Trivia> = if restart == 0 {
+            self.offset = 0;
+            self.line = 1;
+            self.column = 1;
+            Vec::new()
+        } else {
+            let span: Span = tail[0].span;
+            self.offset = span.start;
+            self.line = span.line;
+            self.column = span.column;
+            std::mem::take(&mut tail[0].leading_trivia)
+        };
+
+        // Drop the errors and warnings from the region being re-lexed, keeping the old ones
+        // after it to shift once we know where lexing synced up.
+        let restart_offset: usize = self.offset;
+        let old_errors: Vec<LexError> = self.errors.split_off(
+            self.errors
+                .partition_point(|error| error.span.start < restart_offset),
+        );
+        let old_warnings: Vec<LexError> = self.warnings.split_off(
+            self.warnings
+                .partition_point(|warning| warning.span.start < restart_offset),
+        );
+
+        let mut old_index: usize = 0;
+        let synced: Option<Token> = self.lex_into(&mut tokens, leading_trivia, |token| {
+            while old_index < tail.len()
+                && (tail[old_index].span.start < edit.range.end
+                    || shift(tail[old_index].span.start) < token.span.start)
+            {
+                old_index += 1;
+            }
+            old_index < tail.len() && shift(tail[old_index].span.start) == token.span.start
+        });
+
+        if let Some(synced) = synced {
+            let old_sync: Span = tail[old_index].span;
+            let line_delta: i64 = synced.span.line as i64 - old_sync.line as i64;
+            let column_delta: i64 = synced.span.column as i64 - old_sync.column as i64;
+            let shift_span = |span: &mut Span| {
+                // Only the rest of the sync token's line moves sideways
+                if span.line == old_sync.line {
+                    span.column = (span.column as i64 + column_delta) as u32;
                 }
-                let token_type: TokenType = match tok_buf.as_str() {
-                    "char" => TokenType::TokTypeChar,
-                    "string" => TokenType::TokTypeString,
-                    "f32" => TokenType::TokTypef32,
-                    "f64" => TokenType::TokTypef64,
-                    "i64" => TokenType::TokTypei64,
-                    "u64" => TokenType::TokTypeu64,
-                    "i32" => TokenType::TokTypei32,
-                    "u32" => TokenType::TokTypeu32,
-                    "i16" => TokenType::TokTypei16,
-                    "u16" => TokenType::TokTypeu16,
-                    "i8" => TokenType::TokTypei8,
-                    "u8" => TokenType::TokTypeu8,
-                    "bool" => TokenType::TokTypeBool,
-                    "struct" => TokenType::TokStruct,
-                    "fun" => TokenType::TokFun,
-                    "enum" => TokenType::TokEnum,
-                    "const" => TokenType::TokConst,
-                    "try" => TokenType::TokTry,
-                    "catch" => TokenType::TokCatch,
-                    "throw" => TokenType::TokThrow,
-                    "return" => TokenType::TokReturn,
-                    "bellyflop" => TokenType::TokBellyflop,
-                    "dive" => TokenType::TokDive,
-                    "from" => TokenType::TokFrom,
-                    "switch" => TokenType::TokSwitch,
-                    "case" => TokenType::TokCase,
-                    "default" => TokenType::TokDefault,
-                    "if" => TokenType::TokIf,
-                    "else" => TokenType::TokElse,
-                    "type" => TokenType::TokAlias,
-                    "do" => TokenType::TokDo,
-                    "while" => TokenType::TokWhile,
-                    "break" => TokenType::TokBreak,
-                    "continue" => TokenType::TokContinue,
-                    "for" => TokenType::TokFor,
-                    "in" => TokenType::TokIn,
-                    "when" => TokenType::TokWhen,
-                    "main" => TokenType::TokMain,
-                    _ => TokenType::TokIdentifier,
+                span.line = (span.line as i64 + line_delta) as u32;
+                span.start = shift(span.start);
+                span.end = shift(span.end);
+            };
+
+            let mut reused: Vec<Token> = tail.split_off(old_index);
+            reused[0].leading_trivia = synced.leading_trivia;
+            for (index, token) in reused.iter_mut().enumerate() {
+                shift_span(&mut token.span);
+                let leading: &mut [Trivia] = if index == 0 {
+                    &mut []
+                } else {
+                    &mut token.leading_trivia
                 };
-                tokens.push(Token {
-                    tok_type: token_type.clone(),
-                    value: if matches!(token_type, TokenType::TokIdentifier) {
-                        Some(tok_buf)
-                    } else {
-                        None
-                    },
-                    line: self.line,
-                });
-            } else if ch.is_numeric() {
-                tok_buf = String::new();
-                tok_buf.push(ch);
-                self.consume(1);
-                let mut is_float = false;
-                while let Some(next_char) = self.peek(0) {
-                    if next_char.is_numeric() {
-                        tok_buf.push(next_char);
-                        self.consume(1);
-                    } else if '.' == next_char {
-                        // Check if there is a decimal point
-                        self.consume(1);
-                        if Some('.') == self.peek(0) {
-                            tokens.push(Token {
-                                tok_type: TokenType::TokNumLiteral,
-                                value: Some(tok_buf.clone()),
-                                line: self.line,
-                            });
-                            if Some('.') == self.peek(1) {
-                                self.consume(2);
-                                tokens.push(Token {
-                                    tok_type: TokenType::TokEllipsis,
-                                    value: None,
-                                    line: self.line,
-                                });
-                            } else {
-                                self.consume(1);
-                                tokens.push(Token {
-                                    tok_type: TokenType::TokRange,
-                                    value: None,
-                                    line: self.line,
-                                });
-                            }
-                            tok_buf = String::new();
-                        } else {
-                            if !is_float {
-                                is_float = true;
-                                self.consume(1);
-                            } else {
-                                return Err(format!(
-                                    "[Line {}] Cannot put two decimal points in a float literal",
-                                    self.line
-                                ));
+                for trivia in leading.iter_mut().chain(token.trailing_trivia.iter_mut()) {
+                    shift_span(&mut trivia.span);
+                }
+            }
+            tokens.append(&mut reused);
+
+            for mut error in old_errors {
+                if error.span.start >= old_sync.start {
+                    shift_span(&mut error.span);
+                    self.errors.push(error);
+                }
+            }
+            for mut warning in old_warnings {
+                if warning.span.start >= old_sync.start {
+                    shift_span(&mut warning.span);
+                    self.warnings.push(warning);
+                }
+            }
+        }
+        tokens
+    }
+
+    /// # Lex Into
+    ///
+    /// Lexes tokens from the current position onto `tokens` until the end of the source,
+    /// finishing with a `TokEOF` token.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The tokens to push to.
+    /// * `leading_trivia` - Trivia to attach to the first token (lossless mode only).
+    /// * `is_sync_point` - Called with every token before it is pushed. If it returns `true`,
+    ///   lexing stops and the token is returned instead of pushed.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Token>` - The token lexing stopped at, or `None` if it reached the end.
+    fn lex_into(
+        &mut self,
+        tokens: &mut Vec<Token>,
+        mut leading_trivia: Vec<Trivia>,
+        mut is_sync_point: impl FnMut(&Token) -> bool,
+    ) -> Option<Token> {
+        let mut is_line_ended: bool = tokens.is_empty();
+        while let Some(ch) = self.peek(0) {
+            let start: Position = self.position();
+            let (error_count, warning_count) = (self.errors.len(), self.warnings.len());
+            let mut token: Token = match self.next_token(ch, start) {
+                Ok(Some(token))
+                    if !(self.is_lossless && token.tok_type == TokenType::TokNewline) =>
+                {
+                    token
+                }
+                Ok(_) => {
+                    if self.is_lossless {
+                        let trivia: Trivia = self.trivia(start);
+                        let attach_to: &mut Vec<Trivia> = match tokens.last_mut() {
+                            Some(token) if !is_line_ended => &mut token.trailing_trivia,
+                            _ => &mut leading_trivia,
+                        };
+                        is_line_ended |= trivia.kind == TriviaKind::Newline;
+                        // Whitespace is consumed a character at a time, so merge the runs
+                        match attach_to.last_mut() {
+                            Some(last)
+                                if last.kind == TriviaKind::Whitespace
+                                    && trivia.kind == TriviaKind::Whitespace =>
+                            {
+                                last.span = last.span.to(trivia.span);
                             }
+                            _ => attach_to.push(trivia),
                         }
-                    } else {
-                        break;
                     }
+                    continue;
+                }
+                Err(message) => {
+                    // Keep going after an error so every problem in the file gets reported and
+                    // the parser still gets a usable token stream.
+                    let text: String = self.src[start.offset..self.offset].to_string();
+                    let token: Token = self.token(TokenType::TokError, Some(text), start);
+                    self.errors.push(LexError {
+                        message,
+                        span: token.span,
+                    });
+                    token
+                }
+            };
+            token.leading_trivia = std::mem::take(&mut leading_trivia);
+            if is_sync_point(&token) {
+                // The caller already has this token's errors and warnings
+                self.errors.truncate(error_count);
+                self.warnings.truncate(warning_count);
+                return Some(token);
+            }
+            is_line_ended = false;
+            tokens.push(token);
+        }
+        let end: Position = self.position();
+        let mut eof: Token = self.token(TokenType::TokEOF, None, end);
+        eof.leading_trivia = leading_trivia;
+        if is_sync_point(&eof) {
+            return Some(eof);
+        }
+        tokens.push(eof);
+        None
+    }
+
+    /// # Errors
+    ///
+    /// Returns every lexical error found by the last call to [`Tokenizer::tokenize`]. Each error
+    /// also has a matching `TokError` token in the token stream.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// # Warnings
+    ///
+    /// Returns the lint warnings found by the last call to [`Tokenizer::tokenize`]. Warnings
+    /// don't stop compilation. Right now the only lint is the opt-in `--lint-confusables`
+    /// check for identifiers that mix scripts, like a Cyrillic `а` in a Latin name.
+    pub fn warnings(&self) -> &[LexError] {
+        &self.warnings
+    }
+
+    /// # Next Token
+    ///
+    /// Lexes the token starting at the current character.
+    ///
+    /// # Arguments
+    ///
+    /// * `ch` - The current character.
+    /// * `start` - The current position.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Token>, String>` - The token, `None` for whitespace and comments, or an
+    ///   error message if the source is malformed. The offending characters are always consumed,
+    ///   so the caller can carry on with the next token.
+    fn next_token(&mut self, ch: char, start: Position) -> Result<Option<Token>, String> {
+        if ch.is_whitespace() {
+            self.consume(1);
+            if ch == '\n' {
+                return Ok(Some(self.token(TokenType::TokNewline, None, start)));
+            }
+            Ok(None)
+        } else if ch == 'r' && self.is_raw_string_start() {
+            let value: String = self.lex_raw_string()?;
+            Ok(Some(self.token(
+                TokenType::TokStringLiteral,
+                Some(value),
+                start,
+            )))
+        } else if ch == '_' || ch.is_xid_start() {
+            // Identifiers follow UAX #31 (XID_Start XID_Continue*, plus a leading `_`) and are
+            // normalized to NFC, so names that look the same also compare equal.
+            self.consume(1);
+            while let Some(next_char) = self.peek(0) {
+                if next_char.is_xid_continue() {
+                    self.consume(1);
+                } else {
+                    break;
                 }
-                tokens.push(Token {
-                    tok_type: TokenType::TokNumLiteral,
-                    value: Some(tok_buf),
-                    line: self.line,
+            }
+            let text: &str = &self.src[start.offset..self.offset];
+            let tok_buf: String = if is_nfc(text) {
+                text.to_string()
+            } else {
+                text.nfc().collect()
+            };
+            if self.c_settings.is_lint_confusables && !tok_buf.as_str().is_single_script() {
+                self.warnings.push(LexError {
+                    message: format!(
+                        "Identifier `{}` mixes scripts and may be confused with another name",
+                        tok_buf
+                    ),
+                    span: self.span_from(start),
                 });
-            } else if ch == '/' {
-                if Some('/') == self.peek(1) {
-                    self.consume(2);
+            }
+            let token_type: TokenType = match tok_buf.as_str() {
+                "char" => TokenType::TokTypeChar,
+                "string" => TokenType::TokTypeString,
+                "f32" => TokenType::TokTypef32,
+                "f64" => TokenType::TokTypef64,
+                "i64" => TokenType::TokTypei64,
+                "u64" => TokenType::TokTypeu64,
+                "i32" => TokenType::TokTypei32,
+                "u32" => TokenType::TokTypeu32,
+                "i16" => TokenType::TokTypei16,
+                "u16" => TokenType::TokTypeu16,
+                "i8" => TokenType::TokTypei8,
+                "u8" => TokenType::TokTypeu8,
+                "bool" => TokenType::TokTypeBool,
+                "void" => TokenType::TokTypeVoid,
+                "true" => TokenType::TokTrue,
+                "false" => TokenType::TokFalse,
+                "Null" => TokenType::TokNull,
+                "struct" => TokenType::TokStruct,
+                "fun" => TokenType::TokFun,
+                "enum" => TokenType::TokEnum,
+                "const" => TokenType::TokConst,
+                "try" => TokenType::TokTry,
+                "catch" => TokenType::TokCatch,
+                "throw" => TokenType::TokThrow,
+                "finally" => TokenType::TokFinally,
+                "return" => TokenType::TokReturn,
+                "bellyflop" => TokenType::TokBellyflop,
+                "dive" => TokenType::TokDive,
+                "from" => TokenType::TokFrom,
+                "switch" => TokenType::TokSwitch,
+                "case" => TokenType::TokCase,
+                "default" => TokenType::TokDefault,
+                "if" => TokenType::TokIf,
+                "else" => TokenType::TokElse,
+                "type" => TokenType::TokAlias,
+                "do" => TokenType::TokDo,
+                "while" => TokenType::TokWhile,
+                "break" => TokenType::TokBreak,
+                "continue" => TokenType::TokContinue,
+                "for" => TokenType::TokFor,
+                "in" => TokenType::TokIn,
+                "when" => TokenType::TokWhen,
+                "main" => TokenType::TokMain,
+                _ => TokenType::TokIdentifier,
+            };
+            let value: Option<String> = if matches!(token_type, TokenType::TokIdentifier) {
+                Some(tok_buf)
+            } else {
+                None
+            };
+            Ok(Some(self.token(token_type, value, start)))
+        } else if ch.is_ascii_digit() {
+            let number: NumLiteral = match self.lex_number() {
+                Ok(number) => number,
+                Err(message) => {
+                    // Skip the rest of the malformed literal so it becomes a single error token
                     while let Some(next_char) = self.peek(0) {
-                        if next_char == '\n' {
+                        if next_char.is_alphanumeric()
+                            || next_char == '_'
+                            || (next_char == '.'
+                                && self.peek(1).is_some_and(|c| c.is_ascii_digit()))
+                        {
                             self.consume(1);
-                            self.line += 1;
-                            break;
                         } else {
-                            self.consume(1);
+                            break;
                         }
                     }
-                } else if Some('*') == self.peek(1) {
-                    self.consume(2);
-                    while let Some(next_char) = self.peek(0) {
-                        if next_char == '*' && self.peek(1) == Some('/') {
-                            self.consume(2);
-                            break;
-                        } else {
-                            if next_char == '\n' {
-                                self.line += 1;
-                            }
+                    return Err(message);
+                }
+            };
+            let mut token: Token =
+                self.token(TokenType::TokNumLiteral, Some(number.digits.clone()), start);
+            token.number = Some(number);
+            Ok(Some(token))
+        } else if ch == '/' && matches!(self.peek(1), Some('/' | '*')) {
+            if Some('/') == self.peek(1) {
+                // `///` is an outer doc comment and `//!` an inner one. `////...` is just a
+                // regular comment, so lines of slashes aren't picked up as docs.
+                let doc_type: Option<TokenType> = match (self.peek(2), self.peek(3)) {
+                    (Some('/'), Some('/')) => None,
+                    (Some('/'), _) => Some(TokenType::TokDocComment),
+                    (Some('!'), _) => Some(TokenType::TokInnerDocComment),
+                    _ => None,
+                };
+                self.consume(if doc_type.is_some() { 3 } else { 2 });
+                let text_start: usize = self.offset;
+                while let Some(next_char) = self.peek(0) {
+                    if next_char == '\n' {
+                        break;
+                    }
+                    self.consume(1);
+                }
+                if let Some(doc_type) = doc_type {
+                    let text: &str = self.src[text_start..self.offset].trim_end_matches('\r');
+                    let text: String = text.strip_prefix(' ').unwrap_or(text).to_string();
+                    return Ok(Some(self.token(doc_type, Some(text), start)));
+                }
+                Ok(None)
+            } else {
+                self.skip_block_comment()?;
+                Ok(None)
+            }
+        } else if ch == '\'' {
+            self.consume(1);
+            let value: char = match self.peek(0) {
+                Some('\'') => {
+                    self.consume(1);
+                    return Err("Empty character literal".to_string());
+                }
+                Some('\\') => match self.lex_escape() {
+                    Ok(value) => value,
+                    Err(message) => {
+                        if Some('\'') == self.peek(0) {
                             self.consume(1);
                         }
+                        return Err(message);
                     }
-                } else {
-                    tokens.push(Token {
-                        tok_type: TokenType::TokDivide,
-                        value: None,
-                        line: self.line,
-                    });
+                },
+                Some('\n') | None => {
+                    return Err("Unterminated character literal".to_string());
+                }
+                Some(next_char) => {
                     self.consume(1);
+                    next_char
                 }
-            } else if ch == '"' {
-                tok_buf = String::new();
-                tok_buf.push(ch);
-                self.consume(1);
+            };
+            if Some('\'') != self.peek(0) {
+                // Look for the closing quote on the same line to tell a literal with too many
+                // characters apart from one that is never closed.
                 while let Some(next_char) = self.peek(0) {
-                    if next_char == '"' {
-                        tok_buf.push(next_char);
+                    if next_char == '\'' {
                         self.consume(1);
+                        return Err("Character literal may only contain one character".to_string());
+                    } else if next_char == '\n' {
                         break;
-                    } else {
-                        tok_buf.push(next_char);
-                        self.consume(1);
                     }
+                    self.consume(1);
+                }
+                return Err("Unterminated character literal".to_string());
+            }
+            self.consume(1);
+            Ok(Some(self.token(
+                TokenType::TokCharLiteral,
+                Some(value.to_string()),
+                start,
+            )))
+        } else if ch == '"' {
+            let value: String = self.lex_string()?;
+            Ok(Some(self.token(
+                TokenType::TokStringLiteral,
+                Some(value),
+                start,
+            )))
+        } else {
+            let rest: &str = &self.src[self.offset..];
+            let Some((spelling, tok_type)) = OPERATORS
+                .iter()
+                .find(|(spelling, _)| rest.starts_with(spelling))
+            else {
+                self.consume(1);
+                return Err(format!("Unknown character {:?}", ch));
+            };
+            // Operators are all ASCII, so the spelling's byte length is its length in chars
+            self.consume(spelling.len());
+            Ok(Some(self.token(tok_type.clone(), None, start)))
+        }
+    }
+
+    /// # Skip Block Comment
+    ///
+    /// Consumes a block comment starting at the current `/*`. Block comments nest, so
+    /// `/* a /* b */ c */` is one comment, which makes it safe to comment out code that already
+    /// contains block comments.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok, or an error message if the comment is never closed. The
+    ///   error's span starts where the comment opened.
+    fn skip_block_comment(&mut self) -> Result<(), String> {
+        let mut depth: usize = 0;
+        while let Some(next_char) = self.peek(0) {
+            if next_char == '/' && Some('*') == self.peek(1) {
+                depth += 1;
+                self.consume(2);
+            } else if next_char == '*' && Some('/') == self.peek(1) {
+                depth -= 1;
+                self.consume(2);
+                if depth == 0 {
+                    return Ok(());
                 }
-                tokens.push(Token {
-                    tok_type: TokenType::TokStringLiteral,
-                    value: Some(tok_buf),
-                    line: self.line,
-                });
             } else {
                 self.consume(1);
-                let tok_type: TokenType = match ch {
-                    ':' => {
-                        if Some(':') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokDoubleColon
-                        } else {
-                            TokenType::TokColon
-                        }
-                    }
-                    '!' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokNotEquals
-                        } else {
-                            TokenType::TokBang
-                        }
-                    }
-                    '^' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokBitXorEqual
-                        } else {
-                            TokenType::TokBitXor
-                        }
-                    }
-                    '~' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokBitNotEqual
-                        } else {
-                            TokenType::TokBitNot
-                        }
+            }
+        }
+        Err("Unterminated block comment".to_string())
+    }
+
+    /// # Lex Number
+    ///
+    /// Consumes a number literal starting at the current digit.
+    ///
+    /// Accepts decimal literals with an optional fraction and exponent (`6.02e23`), `0x`, `0o`
+    /// and `0b` integer literals, `_` digit separators (`1_000_000`) and a type suffix that
+    /// matches one of the number type keywords (`42u8`, `3i64`, `1.5f32`). A `..` after the
+    /// digits is left alone so `1..10` still lexes as a range.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<NumLiteral, String>` - The structured literal, or an error message if the
+    ///   literal is malformed.
+    fn lex_number(&mut self) -> Result<NumLiteral, String> {
+        let radix: Radix = match (self.peek(0), self.peek(1)) {
+            (Some('0'), Some('x')) => Radix::Hexadecimal,
+            (Some('0'), Some('o')) => Radix::Octal,
+            (Some('0'), Some('b')) => Radix::Binary,
+            _ => Radix::Decimal,
+        };
+        if radix != Radix::Decimal {
+            self.consume(2);
+        }
+
+        let mut kind: NumKind = NumKind::Int;
+        let mut digits: String = self.lex_digits(radix);
+        if digits.is_empty() {
+            return Err(format!(
+                "Expected digits after `{}` prefix",
+                &self.src[self.offset - 2..self.offset]
+            ));
+        }
+
+        if radix == Radix::Decimal {
+            // A fraction needs a digit after the dot, so `1..10` and `x.0.len` are not floats
+            if Some('.') == self.peek(0) && self.peek(1).is_some_and(|ch| ch.is_ascii_digit()) {
+                kind = NumKind::Float;
+                self.consume(1);
+                digits.push('.');
+                digits.push_str(&self.lex_digits(radix));
+                if Some('.') == self.peek(0) && self.peek(1).is_some_and(|ch| ch.is_ascii_digit()) {
+                    return Err("Cannot put two decimal points in a float literal".to_string());
+                }
+            }
+            if matches!(self.peek(0), Some('e' | 'E')) {
+                let sign: usize = if matches!(self.peek(1), Some('+' | '-')) {
+                    1
+                } else {
+                    0
+                };
+                if self.peek(1 + sign).is_some_and(|ch| ch.is_ascii_digit()) {
+                    kind = NumKind::Float;
+                    digits.push('e');
+                    if sign == 1 {
+                        digits.push(self.peek(1).unwrap());
                     }
-                    '-' => {
-                        if Some('>') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokArrow
-                        } else if Some('-') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokDecrement
-                        } else {
-                            TokenType::TokMinus
-                        }
+                    self.consume(1 + sign);
+                    digits.push_str(&self.lex_digits(radix));
+                }
+            }
+        }
+
+        let mut suffix: Option<TokenType> = None;
+        if self
+            .peek(0)
+            .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+        {
+            let mut text: String = String::new();
+            while let Some(next_char) = self.peek(0) {
+                if next_char.is_alphanumeric() || next_char == '_' {
+                    text.push(next_char);
+                    self.consume(1);
+                } else {
+                    break;
+                }
+            }
+            let suffix_type: TokenType =
+                match NUM_SUFFIXES.iter().find(|(spelling, _)| *spelling == text) {
+                    Some((_, suffix_type)) => suffix_type.clone(),
+                    None if radix != Radix::Decimal
+                        && text.chars().next().is_some_and(|ch| ch.is_ascii_digit()) =>
+                    {
+                        return Err(format!(
+                            "Invalid digit `{}` in base {} literal",
+                            text.chars().next().unwrap(),
+                            radix as u32
+                        ));
                     }
-                    '+' => {
-                        if Some('+') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokIncrement
-                        } else if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokPlusEqual
-                        } else {
-                            TokenType::TokPlus
-                        }
+                    None => {
+                        return Err(format!("Invalid suffix `{}` for number literal", text));
                     }
-                    '*' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokTimesEqual
-                        } else {
-                            TokenType::TokAsterisk
-                        }
-                    }
-                    '%' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokModuloEqual
-                        } else {
-                            TokenType::TokModulo
-                        }
-                    }
-                    '<' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokLessEqual
-                        } else if Some('<') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokLeftShift
-                        } else {
-                            TokenType::TokLeftAngle
-                        }
-                    }
-                    '>' => {
-                        if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokGreaterEqual
-                        } else if Some('>') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokRightShift
-                        } else {
-                            TokenType::TokRightAngle
-                        }
-                    }
-                    '.' => {
-                        if Some('.') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokRange
-                        } else if Some('.') == self.peek(1) {
-                            self.consume(1);
-                            TokenType::TokEllipsis
-                        } else {
-                            TokenType::TokDot
-                        }
-                    }
-                    '=' => {
-                        if Some('=') == self.peek(0) {
+                };
+            if matches!(suffix_type, TokenType::TokTypef32 | TokenType::TokTypef64) {
+                if radix != Radix::Decimal {
+                    return Err(format!(
+                        "Float suffix `{}` is not allowed on a base {} literal",
+                        text, radix as u32
+                    ));
+                }
+                kind = NumKind::Float;
+            } else if kind == NumKind::Float {
+                return Err(format!(
+                    "Integer suffix `{}` is not allowed on a float literal",
+                    text
+                ));
+            }
+            suffix = Some(suffix_type);
+        }
+
+        Ok(NumLiteral {
+            radix,
+            kind,
+            digits,
+            suffix,
+        })
+    }
+
+    /// # Lex Digits
+    ///
+    /// Consumes a run of digits in the given radix, skipping `_` separators.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The digits that were consumed, without separators.
+    fn lex_digits(&mut self, radix: Radix) -> String {
+        let mut digits: String = String::new();
+        while let Some(next_char) = self.peek(0) {
+            if next_char.is_digit(radix as u32) {
+                digits.push(next_char);
+            } else if next_char != '_' {
+                break;
+            }
+            self.consume(1);
+        }
+        digits
+    }
+
+    /// # Lex String
+    ///
+    /// Consumes a string literal starting at the current `"` and returns its decoded value.
+    ///
+    /// Escape sequences are decoded with [`Tokenizer::lex_escape`]. A string may span several
+    /// lines; a `\` at the end of a line joins it to the next one and skips the leading
+    /// whitespace there.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - The decoded string, or an error message if the string is
+    ///   never closed or contains an invalid escape. An invalid escape is only reported once the
+    ///   closing quote has been consumed, so lexing can carry on after the string.
+    fn lex_string(&mut self) -> Result<String, String> {
+        let mut value: String = String::new();
+        let mut escape_error: Option<String> = None;
+        self.consume(1);
+        loop {
+            match self.peek(0) {
+                Some('"') => {
+                    self.consume(1);
+                    return match escape_error {
+                        Some(message) => Err(message),
+                        None => Ok(value),
+                    };
+                }
+                Some('\\') if Some('\n') == self.peek(1) => {
+                    self.consume(2);
+                    while let Some(next_char) = self.peek(0) {
+                        if next_char.is_whitespace() {
                             self.consume(1);
-                            TokenType::TokEquals
                         } else {
-                            TokenType::TokAssign
+                            break;
                         }
                     }
-                    '|' => {
-                        if Some('|') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokBitOr
-                        } else if Some('=') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokBitOrEqual
-                        } else {
-                            TokenType::TokOr
-                        }
+                }
+                Some('\\') => match self.lex_escape() {
+                    Ok(escaped) => value.push(escaped),
+                    Err(message) => {
+                        escape_error.get_or_insert(message);
                     }
-                    '&' => {
-                        if Some('&') == self.peek(0) {
-                            self.consume(1);
-                            TokenType::TokAnd
-                        } else if Some('=') == self.peek(0) {
+                },
+                Some(next_char) => {
+                    value.push(next_char);
+                    self.consume(1);
+                }
+                None => {
+                    return Err("Unterminated string literal".to_string());
+                }
+            }
+        }
+    }
+
+    /// # Is Raw String Start
+    ///
+    /// Checks whether the `r` at the current position starts a raw string (`r"..."` or
+    /// `r#"..."#`) rather than an identifier.
+    fn is_raw_string_start(&self) -> bool {
+        self.src[self.offset + 1..]
+            .trim_start_matches('#')
+            .starts_with('"')
+    }
+
+    /// # Lex Raw String
+    ///
+    /// Consumes a raw string literal starting at the current `r` and returns its contents.
+    ///
+    /// Raw strings do not process escapes. The opening quote may be preceded by any number of
+    /// `#`s, in which case the string only ends at a `"` followed by the same number of `#`s, so
+    /// `r#"say "hi""#` is `say "hi"`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - The string contents, or an error message if the string is
+    ///   never closed.
+    fn lex_raw_string(&mut self) -> Result<String, String> {
+        self.consume(1);
+        let mut hashes: usize = 0;
+        while Some('#') == self.peek(0) {
+            hashes += 1;
+            self.consume(1);
+        }
+        self.consume(1);
+        let closing: String = format!("\"{}", "#".repeat(hashes));
+        match self.src[self.offset..].find(&closing) {
+            Some(length) => {
+                let value: String = self.src[self.offset..self.offset + length].to_string();
+                let chars: usize = value.chars().count();
+                self.consume(chars + 1 + hashes);
+                Ok(value)
+            }
+            None => {
+                self.consume(usize::MAX);
+                Err("Unterminated string literal".to_string())
+            }
+        }
+    }
+
+    /// # Lex Escape
+    ///
+    /// Consumes an escape sequence starting at the current `\` and returns the character it
+    /// stands for.
+    ///
+    /// Supported escapes are `\n`, `\t`, `\r`, `\\`, `\'`, `\"`, `\0`, `\xHH` (up to
+    /// `\x7F`) and `\u{H...}` (1 to 6 hex digits naming a unicode scalar value).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<char, String>` - The decoded character, or an error message if the escape is
+    ///   invalid.
+    fn lex_escape(&mut self) -> Result<char, String> {
+        self.consume(1);
+        let Some(ch) = self.peek(0) else {
+            return Err("Unterminated escape sequence".to_string());
+        };
+        self.consume(1);
+        match ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '\\' => Ok('\\'),
+            '\'' => Ok('\''),
+            '"' => Ok('"'),
+            '0' => Ok('\0'),
+            'x' => {
+                let mut digits: String = String::new();
+                for _ in 0..2 {
+                    match self.peek(0) {
+                        Some(digit) if digit.is_ascii_hexdigit() => {
+                            digits.push(digit);
                             self.consume(1);
-                            TokenType::TokBitAndEqual
-                        } else {
-                            TokenType::TokAmpersand
                         }
+                        _ => break,
                     }
-                    '$' => TokenType::TokDollar,
-                    '?' => TokenType::TokQuestion,
-                    '@' => TokenType::TokAt,
-                    '#' => TokenType::TokPound,
-                    ';' => TokenType::TokSemi,
-                    '{' => TokenType::TokLeftBrace,
-                    '}' => TokenType::TokRightBrace,
-                    '(' => TokenType::TokLeftParen,
-                    ')' => TokenType::TokRightParen,
-                    '[' => TokenType::TokLeftBracket,
-                    ']' => TokenType::TokRightBracket,
-                    ',' => TokenType::TokComma,
-                    _ => {
-                        continue;
+                }
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && value <= 0x7F => Ok(value as char),
+                    _ => Err(format!(
+                        "Invalid escape `\\x{}`: expected two hex digits up to 7F",
+                        digits
+                    )),
+                }
+            }
+            'u' => {
+                if Some('{') != self.peek(0) {
+                    return Err("Invalid escape `\\u`: expected `{` after `\\u`".to_string());
+                }
+                self.consume(1);
+                let mut digits: String = String::new();
+                while let Some(digit) = self.peek(0) {
+                    if digit.is_ascii_hexdigit() {
+                        digits.push(digit);
+                        self.consume(1);
+                    } else {
+                        break;
                     }
-                };
-                // Push the token
-                tokens.push(Token {
-                    tok_type,
-                    value: None,
-                    line: self.line,
-                });
+                }
+                if Some('}') != self.peek(0) || digits.is_empty() || digits.len() > 6 {
+                    return Err(format!(
+                        "Invalid escape `\\u{{{}`: expected 1 to 6 hex digits and a `}}`",
+                        digits
+                    ));
+                }
+                self.consume(1);
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(format!(
+                        "Invalid escape `\\u{{{}}}`: not a unicode scalar value",
+                        digits
+                    ))
             }
+            _ => Err(format!("Unknown escape sequence `\\{}`", ch)),
         }
-        tokens.push(Token {
-            tok_type: TokenType::TokEOF,
-            value: None,
-            line: self.line,
-        });
-        zlog::verbose(
-            &format!(
-                "Tokenization Completed. Lines: {}, Tokens: {}",
-                self.line,
-                tokens.len()
-            ),
-            self.c_settings,
-        );
-        Ok(tokens)
     }
 
     /// # Peek
@@ -544,16 +1282,15 @@ impl<'a> Tokenizer<'a> {
     ///
     /// * `Option<char>` - Returns Some(char) if there is a character to peek at, otherwise None.
     fn peek(&self, forward: usize) -> Option<char> {
-        if self.index + forward < self.src.len() {
-            self.src.chars().nth(self.index + forward)
-        } else {
-            None
-        }
+        // `offset` always sits on a char boundary, and `forward` is only ever a few characters,
+        // so this stays constant time per call.
+        self.src[self.offset..].chars().nth(forward)
     }
 
     /// # Consume
     ///
-    /// Consumes a given amount of characters from the source.
+    /// Consumes a given amount of characters from the source, keeping the byte offset, line and
+    /// column in step.
     ///
     /// # Arguments
     ///
@@ -563,10 +1300,879 @@ impl<'a> Tokenizer<'a> {
     ///
     /// * `Result<(), String>` - Returns Ok if successful or an error message if not.
     fn consume(&mut self, amount: usize) {
-        if self.index + amount <= self.src.len() {
-            self.index += amount;
+        for _ in 0..amount {
+            let Some(ch) = self.peek(0) else {
+                break;
+            };
+            self.offset += ch.len_utf8();
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+
+    /// # Position
+    ///
+    /// Returns the byte offset, line and column the tokenizer is currently at.
+    fn position(&self) -> Position {
+        Position {
+            offset: self.offset,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// # Token
+    ///
+    /// Builds a token whose span runs from `start` to the current position.
+    ///
+    /// # Arguments
+    ///
+    /// * `tok_type` - The type of the token.
+    /// * `value` - The value of the token, if it has one.
+    /// * `start` - The position the token started at.
+    fn token(&self, tok_type: TokenType, value: Option<String>, start: Position) -> Token {
+        Token {
+            tok_type,
+            value,
+            number: None,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+            span: self.span_from(start),
+        }
+    }
+
+    /// # Trivia
+    ///
+    /// Builds the trivia that runs from `start` to the current position. The kind is worked out
+    /// from the text.
+    fn trivia(&self, start: Position) -> Trivia {
+        let text: &str = &self.src[start.offset..self.offset];
+        let kind: TriviaKind = if text.starts_with("//") {
+            TriviaKind::LineComment
+        } else if text.starts_with("/*") {
+            TriviaKind::BlockComment
+        } else if text == "\n" {
+            TriviaKind::Newline
         } else {
-            self.index = self.src.len();
+            TriviaKind::Whitespace
+        };
+        Trivia {
+            kind,
+            span: self.span_from(start),
+        }
+    }
+
+    /// # Span From
+    ///
+    /// Builds a span that runs from `start` to the current position.
+    fn span_from(&self, start: Position) -> Span {
+        Span::new(
+            self.file_id,
+            start.offset,
+            self.offset,
+            start.line,
+            start.column,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenize(src: &str) -> Vec<Token> {
+        let c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        let tokens: Vec<Token> = tokenizer.tokenize();
+        assert_eq!(tokenizer.errors(), []);
+        tokens
+    }
+
+    fn lex_errors(src: &str) -> Vec<String> {
+        let c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        tokenizer.tokenize();
+        tokenizer
+            .errors()
+            .iter()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn spans_have_offsets_lines_and_columns() {
+        let src = "fun main() {\n    return 41+1;\n}";
+        let tokens = tokenize(src);
+
+        let ret = tokens
+            .iter()
+            .find(|token| token.tok_type == TokenType::TokReturn)
+            .unwrap();
+        assert_eq!(ret.span.text(src), "return");
+        assert_eq!((ret.span.line, ret.span.column), (2, 5));
+
+        let num = tokens
+            .iter()
+            .find(|token| token.tok_type == TokenType::TokNumLiteral)
+            .unwrap();
+        assert_eq!(num.span.text(src), "41");
+        assert_eq!((num.span.line, num.span.column), (2, 12));
+
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.tok_type, TokenType::TokEOF);
+        assert_eq!((eof.span.start, eof.span.end), (src.len(), src.len()));
+    }
+
+    #[test]
+    fn spans_use_file_id() {
+        let c_settings: CSettings = CSettings::default();
+        let tokens = Tokenizer::new("x".to_string(), &c_settings)
+            .with_file_id(FileId(3))
+            .tokenize();
+        assert!(tokens.iter().all(|token| token.span.file_id == FileId(3)));
+    }
+
+    #[test]
+    fn handles_non_ascii_source() {
+        let src = "// héllo wörld\nnäme = \"日本\";";
+        let tokens: Vec<Token> = tokenize(src)
+            .into_iter()
+            .filter(|token| token.tok_type != TokenType::TokNewline)
+            .collect();
+
+        let ident = &tokens[0];
+        assert_eq!(ident.tok_type, TokenType::TokIdentifier);
+        assert_eq!(ident.value.as_deref(), Some("näme"));
+        assert_eq!((ident.span.line, ident.span.column), (2, 1));
+
+        let string = &tokens[2];
+        assert_eq!(string.tok_type, TokenType::TokStringLiteral);
+        assert_eq!(string.span.text(src), "\"日本\"");
+        assert_eq!(string.span.column, 8);
+        assert_eq!(tokens[3].tok_type, TokenType::TokSemi);
+        assert_eq!(tokens[3].span.column, 12);
+    }
+
+    #[test]
+    fn char_literals_decode_escapes() {
+        let src = r"'a' '\n' '\t' '\\' '\'' '\0' '\x7F' '\u{1F600}'";
+        let values: Vec<String> = tokenize(src)
+            .into_iter()
+            .filter(|token| token.tok_type == TokenType::TokCharLiteral)
+            .map(|token| token.value.unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            ["a", "\n", "\t", "\\", "'", "\0", "\x7F", "\u{1F600}"]
+        );
+    }
+
+    #[test]
+    fn char_literal_errors_carry_line() {
+        assert_eq!(
+            lex_errors("\nx = '';"),
+            ["[Line 2, Column 5] Empty character literal"]
+        );
+        assert_eq!(
+            lex_errors("x = 'ab';"),
+            ["[Line 1, Column 5] Character literal may only contain one character"]
+        );
+        assert_eq!(
+            lex_errors("x = 'a"),
+            ["[Line 1, Column 5] Unterminated character literal"]
+        );
+        assert!(lex_errors(r"'\x80'")[0].contains("Invalid escape"));
+    }
+
+    #[test]
+    fn string_literals_are_decoded() {
+        let src = r#""Hello, world!\n" "say \"hi\"" "tab\tend" "\u{1F600}""#;
+        let values: Vec<String> = tokenize(src)
+            .into_iter()
+            .filter(|token| token.tok_type == TokenType::TokStringLiteral)
+            .map(|token| token.value.unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            ["Hello, world!\n", "say \"hi\"", "tab\tend", "\u{1F600}"]
+        );
+    }
+
+    #[test]
+    fn raw_strings_skip_escapes() {
+        let src = r###"r"C:\zinc\n" r#"say "hi""# raw"###;
+        let tokens = tokenize(src);
+        assert_eq!(tokens[0].value.as_deref(), Some(r"C:\zinc\n"));
+        assert_eq!(tokens[1].value.as_deref(), Some(r#"say "hi""#));
+        assert_eq!(tokens[1].span.text(src), r###"r#"say "hi""#"###);
+        assert_eq!(tokens[2].tok_type, TokenType::TokIdentifier);
+    }
+
+    #[test]
+    fn multi_line_strings_keep_line_count() {
+        let src = "x = \"one\ntwo\";\ny = \"joined \\\n    here\";\nz";
+        let tokens = tokenize(src);
+        let strings: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| token.tok_type == TokenType::TokStringLiteral)
+            .collect();
+        assert_eq!(strings[0].value.as_deref(), Some("one\ntwo"));
+        assert_eq!(strings[1].value.as_deref(), Some("joined here"));
+        assert_eq!(strings[1].span.line, 3);
+        let z = tokens
+            .iter()
+            .find(|token| token.value.as_deref() == Some("z"))
+            .unwrap();
+        assert_eq!((z.span.line, z.span.column), (5, 1));
+    }
+
+    #[test]
+    fn unterminated_string_is_an_error() {
+        assert_eq!(
+            lex_errors("\nx = \"never closed;\n\n"),
+            ["[Line 2, Column 5] Unterminated string literal"]
+        );
+        assert_eq!(
+            lex_errors("r#\"raw\""),
+            ["[Line 1, Column 1] Unterminated string literal"]
+        );
+    }
+
+    fn number(src: &str) -> NumLiteral {
+        let tokens = tokenize(src);
+        assert_eq!(tokens.len(), 2, "`{}` should be a single token", src);
+        tokens[0].number.clone().unwrap()
+    }
+
+    #[test]
+    fn number_literals_are_structured() {
+        let cases = [
+            ("42", Radix::Decimal, NumKind::Int, "42", None),
+            ("0xFF", Radix::Hexadecimal, NumKind::Int, "FF", None),
+            ("0o755", Radix::Octal, NumKind::Int, "755", None),
+            ("0b1010", Radix::Binary, NumKind::Int, "1010", None),
+            ("1_000_000", Radix::Decimal, NumKind::Int, "1000000", None),
+            ("3.14", Radix::Decimal, NumKind::Float, "3.14", None),
+            ("6.02e23", Radix::Decimal, NumKind::Float, "6.02e23", None),
+            ("1E-3", Radix::Decimal, NumKind::Float, "1e-3", None),
+            (
+                "42u8",
+                Radix::Decimal,
+                NumKind::Int,
+                "42",
+                Some(TokenType::TokTypeu8),
+            ),
+            (
+                "3i64",
+                Radix::Decimal,
+                NumKind::Int,
+                "3",
+                Some(TokenType::TokTypei64),
+            ),
+            (
+                "1.5f32",
+                Radix::Decimal,
+                NumKind::Float,
+                "1.5",
+                Some(TokenType::TokTypef32),
+            ),
+            (
+                "1f64",
+                Radix::Decimal,
+                NumKind::Float,
+                "1",
+                Some(TokenType::TokTypef64),
+            ),
+            (
+                "0xFF_u16",
+                Radix::Hexadecimal,
+                NumKind::Int,
+                "FF",
+                Some(TokenType::TokTypeu16),
+            ),
+        ];
+        for (src, radix, kind, digits, suffix) in cases {
+            let literal = number(src);
+            assert_eq!(
+                literal,
+                NumLiteral {
+                    radix,
+                    kind,
+                    digits: digits.to_string(),
+                    suffix,
+                },
+                "{}",
+                src
+            );
+        }
+        assert_eq!(number("0xFF").int_value(), Some(255));
+        assert_eq!(number("0o755").int_value(), Some(0o755));
+        assert_eq!(number("6.02e23").float_value(), Some(6.02e23));
+    }
+
+    #[test]
+    fn numbers_stop_before_ranges() {
+        let types: Vec<TokenType> = tokenize("1..10 1...5 3.14.max")
+            .into_iter()
+            .map(|token| token.tok_type)
+            .collect();
+        assert_eq!(
+            types,
+            [
+                TokenType::TokNumLiteral,
+                TokenType::TokRange,
+                TokenType::TokNumLiteral,
+                TokenType::TokNumLiteral,
+                TokenType::TokEllipsis,
+                TokenType::TokNumLiteral,
+                TokenType::TokNumLiteral,
+                TokenType::TokDot,
+                TokenType::TokIdentifier,
+                TokenType::TokEOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_numbers_are_errors() {
+        assert_eq!(
+            lex_errors("1.2.3"),
+            ["[Line 1, Column 1] Cannot put two decimal points in a float literal"]
+        );
+        assert_eq!(
+            lex_errors("0b102"),
+            ["[Line 1, Column 1] Invalid digit `2` in base 2 literal"]
+        );
+        assert_eq!(
+            lex_errors("42u7"),
+            ["[Line 1, Column 1] Invalid suffix `u7` for number literal"]
+        );
+        assert_eq!(
+            lex_errors("1.5u8"),
+            ["[Line 1, Column 1] Integer suffix `u8` is not allowed on a float literal"]
+        );
+        assert_eq!(
+            lex_errors("0x"),
+            ["[Line 1, Column 1] Expected digits after `0x` prefix"]
+        );
+    }
+
+    #[test]
+    fn every_documented_keyword_is_reserved() {
+        // The first column of every table row in the keyword reference, i.e. `| `fun` | ...`
+        let keywords: Vec<&str> = include_str!("../docs/keywords.md")
+            .lines()
+            .filter_map(|line| line.strip_prefix("| `"))
+            .filter_map(|line| line.split('`').next())
+            .collect();
+        assert!(keywords.len() > 40);
+        for keyword in keywords {
+            let tokens = tokenize(keyword);
+            assert_ne!(
+                tokens[0].tok_type,
+                TokenType::TokIdentifier,
+                "`{}` is documented as a keyword",
+                keyword
+            );
+            assert_eq!(tokens.len(), 2);
+        }
+    }
+
+    #[test]
+    fn literal_keywords() {
+        let types: Vec<TokenType> = tokenize("true false Null void null True")
+            .into_iter()
+            .map(|token| token.tok_type)
+            .collect();
+        assert_eq!(
+            types,
+            [
+                TokenType::TokTrue,
+                TokenType::TokFalse,
+                TokenType::TokNull,
+                TokenType::TokTypeVoid,
+                TokenType::TokIdentifier,
+                TokenType::TokIdentifier,
+                TokenType::TokEOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_comments_are_kept() {
+        let src =
+            "//! Module docs\n// plain comment\n/// # Adds\n///   indented\n////// banner\nfun";
+        let docs: Vec<(TokenType, String)> = tokenize(src)
+            .into_iter()
+            .filter(|token| token.value.is_some())
+            .map(|token| (token.tok_type, token.value.unwrap()))
+            .collect();
+        assert_eq!(
+            docs,
+            [
+                (TokenType::TokInnerDocComment, "Module docs".to_string()),
+                (TokenType::TokDocComment, "# Adds".to_string()),
+                (TokenType::TokDocComment, "  indented".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        let src = "a /* outer /* inner */ still comment */ b /**/ c";
+        let values: Vec<String> = tokenize(src)
+            .into_iter()
+            .filter_map(|token| token.value)
+            .collect();
+        assert_eq!(values, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn unterminated_block_comment_points_at_opening() {
+        assert_eq!(
+            lex_errors("x = 1;\n  /* open /* nested */\nfun"),
+            ["[Line 2, Column 3] Unterminated block comment"]
+        );
+    }
+
+    #[test]
+    fn every_lex_error_is_reported() {
+        let src = "x = `1;\ny = ' ;\nz = 1.2.3 + \"\\q\" + ‽;";
+        assert_eq!(
+            lex_errors(src),
+            [
+                "[Line 1, Column 5] Unknown character '`'",
+                "[Line 2, Column 5] Unterminated character literal",
+                "[Line 3, Column 5] Cannot put two decimal points in a float literal",
+                "[Line 3, Column 13] Unknown escape sequence `\\q`",
+                "[Line 3, Column 20] Unknown character '‽'",
+            ]
+        );
+    }
+
+    #[test]
+    fn lexing_continues_after_errors() {
+        let c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new("a ` b 'xy' c".to_string(), &c_settings);
+        let tokens: Vec<(TokenType, Option<String>)> = tokenizer
+            .tokenize()
+            .into_iter()
+            .map(|token| (token.tok_type, token.value))
+            .collect();
+        let ident = |name: &str| (TokenType::TokIdentifier, Some(name.to_string()));
+        let error = |text: &str| (TokenType::TokError, Some(text.to_string()));
+        assert_eq!(
+            tokens,
+            [
+                ident("a"),
+                error("`"),
+                ident("b"),
+                error("'xy'"),
+                ident("c"),
+                (TokenType::TokEOF, None),
+            ]
+        );
+        assert_eq!(tokenizer.errors()[1].span.column, 7);
+    }
+
+    fn types(src: &str) -> Vec<TokenType> {
+        tokenize(src)
+            .into_iter()
+            .map(|token| token.tok_type)
+            .collect()
+    }
+
+    #[test]
+    fn every_operator_round_trips_from_its_spelling() {
+        for (spelling, tok_type) in OPERATORS {
+            let tokens = tokenize(spelling);
+            assert_eq!(tokens.len(), 2, "`{}` should be a single token", spelling);
+            assert_eq!(tokens[0].tok_type, *tok_type, "{}", spelling);
+            assert_eq!(tokens[0].span.text(spelling), *spelling);
+        }
+    }
+
+    #[test]
+    fn operator_table_covers_every_operator_type() {
+        let operator_types = [
+            TokenType::TokAssign,
+            TokenType::TokLeftParen,
+            TokenType::TokRightParen,
+            TokenType::TokLeftBrace,
+            TokenType::TokRightBrace,
+            TokenType::TokLeftBracket,
+            TokenType::TokRightBracket,
+            TokenType::TokSemi,
+            TokenType::TokDot,
+            TokenType::TokComma,
+            TokenType::TokPound,
+            TokenType::TokAt,
+            TokenType::TokAmpersand,
+            TokenType::TokAsterisk,
+            TokenType::TokArrow,
+            TokenType::TokColon,
+            TokenType::TokDoubleColon,
+            TokenType::TokModulo,
+            TokenType::TokPlus,
+            TokenType::TokMinus,
+            TokenType::TokIncrement,
+            TokenType::TokDecrement,
+            TokenType::TokEquals,
+            TokenType::TokTimesEqual,
+            TokenType::TokDivideEqual,
+            TokenType::TokPlusEqual,
+            TokenType::TokMinusEqual,
+            TokenType::TokModuloEqual,
+            TokenType::TokNotEquals,
+            TokenType::TokBitAndEqual,
+            TokenType::TokBitOrEqual,
+            TokenType::TokBitXorEqual,
+            TokenType::TokBitNotEqual,
+            TokenType::TokLeftShiftEqual,
+            TokenType::TokRightShiftEqual,
+            TokenType::TokLeftAngle,
+            TokenType::TokRightAngle,
+            TokenType::TokLessEqual,
+            TokenType::TokGreaterEqual,
+            TokenType::TokBitOr,
+            TokenType::TokBitXor,
+            TokenType::TokBitNot,
+            TokenType::TokLeftShift,
+            TokenType::TokRightShift,
+            TokenType::TokEllipsis,
+            TokenType::TokQuestion,
+            TokenType::TokDollar,
+            TokenType::TokRange,
+            TokenType::TokDivide,
+            TokenType::TokAnd,
+            TokenType::TokOr,
+            TokenType::TokBang,
+        ];
+        assert_eq!(operator_types.len(), OPERATORS.len());
+        for tok_type in operator_types {
+            assert!(
+                OPERATORS.iter().any(|(_, entry)| *entry == tok_type),
+                "{:?} is missing from OPERATORS",
+                tok_type
+            );
+        }
+    }
+
+    #[test]
+    fn operator_table_is_longest_first() {
+        for pair in OPERATORS.windows(2) {
+            assert!(pair[0].0.len() >= pair[1].0.len(), "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn operators_use_maximal_munch() {
+        use TokenType::*;
+        assert_eq!(
+            types("a<<=b>>=c"),
+            [
+                TokIdentifier,
+                TokLeftShiftEqual,
+                TokIdentifier,
+                TokRightShiftEqual,
+                TokIdentifier,
+                TokEOF
+            ]
+        );
+        assert_eq!(
+            types("x---y"),
+            [TokIdentifier, TokDecrement, TokMinus, TokIdentifier, TokEOF]
+        );
+        assert_eq!(
+            types("a||b|c"),
+            [
+                TokIdentifier,
+                TokOr,
+                TokIdentifier,
+                TokBitOr,
+                TokIdentifier,
+                TokEOF
+            ]
+        );
+        assert_eq!(
+            types(".x..y...z"),
+            [
+                TokDot,
+                TokIdentifier,
+                TokRange,
+                TokIdentifier,
+                TokEllipsis,
+                TokIdentifier,
+                TokEOF
+            ]
+        );
+        assert_eq!(
+            types("a/=b-=c"),
+            [
+                TokIdentifier,
+                TokDivideEqual,
+                TokIdentifier,
+                TokMinusEqual,
+                TokIdentifier,
+                TokEOF
+            ]
+        );
+        assert_eq!(
+            types("a<<<b"),
+            [
+                TokIdentifier,
+                TokLeftShift,
+                TokLeftAngle,
+                TokIdentifier,
+                TokEOF
+            ]
+        );
+    }
+
+    #[test]
+    fn identifiers_follow_uax31() {
+        let values: Vec<String> = tokenize("_private __x2 naïve 名前 Ωmega")
+            .into_iter()
+            .filter_map(|token| token.value)
+            .collect();
+        assert_eq!(values, ["_private", "__x2", "naïve", "名前", "Ωmega"]);
+
+        // `½` is numeric but not XID_Continue, so it can't be part of a name
+        assert_eq!(
+            lex_errors("half½"),
+            ["[Line 1, Column 5] Unknown character '½'"]
+        );
+    }
+
+    #[test]
+    fn identifiers_are_nfc_normalized() {
+        // "é" as one code point and as "e" + combining acute accent
+        let tokens = tokenize("caf\u{e9} cafe\u{301}");
+        assert_eq!(tokens[0].value.as_deref(), Some("caf\u{e9}"));
+        assert_eq!(tokens[0].value, tokens[1].value);
+        assert_eq!(tokens[1].span.len(), "cafe\u{301}".len());
+    }
+
+    #[test]
+    fn confusable_lint_is_opt_in() {
+        // The `а` in `pаssword` is Cyrillic
+        let src = "password pаssword 日本語とカタカナ";
+        let mut c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        tokenizer.tokenize();
+        assert!(tokenizer.warnings().is_empty());
+
+        c_settings.is_lint_confusables = true;
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        tokenizer.tokenize();
+        let warnings: Vec<String> = tokenizer
+            .warnings()
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                "[Line 1, Column 10] Identifier `pаssword` mixes scripts and may be confused with another name"
+            ]
+        );
+    }
+
+    fn lossless(src: &str) -> Vec<Token> {
+        let c_settings: CSettings = CSettings::default();
+        Tokenizer::new(src.to_string(), &c_settings)
+            .lossless()
+            .tokenize()
+    }
+
+    #[test]
+    fn lossless_tokens_rebuild_the_source() {
+        let sources = [
+            include_str!("../demos/syntax.zc"),
+            include_str!("../demos/ast_test.zc"),
+            "",
+            "  \n\n",
+            "x = 1; // trailing\r\n\t/* block\n */ y\u{a0}= `bad` 'ab' \"open",
+            "/// docs\n//! inner\nfun   main ( ) { }   ",
+            "/* never closed",
+        ];
+        for src in sources {
+            let rebuilt: String = lossless(src)
+                .iter()
+                .map(|token| token.to_source(src))
+                .collect();
+            assert_eq!(rebuilt, src);
+        }
+    }
+
+    #[test]
+    fn trivia_attaches_to_the_nearest_token() {
+        let src = "// header\nx = 1; // one\n\n  y";
+        let tokens = lossless(src);
+        let kinds = |trivia: &[Trivia]| -> Vec<TriviaKind> {
+            trivia.iter().map(|trivia| trivia.kind).collect()
+        };
+
+        assert!(
+            tokens
+                .iter()
+                .all(|token| token.tok_type != TokenType::TokNewline)
+        );
+        assert_eq!(
+            kinds(&tokens[0].leading_trivia),
+            [TriviaKind::LineComment, TriviaKind::Newline]
+        );
+        assert_eq!(kinds(&tokens[0].trailing_trivia), [TriviaKind::Whitespace]);
+
+        let semi = &tokens[3];
+        assert_eq!(semi.tok_type, TokenType::TokSemi);
+        assert_eq!(
+            kinds(&semi.trailing_trivia),
+            [
+                TriviaKind::Whitespace,
+                TriviaKind::LineComment,
+                TriviaKind::Newline
+            ]
+        );
+
+        let y = &tokens[4];
+        assert_eq!(
+            kinds(&y.leading_trivia),
+            [TriviaKind::Newline, TriviaKind::Whitespace]
+        );
+        assert_eq!(y.leading_trivia[1].span.text(src), "  ");
+    }
+
+    /// Applies a series of pseudo-random edits with `relex` and checks every result against
+    /// lexing the edited source from scratch.
+    fn check_relex_matches_full_lex(is_lossless: bool) {
+        let snippets = [
+            "\"", "/*", "*/", "'", "x", "\n", "1.5", " ", "//", "r#\"", "\"#", "é", "", "`", "fun",
+            "0x",
+        ];
+        let c_settings: CSettings = CSettings::default();
+        let new_tokenizer = |src: &str| {
+            let tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+            if is_lossless {
+                tokenizer.lossless()
+            } else {
+                tokenizer
+            }
+        };
+
+        let mut src: String = include_str!("../demos/syntax.zc").to_string();
+        let mut incremental: Tokenizer = new_tokenizer(&src);
+        let mut tokens: Vec<Token> = incremental.tokenize();
+        let mut seed: u64 = 42;
+        let mut random = |max: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % max.max(1)
+        };
+        for _ in 0..300 {
+            let mut start: usize = random(src.len() + 1);
+            while !src.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end: usize = (start + random(8)).min(src.len());
+            while !src.is_char_boundary(end) {
+                end += 1;
+            }
+            let edit: TextEdit = TextEdit {
+                range: start..end,
+                replacement: snippets[random(snippets.len())].to_string(),
+            };
+            src.replace_range(edit.range.clone(), &edit.replacement);
+            tokens = incremental.relex(tokens, &edit);
+
+            let mut full: Tokenizer = new_tokenizer(&src);
+            assert_eq!(tokens, full.tokenize(), "after {:?}", edit);
+            assert_eq!(incremental.errors(), full.errors(), "after {:?}", edit);
+        }
+    }
+
+    #[test]
+    fn relex_matches_full_lex() {
+        check_relex_matches_full_lex(false);
+    }
+
+    #[test]
+    fn relex_matches_full_lex_in_lossless_mode() {
+        check_relex_matches_full_lex(true);
+    }
+
+    #[test]
+    fn relex_reuses_tokens_after_the_edit() {
+        let c_settings: CSettings = CSettings::default();
+        let mut tokenizer: Tokenizer = Tokenizer::new("a = 1;\nb = 2;".to_string(), &c_settings);
+        let tokens: Vec<Token> = tokenizer.tokenize();
+        let edit: TextEdit = TextEdit {
+            range: 4..5,
+            replacement: "/* one */ 100".to_string(),
+        };
+        let tokens: Vec<Token> = tokenizer.relex(tokens, &edit);
+        assert_eq!(tokens[2].value.as_deref(), Some("100"));
+        assert_eq!((tokens[2].span.line, tokens[2].span.column), (1, 15));
+        assert_eq!(tokens[3].span.column, 18);
+        let b = &tokens[5];
+        assert_eq!(b.value.as_deref(), Some("b"));
+        assert_eq!((b.span.start, b.span.line, b.span.column), (19, 2, 1));
+    }
+
+    #[test]
+    fn relex_handles_strings_and_comments_opening_and_closing() {
+        let c_settings: CSettings = CSettings::default();
+        let src = "a = 1; b = 2; c = 3;";
+        let edits = [
+            (4..4, "\""),  // Opens a string that swallows the rest of the file
+            (4..5, ""),    // Closes it again
+            (0..0, "/* "), // Opens a block comment
+            (0..3, ""),    // And closes it
+            (7..7, "/* "),
+            (18..18, " */"),
+        ];
+        let mut tokenizer: Tokenizer = Tokenizer::new(src.to_string(), &c_settings);
+        let mut tokens: Vec<Token> = tokenizer.tokenize();
+        let mut src: String = src.to_string();
+        for (range, replacement) in edits {
+            src.replace_range(range.clone(), replacement);
+            let edit: TextEdit = TextEdit {
+                range,
+                replacement: replacement.to_string(),
+            };
+            tokens = tokenizer.relex(tokens, &edit);
+            let mut full: Tokenizer = Tokenizer::new(src.clone(), &c_settings);
+            assert_eq!(tokens, full.tokenize(), "{}", src);
+            assert_eq!(tokenizer.errors(), full.errors(), "{}", src);
+        }
+        assert_eq!(src, "a = 1; /* b = 2; c */ = 3;");
+    }
+
+    /// Synthetic benchmark. Run with
+    /// `cargo test --release tokenize_throughput -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn tokenize_throughput() {
+        let function = "fun add_numbers(x: i32, y: i32) -> i32 {\n    // Add the two numbers\n    z: i32 = x + y * 2;\n    println(\"{} + {} = {}\", x, y, z);\n    return z;\n}\n\n";
+        for lines in [10_000, 50_000, 100_000] {
+            let src: String = function.repeat(lines / 7);
+            let c_settings: CSettings = CSettings::default();
+            let started = std::time::Instant::now();
+            let tokens = Tokenizer::new(src.clone(), &c_settings).tokenize();
+            let elapsed = started.elapsed();
+            println!(
+                "{:>7} lines, {:>9} bytes, {:>8} tokens: {:>8.2?} ({:.1} MB/s)",
+                lines,
+                src.len(),
+                tokens.len(),
+                elapsed,
+                src.len() as f64 / elapsed.as_secs_f64() / 1_000_000.0
+            );
         }
     }
 }
@@ -31,7 +31,7 @@ pub fn verbose(message: &str, c_settings: &CSettings) {
 
 /// # Error Log
 ///
-/// Prints a zinc standard error message.
+/// Prints a zinc standard error message, with or without `--verbose`.
 ///
 /// # Arguments
 ///
@@ -48,18 +48,16 @@ pub fn verbose(message: &str, c_settings: &CSettings) {
 /// zlog::err("This is an error message", &c_settings);
 /// ```
 pub fn err(message: &str, c_settings: &CSettings) {
-    if c_settings.is_verbose {
-        if c_settings.is_no_color {
-            println!("[ERROR] {}", message);
-        } else {
-            println!("{} {}", "[ERROR]".red(), message);
-        }
+    if c_settings.is_no_color {
+        println!("[ERROR] {}", message);
+    } else {
+        println!("{} {}", "[ERROR]".red(), message);
     }
 }
 
 /// # Warning Log
 ///
-/// Prints a zinc standard warning message.
+/// Prints a zinc standard warning message, with or without `--verbose`.
 ///
 /// # Arguments
 ///
@@ -76,18 +74,16 @@ pub fn err(message: &str, c_settings: &CSettings) {
 /// zlog::warn("This is an error message", &c_settings);
 /// ```
 pub fn warn(message: &str, c_settings: &CSettings) {
-    if c_settings.is_verbose {
-        if c_settings.is_no_color {
-            println!("[WARNING] {}", message);
-        } else {
-            println!("{} {}", "[WARNING]".yellow(), message);
-        }
+    if c_settings.is_no_color {
+        println!("[WARNING] {}", message);
+    } else {
+        println!("{} {}", "[WARNING]".yellow(), message);
     }
 }
 
 /// # Log
 ///
-/// Prints a zinc standard log message.
+/// Prints a zinc standard log message, with or without `--verbose`.
 ///
 /// # Arguments
 ///
@@ -104,11 +100,9 @@ pub fn warn(message: &str, c_settings: &CSettings) {
 /// zlog::log("This is a log message", &c_settings);
 /// ```
 pub fn log(message: &str, c_settings: &CSettings) {
-    if c_settings.is_verbose {
-        if c_settings.is_no_color {
-            println!("[LOG] {}", message);
-        } else {
-            println!("{} {}", "[LOG]".green(), message);
-        }
+    if c_settings.is_no_color {
+        println!("[LOG] {}", message);
+    } else {
+        println!("{} {}", "[LOG]".green(), message);
     }
 }
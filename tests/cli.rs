@@ -0,0 +1,103 @@
+// Runs the `zinc` binary on small files and checks what it prints
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A directory under `target/` for one test's files, deleted when dropped. `zinc` resolves its
+/// input against the working directory, so the files have to live inside the package.
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(test: &str) -> Self {
+        let dir: PathBuf = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("cli-{}", test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TestDir(dir)
+    }
+
+    /// Writes `src` to `name` and returns its path relative to the package.
+    fn file(&self, name: &str, src: &str) -> PathBuf {
+        let path: PathBuf = self.0.join(name);
+        fs::write(&path, src).unwrap();
+        path.strip_prefix(env!("CARGO_MANIFEST_DIR"))
+            .unwrap()
+            .to_path_buf()
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs `zinc` from the package directory and returns everything it printed.
+fn zinc(args: &[&str], file: &Path) -> (Output, String) {
+    let output: Output = Command::new(env!("CARGO_BIN_EXE_zinc"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("--no-color")
+        .args(args)
+        .arg(file)
+        .output()
+        .unwrap();
+    let printed: String = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (output, printed)
+}
+
+#[test]
+fn errors_are_printed_without_verbose() {
+    let dir: TestDir = TestDir::new("errors");
+    let file: PathBuf = dir.file(
+        "lex.zc",
+        "fun main() {\n    x = `;\n    s: string = \"open\n}\n",
+    );
+    let (output, printed) = zinc(&[], &file);
+    assert!(!output.status.success());
+    assert!(printed.contains("[ERROR] [Line 2, Column 9] Unknown character '`'"));
+    assert!(printed.contains("[ERROR] [Line 3, Column 17] Unterminated string literal"));
+    assert!(!printed.contains("[VERBOSE]"));
+}
+
+#[test]
+fn lint_confusables_warns_when_enabled() {
+    let dir: TestDir = TestDir::new("confusables");
+    let file: PathBuf = dir.file("conf.zc", "fun main() {\n    p\u{430}ypal: i32 = 1;\n}\n");
+    let warning: &str = "[WARNING] [Line 2, Column 5] Identifier `p\u{430}ypal` mixes scripts and may be confused with another name";
+    let (output, printed) = zinc(&["--lint-confusables"], &file);
+    assert!(output.status.success());
+    assert!(printed.contains(warning));
+    let (output, printed) = zinc(&[], &file);
+    assert!(output.status.success());
+    assert!(!printed.contains("[WARNING]"));
+}
+
+#[test]
+fn run_unwinds_and_reports_uncaught_errors() {
+    let dir: TestDir = TestDir::new("run");
+    let file: PathBuf = dir.file(
+        "run.zc",
+        "dive { println } from std::io;\n\
+         enum IoError: Error { Closed }\n\
+         fun read(): i32 { throw IoError::Closed; }\n\
+         fun main() -> i32 {\n\
+             try { read(); } catch (e: IoError) { println(\"caught {}\", e); }\n\
+             finally { println(\"finally\"); }\n\
+             x: i32 = 0;\n\
+             println(\"{}\", 1 / x);\n\
+             return 0;\n\
+         }\n",
+    );
+    let (output, printed) = zinc(&["--run"], &file);
+    assert!(!output.status.success());
+    assert!(printed.contains("caught IoError::Closed\nfinally\n"));
+    assert!(printed.contains(
+        "[ERROR] [Line 8, Column 15] Uncaught error `Error::DivisionByZero` ended the program"
+    ));
+    let (output, printed) = zinc(&[], &file);
+    assert!(output.status.success());
+    assert!(!printed.contains("caught"));
+}